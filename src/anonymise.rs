@@ -0,0 +1,348 @@
+// Erasing what identifies a user, and exporting what we hold about them.
+//
+// Anonymising keeps the record, and with it the id, sign-in count, roles and lifecycle
+// history, so references by id still resolve and aggregates still add up. The username and
+// email become pseudonyms, the password hash and profile are dropped and the account is
+// closed. Pseudonyms are the id and an HMAC of it under a secret key, not anything the user
+// gave us, so nothing of the old values can be worked back from them, and no two ids share
+// one; with the same key every system holding the user picks the same pseudonyms.
+//
+// In an event store (user_events.rs) the erasure is itself an event, and the user's earlier
+// events are redacted to match, so their history can't be read back either. A `UserStore`
+// (user_store.rs) compacts its log right after, see `UserStore::anonymise`.
+use crate::clock::unix_secs;
+use crate::json::Json;
+use crate::lifecycle::{AccountState, LifecycleError, Transition};
+use crate::sha256::{hmac_sha256, to_hex};
+use crate::structs::{Profile, User};
+use crate::user_events::{RecordedEvent, UserEvent};
+use crate::user_repository::{RepositoryError, UserId, UserRepository};
+use std::fmt;
+use std::time::SystemTime;
+
+// a reserved top-level domain, mail to it can't go anywhere
+pub const PSEUDONYM_DOMAIN: &str = "anonymised.invalid";
+// what secrets in redacted events are replaced with
+pub const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnonymiseError {
+    Repository(RepositoryError),
+    Lifecycle(LifecycleError),
+}
+
+impl fmt::Display for AnonymiseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnonymiseError::Repository(e) => write!(f, "{}", e),
+            AnonymiseError::Lifecycle(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for AnonymiseError {}
+
+// (username, email), both valid and unique as long as ids are, since the id is in them; in hex
+// so the longest id still fits in a username
+pub fn pseudonyms(key: &[u8], id: UserId) -> (String, String) {
+    let mac = hmac_sha256(key, format!("user:{}", id).as_bytes());
+    let tag = format!("anon-{:x}-{}", id, to_hex(&mac[..4]));
+    let email = format!("{}@{}", tag, PSEUDONYM_DOMAIN);
+    (tag, email)
+}
+
+// Puts the pseudonyms in and drops the rest, closing the account unless it already is.
+pub fn erase(
+    user: &mut User,
+    username: &str,
+    email: &str,
+    reason: &str,
+    at: SystemTime,
+) -> Result<(), LifecycleError> {
+    if user.lifecycle.state() != AccountState::Deleted {
+        user.lifecycle.delete(reason, at)?;
+    }
+    user.username = username.to_string();
+    user.email = email.to_string();
+    user.password_hash = None;
+    user.profile = Profile::default();
+    Ok(())
+}
+
+// Anonymising twice is harmless, the second time changes nothing.
+pub fn anonymise(
+    repo: &mut UserRepository,
+    id: UserId,
+    key: &[u8],
+    reason: &str,
+    at: SystemTime,
+) -> Result<(), AnonymiseError> {
+    let mut user = repo
+        .get(id)
+        .cloned()
+        .ok_or(AnonymiseError::Repository(RepositoryError::NotFound(id)))?;
+    let (username, email) = pseudonyms(key, id);
+    erase(&mut user, &username, &email, reason, at).map_err(AnonymiseError::Lifecycle)?;
+    repo.update(id, user).map_err(AnonymiseError::Repository)
+}
+
+// Replaces the personal data in one of an anonymised user's old events.
+pub fn redact(event: &mut UserEvent, pseudonym: &str, pseudonym_email: &str) {
+    match event {
+        UserEvent::UserCreated { username, email } => {
+            *username = pseudonym.to_string();
+            *email = pseudonym_email.to_string();
+        }
+        UserEvent::UsernameChanged { username } => *username = pseudonym.to_string(),
+        UserEvent::EmailChanged { email } => *email = pseudonym_email.to_string(),
+        UserEvent::PasswordChanged { password_hash } => *password_hash = REDACTED.to_string(),
+        _ => {}
+    }
+}
+
+fn optional(value: &Option<String>) -> Json {
+    match value {
+        Some(value) => value.as_str().into(),
+        None => Json::Null,
+    }
+}
+
+fn member(name: &str, value: Json) -> (String, Json) {
+    (name.to_string(), value)
+}
+
+fn transition_json(transition: &Transition) -> Json {
+    Json::Object(vec![
+        member("at", unix_secs(transition.at).into()),
+        member("from", transition.from.name().into()),
+        member("to", transition.to.name().into()),
+        member("reason", transition.reason.as_str().into()),
+    ])
+}
+
+fn event_json(recorded: &RecordedEvent) -> Json {
+    let mut members = vec![
+        member("sequence", recorded.sequence.into()),
+        member("at", unix_secs(recorded.at).into()),
+        member("event", recorded.event.name().into()),
+    ];
+    match &recorded.event {
+        UserEvent::UserCreated { username, email }
+        | UserEvent::Anonymised {
+            username, email, ..
+        } => {
+            members.push(member("username", username.as_str().into()));
+            members.push(member("email", email.as_str().into()));
+        }
+        UserEvent::UsernameChanged { username } => {
+            members.push(member("username", username.as_str().into()))
+        }
+        UserEvent::EmailChanged { email } => members.push(member("email", email.as_str().into())),
+        UserEvent::RolesChanged { roles } => members.push(member(
+            "roles",
+            Json::Array(roles.iter().map(|r| r.as_str().into()).collect()),
+        )),
+        UserEvent::Suspended { until, .. } => members.push(member(
+            "until",
+            until
+                .map(|until| unix_secs(until).into())
+                .unwrap_or(Json::Null),
+        )),
+        _ => {}
+    }
+    match &recorded.event {
+        UserEvent::Activated { reason }
+        | UserEvent::Suspended { reason, .. }
+        | UserEvent::Deactivated { reason }
+        | UserEvent::Deleted { reason }
+        | UserEvent::Anonymised { reason, .. } => {
+            members.push(member("reason", reason.as_str().into()))
+        }
+        _ => {}
+    }
+    Json::Object(members)
+}
+
+// Everything held about a user, for a subject access request. Pass the user's events if they
+// are kept, e.g. `EventStore::history`. The password hash is left out: it is a secret of ours,
+// only whether there is one is the user's business.
+pub fn export_user(id: UserId, user: &User, events: &[&RecordedEvent]) -> Json {
+    let profile = &user.profile;
+    Json::Object(vec![
+        member("id", id.into()),
+        member("username", user.username.as_str().into()),
+        member("email", user.email.as_str().into()),
+        member("sign_in_count", user.sign_in_count.into()),
+        member("state", user.lifecycle.state().name().into()),
+        member("has_password", user.password_hash.is_some().into()),
+        member(
+            "roles",
+            Json::Array(user.roles.iter().map(|r| r.as_str().into()).collect()),
+        ),
+        member(
+            "profile",
+            Json::Object(vec![
+                member("display_name", optional(&profile.display_name)),
+                member("locale", optional(&profile.locale)),
+                member("timezone", optional(&profile.timezone)),
+                member("avatar_url", optional(&profile.avatar_url)),
+            ]),
+        ),
+        member(
+            "lifecycle",
+            Json::Array(
+                user.lifecycle
+                    .history()
+                    .iter()
+                    .map(transition_json)
+                    .collect(),
+            ),
+        ),
+        member(
+            "events",
+            Json::Array(events.iter().map(|recorded| event_json(recorded)).collect()),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::anonymise::{anonymise, export_user, pseudonyms};
+    use crate::clock::{Clock, ManualClock};
+    use crate::json::{self, Json};
+    use crate::lifecycle::AccountState;
+    use crate::structs::build_user;
+    use crate::user_events::{fold, EventStore, UserEvent};
+    use crate::user_repository::{RepositoryError, UserRepository};
+    use crate::user_validation::try_build_user;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const KEY: &[u8] = b"erasure key";
+
+    #[test]
+    fn test_pseudonyms() {
+        let (username, email) = pseudonyms(KEY, 7);
+        assert_eq!(pseudonyms(KEY, 7), (username.clone(), email.clone()));
+        assert_ne!(pseudonyms(KEY, 8).0, username);
+        assert_ne!(pseudonyms(b"other key", 7).0, username);
+        assert!(username.starts_with("anon-"));
+        assert!(email.ends_with("@anonymised.invalid"));
+        // they pass the same checks as any other user
+        try_build_user(email, username).unwrap();
+        let (longest, email) = pseudonyms(KEY, u64::MAX);
+        try_build_user(email, longest).unwrap();
+    }
+
+    #[test]
+    fn test_anonymise_in_repository() {
+        let mut repo = UserRepository::new();
+        let mut ray = build_user("ray@example.com".to_string(), "Ray".to_string());
+        ray.sign_in_count = 42;
+        ray.roles = vec!["editor".to_string()];
+        ray.password_hash = Some("$pbkdf2-sha256$i=1$c2FsdA$VawE".to_string());
+        ray.profile.display_name = Some("Ray Young".to_string());
+        let id = repo.create(ray).unwrap();
+        let sam = repo
+            .create(build_user("sam@example.com".to_string(), "Sam".to_string()))
+            .unwrap();
+
+        let at = UNIX_EPOCH + Duration::from_secs(1_000);
+        anonymise(&mut repo, id, KEY, "erasure request", at).unwrap();
+        let erased = repo.get(id).unwrap().clone();
+        assert_eq!(erased.username, pseudonyms(KEY, id).0);
+        assert_eq!(erased.email, pseudonyms(KEY, id).1);
+        assert_eq!(erased.password_hash, None);
+        assert_eq!(erased.profile.display_name, None);
+        // what isn't personal stays
+        assert_eq!(erased.sign_in_count, 42);
+        assert_eq!(erased.roles, ["editor"]);
+        assert_eq!(erased.lifecycle.state(), AccountState::Deleted);
+        assert_eq!(erased.lifecycle.history()[0].reason, "erasure request");
+        // the old name and email are free, the other user untouched
+        assert!(repo.find_by_email("ray@example.com").is_none());
+        assert_eq!(repo.get(sam).unwrap().username, "Sam");
+
+        anonymise(&mut repo, id, KEY, "again", at).unwrap();
+        assert_eq!(repo.get(id).unwrap(), &erased);
+        assert!(matches!(
+            anonymise(&mut repo, 99, KEY, "", at),
+            Err(crate::anonymise::AnonymiseError::Repository(
+                RepositoryError::NotFound(99)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_anonymise_redacts_history() {
+        let clock = ManualClock::at_unix(1_000_000);
+        let mut store = EventStore::new(&clock);
+        let ray = store.create_user("Ray", "ray@example.com").unwrap();
+        let sam = store.create_user("Sam", "sam@example.com").unwrap();
+        store
+            .record(
+                ray,
+                UserEvent::PasswordChanged {
+                    password_hash: "$pbkdf2-sha256$i=1$c2FsdA$VawE".to_string(),
+                },
+            )
+            .unwrap();
+        store
+            .record(
+                ray,
+                UserEvent::Activated {
+                    reason: "email verified".to_string(),
+                },
+            )
+            .unwrap();
+        let before = clock.now();
+        store.record(ray, UserEvent::SignedIn).unwrap();
+        store
+            .record(
+                ray,
+                UserEvent::EmailChanged {
+                    email: "ray@new.example.com".to_string(),
+                },
+            )
+            .unwrap();
+
+        let exported = export_user(ray, store.current(ray).unwrap(), &store.history(ray));
+        assert_eq!(
+            exported.get("email").and_then(Json::as_str),
+            Some("ray@new.example.com")
+        );
+        assert_eq!(exported.get("has_password"), Some(&Json::Bool(true)));
+        assert_eq!(
+            exported
+                .get("events")
+                .and_then(Json::as_array)
+                .unwrap()
+                .len(),
+            5
+        );
+        assert!(!exported.to_string().contains("pbkdf2"));
+
+        clock.advance(Duration::from_secs(60));
+        let erased = store
+            .anonymise(ray, KEY, "erasure request")
+            .unwrap()
+            .clone();
+        assert_eq!(erased.sign_in_count, 2);
+        assert_eq!(erased.lifecycle.state(), AccountState::Deleted);
+        // the audit trail says what happened, and nothing of what was erased
+        let history = store.history(ray);
+        assert_eq!(history.last().unwrap().event.name(), "anonymised");
+        let log = format!("{:?}", store.history(ray));
+        for secret in ["Ray", "ray@example.com", "ray@new.example.com", "pbkdf2"].iter() {
+            assert!(!log.contains(secret), "{} is still in the log", secret);
+        }
+        let exported = export_user(ray, &erased, &history).to_string();
+        assert!(!exported.contains("example.com"));
+        assert!(json::parse(&exported).is_ok());
+        // even looking back, only the pseudonyms are there
+        assert_eq!(store.as_of(ray, before).unwrap().username, erased.username);
+        // and replaying the log still gives the same users
+        let users = fold(store.events()).unwrap();
+        assert_eq!(&users[&ray], store.current(ray).unwrap());
+        assert_eq!(store.current(sam).unwrap().email, "sam@example.com");
+    }
+}
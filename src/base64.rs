@@ -0,0 +1,108 @@
+// Base64 (RFC 4648) in the two alphabets we need, always without `=` padding:
+// the standard one for PHC password strings and the URL-safe one for tokens.
+const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn encode_with(data: &[u8], alphabet: &[u8; 64]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        // 1 byte makes 2 characters, 2 make 3, 3 make 4
+        for i in 0..=chunk.len() {
+            out.push(alphabet[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+fn decode_with(text: &str, alphabet: &[u8; 64]) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    // a single leftover character can't hold a whole byte
+    if text.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = alphabet.iter().position(|&a| a == c)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+        let bytes = n.to_be_bytes();
+        out.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+    Some(out)
+}
+
+pub fn encode(data: &[u8]) -> String {
+    encode_with(data, STANDARD)
+}
+
+pub fn decode(text: &str) -> Option<Vec<u8>> {
+    decode_with(text, STANDARD)
+}
+
+pub fn encode_url_safe(data: &[u8]) -> String {
+    encode_with(data, URL_SAFE)
+}
+
+pub fn decode_url_safe(text: &str) -> Option<Vec<u8>> {
+    decode_with(text, URL_SAFE)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base64::{decode, decode_url_safe, encode, encode_url_safe};
+
+    #[test]
+    fn test_rfc4648_vectors() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg"),
+            ("fo", "Zm8"),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg"),
+            ("fooba", "Zm9vYmE"),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for &(plain, encoded) in cases.iter() {
+            assert_eq!(encode(plain.as_bytes()), encoded);
+            assert_eq!(decode(encoded).unwrap(), plain.as_bytes());
+        }
+        // padding is accepted on the way in
+        assert_eq!(decode("Zm8=").unwrap(), b"fo");
+    }
+
+    #[test]
+    fn test_url_safe_alphabet() {
+        let data = [0xfb, 0xff, 0xbf];
+        assert_eq!(encode(&data), "+/+/");
+        assert_eq!(encode_url_safe(&data), "-_-_");
+        assert_eq!(decode_url_safe("-_-_").unwrap(), data);
+        assert_eq!(decode_url_safe("+/+/"), None);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert_eq!(decode("Z"), None);
+        assert_eq!(decode("Zm9v!"), None);
+        assert_eq!(decode("Zm 9v"), None);
+    }
+
+    #[test]
+    fn test_round_trip_all_lengths() {
+        let data: Vec<u8> = (0..=255).collect();
+        for len in 0..data.len() {
+            assert_eq!(decode(&encode(&data[..len])).unwrap(), &data[..len]);
+            assert_eq!(
+                decode_url_safe(&encode_url_safe(&data[..len])).unwrap(),
+                &data[..len]
+            );
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// Where "now" comes from. Code that depends on time takes a `Clock` so tests can move it by hand.
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+// the real wall clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+// a clock that only moves when told to
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<SystemTime>,
+}
+
+impl ManualClock {
+    pub fn new(start: SystemTime) -> ManualClock {
+        ManualClock {
+            now: Mutex::new(start),
+        }
+    }
+
+    pub fn at_unix(secs: u64) -> ManualClock {
+        ManualClock::new(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now += by;
+    }
+
+    pub fn set(&self, to: SystemTime) {
+        *self.now.lock().unwrap() = to;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap()
+    }
+}
+
+// so a test can keep a handle on the clock it gave away
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Rc<C> {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+// seconds since the epoch, times before 1970 count as 0
+pub fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::{unix_secs, Clock, ManualClock, SystemClock};
+    use std::sync::Arc;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::at_unix(1_000);
+        assert_eq!(unix_secs(clock.now()), 1_000);
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(unix_secs(clock.now()), 1_030);
+        clock.set(UNIX_EPOCH);
+        assert_eq!(unix_secs(clock.now()), 0);
+    }
+
+    #[test]
+    fn test_shared_handles() {
+        let clock = Arc::new(ManualClock::at_unix(5));
+        let borrowed = &*clock;
+        let shared = Arc::clone(&clock);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(unix_secs(borrowed.now()), 6);
+        assert_eq!(unix_secs(shared.now()), 6);
+    }
+
+    #[test]
+    fn test_system_clock_is_after_2020() {
+        assert!(unix_secs(SystemClock.now()) > 1_577_836_800);
+    }
+}
@@ -0,0 +1,388 @@
+// Colour-vision-deficiency simulation with the Machado, Oliveira and Fernandes (2009) model.
+// The matrices operate on linear RGB, so colours are decoded before and re-encoded after.
+use crate::colors::{linear_to_srgb, srgb_to_linear};
+use crate::structs::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Deficiency {
+    // missing or anomalous L (red) cones
+    Protan,
+    // missing or anomalous M (green) cones
+    Deutan,
+    // missing or anomalous S (blue) cones
+    Tritan,
+}
+
+type Matrix = [[f64; 3]; 3];
+
+const IDENTITY: Matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+// The published matrices for severities 0.1 to 1.0 in steps of 0.1, severity 0.0 being the
+// identity. The last of each is protanopia, deuteranopia and tritanopia.
+const PROTAN: [Matrix; 10] = [
+    [
+        [0.856_167, 0.182_038, -0.038_205],
+        [0.029_342, 0.955_115, 0.015_544],
+        [-0.002_880, -0.001_563, 1.004_443],
+    ],
+    [
+        [0.734_766, 0.334_872, -0.069_637],
+        [0.051_840, 0.919_198, 0.028_963],
+        [-0.004_928, -0.004_209, 1.009_137],
+    ],
+    [
+        [0.630_323, 0.465_641, -0.095_964],
+        [0.069_181, 0.890_046, 0.040_773],
+        [-0.006_308, -0.007_724, 1.014_032],
+    ],
+    [
+        [0.539_009, 0.579_343, -0.118_352],
+        [0.082_546, 0.866_121, 0.051_332],
+        [-0.007_136, -0.011_959, 1.019_095],
+    ],
+    [
+        [0.458_064, 0.679_578, -0.137_642],
+        [0.092_785, 0.846_313, 0.060_902],
+        [-0.007_494, -0.016_807, 1.024_301],
+    ],
+    [
+        [0.385_450, 0.769_005, -0.154_455],
+        [0.100_526, 0.829_802, 0.069_673],
+        [-0.007_442, -0.022_190, 1.029_632],
+    ],
+    [
+        [0.319_627, 0.849_633, -0.169_261],
+        [0.106_241, 0.815_969, 0.077_790],
+        [-0.007_025, -0.028_051, 1.035_076],
+    ],
+    [
+        [0.259_411, 0.923_008, -0.182_420],
+        [0.110_296, 0.804_340, 0.085_364],
+        [-0.006_276, -0.034_346, 1.040_622],
+    ],
+    [
+        [0.203_876, 0.990_338, -0.194_214],
+        [0.112_975, 0.794_542, 0.092_483],
+        [-0.005_222, -0.041_043, 1.046_265],
+    ],
+    [
+        [0.152_286, 1.052_583, -0.204_868],
+        [0.114_503, 0.786_281, 0.099_216],
+        [-0.003_882, -0.048_116, 1.051_998],
+    ],
+];
+const DEUTAN: [Matrix; 10] = [
+    [
+        [0.866_435, 0.177_704, -0.044_139],
+        [0.049_567, 0.939_063, 0.011_370],
+        [-0.003_453, 0.007_233, 0.996_220],
+    ],
+    [
+        [0.760_729, 0.319_078, -0.079_807],
+        [0.090_568, 0.889_315, 0.020_117],
+        [-0.006_027, 0.013_325, 0.992_702],
+    ],
+    [
+        [0.675_425, 0.433_850, -0.109_275],
+        [0.125_303, 0.847_755, 0.026_942],
+        [-0.007_950, 0.018_572, 0.989_378],
+    ],
+    [
+        [0.605_511, 0.528_560, -0.134_071],
+        [0.155_318, 0.812_366, 0.032_316],
+        [-0.009_376, 0.023_176, 0.986_200],
+    ],
+    [
+        [0.547_494, 0.607_765, -0.155_259],
+        [0.181_692, 0.781_742, 0.036_566],
+        [-0.010_410, 0.027_275, 0.983_136],
+    ],
+    [
+        [0.498_864, 0.674_741, -0.173_604],
+        [0.205_199, 0.754_872, 0.039_929],
+        [-0.011_131, 0.030_969, 0.980_162],
+    ],
+    [
+        [0.457_771, 0.731_899, -0.189_670],
+        [0.226_409, 0.731_012, 0.042_579],
+        [-0.011_595, 0.034_333, 0.977_261],
+    ],
+    [
+        [0.422_823, 0.781_057, -0.203_881],
+        [0.245_752, 0.709_602, 0.044_646],
+        [-0.011_843, 0.037_423, 0.974_421],
+    ],
+    [
+        [0.392_952, 0.823_610, -0.216_562],
+        [0.263_559, 0.690_210, 0.046_232],
+        [-0.011_910, 0.040_281, 0.971_630],
+    ],
+    [
+        [0.367_322, 0.860_646, -0.227_968],
+        [0.280_085, 0.672_501, 0.047_413],
+        [-0.011_820, 0.042_940, 0.968_881],
+    ],
+];
+const TRITAN: [Matrix; 10] = [
+    [
+        [0.926_670, 0.092_514, -0.019_184],
+        [0.021_191, 0.964_503, 0.014_306],
+        [0.008_437, 0.054_813, 0.936_750],
+    ],
+    [
+        [0.895_720, 0.133_330, -0.029_050],
+        [0.029_997, 0.945_400, 0.024_603],
+        [0.013_027, 0.104_707, 0.882_266],
+    ],
+    [
+        [0.905_871, 0.127_791, -0.033_662],
+        [0.026_856, 0.941_251, 0.031_893],
+        [0.013_410, 0.148_296, 0.838_294],
+    ],
+    [
+        [0.948_035, 0.089_490, -0.037_526],
+        [0.014_364, 0.946_792, 0.038_844],
+        [0.010_853, 0.193_991, 0.795_156],
+    ],
+    [
+        [1.017_277, 0.027_029, -0.044_306],
+        [-0.006_113, 0.958_479, 0.047_634],
+        [0.006_379, 0.248_708, 0.744_913],
+    ],
+    [
+        [1.104_996, -0.046_633, -0.058_363],
+        [-0.032_137, 0.971_635, 0.060_503],
+        [0.001_336, 0.317_922, 0.680_742],
+    ],
+    [
+        [1.193_214, -0.109_812, -0.083_402],
+        [-0.058_496, 0.979_410, 0.079_086],
+        [-0.002_346, 0.403_492, 0.598_854],
+    ],
+    [
+        [1.257_728, -0.139_648, -0.118_081],
+        [-0.078_003, 0.975_409, 0.102_594],
+        [-0.003_316, 0.501_214, 0.502_102],
+    ],
+    [
+        [1.278_864, -0.125_333, -0.153_531],
+        [-0.084_748, 0.957_674, 0.127_074],
+        [-0.000_989, 0.601_151, 0.399_838],
+    ],
+    [
+        [1.255_528, -0.076_749, -0.178_779],
+        [-0.078_411, 0.930_809, 0.147_602],
+        [0.004_733, 0.691_367, 0.303_900],
+    ],
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    matrix: Matrix,
+    // 8-bit sRGB channel to linear light, so buffers don't pay for powf per pixel
+    decode: Vec<f64>,
+}
+
+impl Simulation {
+    // Severity runs from 0.0 (normal vision) to 1.0 (dichromacy), anything between is the
+    // anomalous trichromacy, e.g. Protan at 0.6 is a fairly strong protanomaly.
+    // Severities between two published steps interpolate between their matrices.
+    pub fn new(deficiency: Deficiency, severity: f64) -> Simulation {
+        let severity = if severity.is_nan() {
+            0.0
+        } else {
+            severity.clamp(0.0, 1.0)
+        };
+        let steps = match deficiency {
+            Deficiency::Protan => &PROTAN,
+            Deficiency::Deutan => &DEUTAN,
+            Deficiency::Tritan => &TRITAN,
+        };
+        let step = |i: usize| if i == 0 { IDENTITY } else { steps[i - 1] };
+        let scaled = severity * steps.len() as f64;
+        let below = (scaled.floor() as usize).min(steps.len() - 1);
+        let fraction = scaled - below as f64;
+        let mut matrix = step(below);
+        for (row, next_row) in matrix.iter_mut().zip(step(below + 1).iter()) {
+            for (cell, &next) in row.iter_mut().zip(next_row.iter()) {
+                *cell += (next - *cell) * fraction;
+            }
+        }
+        Simulation {
+            matrix,
+            decode: (0..256).map(|c| srgb_to_linear(c as f64 / 255.0)).collect(),
+        }
+    }
+
+    // complete absence of one cone type
+    pub fn dichromacy(deficiency: Deficiency) -> Simulation {
+        Simulation::new(deficiency, 1.0)
+    }
+
+    fn transform(&self, rgb: [f64; 3]) -> [u8; 3] {
+        let mut out = [0u8; 3];
+        for (channel, row) in out.iter_mut().zip(self.matrix.iter()) {
+            let linear = row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2];
+            *channel = (linear_to_srgb(linear.clamp(0.0, 1.0)) * 255.0).round() as u8;
+        }
+        out
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        let [r, g, b] = self.transform(color.to_linear());
+        Color(r as i32, g as i32, b as i32)
+    }
+
+    // Simulates an interleaved 8-bit buffer in place, `channels` is 3 for RGB or 4 for RGBA.
+    // Alpha is left untouched, a trailing partial pixel is ignored.
+    pub fn apply_rgb8(&self, pixels: &mut [u8], channels: usize) {
+        assert!(
+            channels == 3 || channels == 4,
+            "expected 3 (RGB) or 4 (RGBA) channels, got {}",
+            channels
+        );
+        for pixel in pixels.chunks_exact_mut(channels) {
+            let rgb = [
+                self.decode[pixel[0] as usize],
+                self.decode[pixel[1] as usize],
+                self.decode[pixel[2] as usize],
+            ];
+            pixel[..3].copy_from_slice(&self.transform(rgb));
+        }
+    }
+}
+
+// one-off convenience, build a `Simulation` when converting many colours
+pub fn simulate(color: Color, deficiency: Deficiency, severity: f64) -> Color {
+    Simulation::new(deficiency, severity).apply(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color_blindness::{simulate, Deficiency, Simulation, DEUTAN, PROTAN, TRITAN};
+    use crate::structs::Color;
+
+    const ALL: [Deficiency; 3] = [Deficiency::Protan, Deficiency::Deutan, Deficiency::Tritan];
+
+    fn oklab_distance(a: Color, b: Color) -> f64 {
+        let (a, b) = (a.to_oklab(), b.to_oklab());
+        ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+    }
+
+    #[test]
+    fn test_neutral_colours_are_preserved() {
+        // every matrix row sums to 1, so greys stay grey
+        for &deficiency in ALL.iter() {
+            for &c in [0, 64, 128, 255].iter() {
+                let grey = Color(c, c, c);
+                assert_eq!(simulate(grey, deficiency, 1.0), grey);
+            }
+        }
+    }
+
+    #[test]
+    fn test_zero_severity_is_identity() {
+        let color = Color(200, 30, 90);
+        for &deficiency in ALL.iter() {
+            assert_eq!(simulate(color, deficiency, 0.0), color);
+            assert_eq!(simulate(color, deficiency, -1.0), color);
+            assert_eq!(simulate(color, deficiency, f64::NAN), color);
+        }
+    }
+
+    #[test]
+    fn test_published_steps() {
+        let close = |sim: &Simulation, expected: &[[f64; 3]; 3]| {
+            sim.matrix
+                .iter()
+                .flatten()
+                .zip(expected.iter().flatten())
+                .all(|(a, b)| (a - b).abs() < 1e-9)
+        };
+        for &(deficiency, steps) in [
+            (Deficiency::Protan, &PROTAN),
+            (Deficiency::Deutan, &DEUTAN),
+            (Deficiency::Tritan, &TRITAN),
+        ]
+        .iter()
+        {
+            for (i, matrix) in steps.iter().enumerate() {
+                let severity = (i + 1) as f64 / 10.0;
+                assert!(close(&Simulation::new(deficiency, severity), matrix));
+            }
+            // halfway between two steps is halfway between their matrices
+            let mut middle = steps[4];
+            for (row, next) in middle.iter_mut().zip(steps[5].iter()) {
+                for (cell, &next) in row.iter_mut().zip(next.iter()) {
+                    *cell = (*cell + next) / 2.0;
+                }
+            }
+            assert!(close(&Simulation::new(deficiency, 0.55), &middle));
+        }
+    }
+
+    #[test]
+    fn test_red_green_confusion() {
+        let red = Color(180, 60, 60);
+        let green = Color(90, 110, 60);
+        let normal = oklab_distance(red, green);
+        for &deficiency in [Deficiency::Protan, Deficiency::Deutan].iter() {
+            let sim = Simulation::dichromacy(deficiency);
+            assert!(oklab_distance(sim.apply(red), sim.apply(green)) < normal / 2.0);
+        }
+        // tritanopes tell red from green just fine
+        let sim = Simulation::dichromacy(Deficiency::Tritan);
+        assert!(oklab_distance(sim.apply(red), sim.apply(green)) > normal / 2.0);
+    }
+
+    #[test]
+    fn test_blue_yellow_confusion_for_tritans() {
+        let blue = Color(60, 60, 230);
+        let teal = Color(20, 140, 140);
+        let sim = Simulation::dichromacy(Deficiency::Tritan);
+        assert!(oklab_distance(sim.apply(blue), sim.apply(teal)) < oklab_distance(blue, teal));
+    }
+
+    #[test]
+    fn test_severity_is_monotonic() {
+        let red = Color(255, 0, 0);
+        let mut last = 0.0;
+        for step in 1..=10 {
+            let simulated = simulate(red, Deficiency::Protan, step as f64 / 10.0);
+            let distance = oklab_distance(red, simulated);
+            assert!(distance >= last);
+            last = distance;
+        }
+    }
+
+    #[test]
+    fn test_buffers_match_single_colours() {
+        let sim = Simulation::new(Deficiency::Deutan, 0.7);
+        let colors = [Color(255, 0, 0), Color(0, 255, 0), Color(12, 34, 56)];
+        let mut rgb: Vec<u8> = Vec::new();
+        let mut rgba: Vec<u8> = Vec::new();
+        for c in colors.iter() {
+            rgb.extend_from_slice(&[c.0 as u8, c.1 as u8, c.2 as u8]);
+            rgba.extend_from_slice(&[c.0 as u8, c.1 as u8, c.2 as u8, 77]);
+        }
+        sim.apply_rgb8(&mut rgb, 3);
+        sim.apply_rgb8(&mut rgba, 4);
+        for (i, &c) in colors.iter().enumerate() {
+            let expected = sim.apply(c);
+            let got = Color(
+                rgb[i * 3] as i32,
+                rgb[i * 3 + 1] as i32,
+                rgb[i * 3 + 2] as i32,
+            );
+            assert_eq!(got, expected);
+            assert_eq!(rgba[i * 4..i * 4 + 3], rgb[i * 3..i * 3 + 3]);
+            assert_eq!(rgba[i * 4 + 3], 77);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 3 (RGB) or 4 (RGBA) channels")]
+    fn test_buffer_channel_count() {
+        Simulation::dichromacy(Deficiency::Protan).apply_rgb8(&mut [0; 8], 2);
+    }
+}
@@ -0,0 +1,293 @@
+// Conversions between `Color` (8-bit sRGB channels) and the colour spaces we interpolate in.
+// All float representations use channels in 0.0..=1.0, except hue which is in degrees.
+use crate::structs::Color;
+
+// D65 reference white, used by XYZ and Lab
+const WHITE_X: f64 = 0.950_47;
+const WHITE_Y: f64 = 1.0;
+const WHITE_Z: f64 = 1.088_83;
+
+// sRGB transfer function: gamma-encoded value to linear light
+pub fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// inverse of `srgb_to_linear`
+pub fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn to_channel(c: f64) -> i32 {
+    (c.clamp(0.0, 1.0) * 255.0).round() as i32
+}
+
+fn from_channel(c: i32) -> f64 {
+    c.clamp(0, 255) as f64 / 255.0
+}
+
+fn lab_f(t: f64) -> f64 {
+    let delta: f64 = 6.0 / 29.0;
+    if t > delta.powi(3) {
+        t.cbrt()
+    } else {
+        t / (3.0 * delta * delta) + 4.0 / 29.0
+    }
+}
+
+fn lab_f_inv(t: f64) -> f64 {
+    let delta: f64 = 6.0 / 29.0;
+    if t > delta {
+        t.powi(3)
+    } else {
+        3.0 * delta * delta * (t - 4.0 / 29.0)
+    }
+}
+
+// the methods live here rather than in structs.rs, an impl block can be in any module of the crate
+impl Color {
+    pub fn to_srgb(self) -> [f64; 3] {
+        [
+            from_channel(self.0),
+            from_channel(self.1),
+            from_channel(self.2),
+        ]
+    }
+
+    // out-of-gamut values are clamped, then rounded to the nearest 8-bit channel
+    pub fn from_srgb(rgb: [f64; 3]) -> Color {
+        Color(to_channel(rgb[0]), to_channel(rgb[1]), to_channel(rgb[2]))
+    }
+
+    pub fn to_linear(self) -> [f64; 3] {
+        let [r, g, b] = self.to_srgb();
+        [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b)]
+    }
+
+    pub fn from_linear(rgb: [f64; 3]) -> Color {
+        Color::from_srgb([
+            linear_to_srgb(rgb[0]),
+            linear_to_srgb(rgb[1]),
+            linear_to_srgb(rgb[2]),
+        ])
+    }
+
+    // accepts "#rrggbb", "rrggbb" and the short "#rgb" form
+    pub fn from_hex(hex: &str) -> Option<Color> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digits = match digits.len() {
+            3 => digits.chars().flat_map(|c| vec![c, c]).collect(),
+            6 => digits.to_string(),
+            _ => return None,
+        };
+        let channel = |i: usize| i32::from_str_radix(&digits[i..i + 2], 16).unwrap_or(0);
+        Some(Color(channel(0), channel(2), channel(4)))
+    }
+
+    pub fn to_hex(self) -> String {
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            self.0.clamp(0, 255),
+            self.1.clamp(0, 255),
+            self.2.clamp(0, 255)
+        )
+    }
+
+    // [hue in degrees 0..360, saturation, lightness]
+    pub fn to_hsl(self) -> [f64; 3] {
+        let [r, g, b] = self.to_srgb();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let l = (max + min) / 2.0;
+        let d = max - min;
+        if d == 0.0 {
+            return [0.0, 0.0, l];
+        }
+        let s = d / (1.0 - (2.0 * l - 1.0).abs());
+        let h = if max == r {
+            60.0 * ((g - b) / d).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / d + 2.0)
+        } else {
+            60.0 * ((r - g) / d + 4.0)
+        };
+        [h, s, l]
+    }
+
+    pub fn from_hsl(hsl: [f64; 3]) -> Color {
+        let [h, s, l] = hsl;
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let h = h.rem_euclid(360.0) / 60.0;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = l - c / 2.0;
+        Color::from_srgb([r + m, g + m, b + m])
+    }
+
+    // CIE XYZ relative to D65, Y of white is 1.0
+    pub fn to_xyz(self) -> [f64; 3] {
+        let [r, g, b] = self.to_linear();
+        [
+            0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b,
+            0.212_672_9 * r + 0.715_152_2 * g + 0.072_175_0 * b,
+            0.019_333_9 * r + 0.119_192_0 * g + 0.950_304_1 * b,
+        ]
+    }
+
+    pub fn from_xyz(xyz: [f64; 3]) -> Color {
+        let [x, y, z] = xyz;
+        Color::from_linear([
+            3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z,
+            -0.969_266_0 * x + 1.876_010_8 * y + 0.041_556_0 * z,
+            0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z,
+        ])
+    }
+
+    // CIELAB: [L in 0..100, a, b]
+    pub fn to_lab(self) -> [f64; 3] {
+        let [x, y, z] = self.to_xyz();
+        let fx = lab_f(x / WHITE_X);
+        let fy = lab_f(y / WHITE_Y);
+        let fz = lab_f(z / WHITE_Z);
+        [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+    }
+
+    pub fn from_lab(lab: [f64; 3]) -> Color {
+        let fy = (lab[0] + 16.0) / 116.0;
+        let fx = fy + lab[1] / 500.0;
+        let fz = fy - lab[2] / 200.0;
+        Color::from_xyz([
+            WHITE_X * lab_f_inv(fx),
+            WHITE_Y * lab_f_inv(fy),
+            WHITE_Z * lab_f_inv(fz),
+        ])
+    }
+
+    // Björn Ottosson's OKLab: [L in 0..1, a, b]
+    pub fn to_oklab(self) -> [f64; 3] {
+        let [r, g, b] = self.to_linear();
+        let l = (0.412_221_470_8 * r + 0.536_332_536_3 * g + 0.051_445_992_9 * b).cbrt();
+        let m = (0.211_903_498_2 * r + 0.680_699_545_1 * g + 0.107_396_956_6 * b).cbrt();
+        let s = (0.088_302_461_9 * r + 0.281_718_837_6 * g + 0.629_978_700_5 * b).cbrt();
+        [
+            0.210_454_255_3 * l + 0.793_617_785_0 * m - 0.004_072_046_8 * s,
+            1.977_998_495_1 * l - 2.428_592_205_0 * m + 0.450_593_709_9 * s,
+            0.025_904_037_1 * l + 0.782_771_766_2 * m - 0.808_675_766_0 * s,
+        ]
+    }
+
+    pub fn from_oklab(lab: [f64; 3]) -> Color {
+        let [l, a, b] = lab;
+        let l_ = (l + 0.396_337_777_4 * a + 0.215_803_757_3 * b).powi(3);
+        let m_ = (l - 0.105_561_345_8 * a - 0.063_854_172_8 * b).powi(3);
+        let s_ = (l - 0.089_484_177_5 * a - 1.291_485_548_0 * b).powi(3);
+        Color::from_linear([
+            4.076_741_662_1 * l_ - 3.307_711_591_3 * m_ + 0.230_969_929_2 * s_,
+            -1.268_438_004_6 * l_ + 2.609_757_401_1 * m_ - 0.341_319_396_5 * s_,
+            -0.004_196_086_3 * l_ - 0.703_418_614_7 * m_ + 1.707_614_701_0 * s_,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::colors::{linear_to_srgb, srgb_to_linear};
+    use crate::structs::Color;
+
+    const SAMPLES: [Color; 6] = [
+        Color(0, 0, 0),
+        Color(255, 255, 255),
+        Color(255, 0, 0),
+        Color(18, 52, 86),
+        Color(200, 150, 100),
+        Color(128, 128, 128),
+    ];
+
+    fn close(a: f64, b: f64, eps: f64) -> bool {
+        (a - b).abs() < eps
+    }
+
+    #[test]
+    fn test_transfer_function_round_trip() {
+        for i in 0..=100 {
+            let c = i as f64 / 100.0;
+            assert!(close(linear_to_srgb(srgb_to_linear(c)), c, 1e-9));
+        }
+        // mid grey in sRGB is much darker in linear light
+        assert!(close(srgb_to_linear(0.5), 0.214, 1e-3));
+    }
+
+    #[test]
+    fn test_out_of_range_channels_are_clamped() {
+        assert_eq!(Color::from_srgb([1.5, -0.2, 0.5]), Color(255, 0, 128));
+        assert_eq!(Color(300, -5, 0).to_srgb(), [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(Color::from_hex("#1a2B3c"), Some(Color(26, 43, 60)));
+        assert_eq!(Color::from_hex("ffffff"), Some(Color(255, 255, 255)));
+        assert_eq!(Color::from_hex("#f80"), Some(Color(255, 136, 0)));
+        assert_eq!(Color::from_hex("#12345"), None);
+        assert_eq!(Color::from_hex("#gggggg"), None);
+        assert_eq!(Color::from_hex("#ééé"), None);
+        assert_eq!(Color::from_hex("+1+2+3"), None);
+        assert_eq!(Color(26, 43, 60).to_hex(), "#1a2b3c");
+    }
+
+    #[test]
+    fn test_hsl() {
+        assert_eq!(Color(255, 0, 0).to_hsl(), [0.0, 1.0, 0.5]);
+        let [h, s, l] = Color(0, 0, 255).to_hsl();
+        assert_eq!((h, s, l), (240.0, 1.0, 0.5));
+        assert_eq!(Color::from_hsl([120.0, 1.0, 0.5]), Color(0, 255, 0));
+        // hue wraps around
+        assert_eq!(Color::from_hsl([480.0, 1.0, 0.5]), Color(0, 255, 0));
+    }
+
+    #[test]
+    fn test_lab_reference_values() {
+        let [l, a, b] = Color(255, 255, 255).to_lab();
+        assert!(close(l, 100.0, 1e-2) && close(a, 0.0, 1e-2) && close(b, 0.0, 1e-2));
+        // published value for pure red is roughly L=53.24 a=80.09 b=67.20
+        let [l, a, b] = Color(255, 0, 0).to_lab();
+        assert!(close(l, 53.24, 0.05) && close(a, 80.09, 0.05) && close(b, 67.20, 0.05));
+    }
+
+    #[test]
+    fn test_oklab_reference_values() {
+        let [l, a, b] = Color(255, 255, 255).to_oklab();
+        assert!(close(l, 1.0, 1e-4) && close(a, 0.0, 1e-4) && close(b, 0.0, 1e-4));
+        let [l, a, b] = Color(255, 0, 0).to_oklab();
+        assert!(close(l, 0.628, 1e-3) && close(a, 0.225, 1e-3) && close(b, 0.126, 1e-3));
+    }
+
+    #[test]
+    fn test_round_trips() {
+        for &c in SAMPLES.iter() {
+            assert_eq!(Color::from_srgb(c.to_srgb()), c);
+            assert_eq!(Color::from_linear(c.to_linear()), c);
+            assert_eq!(Color::from_hsl(c.to_hsl()), c);
+            assert_eq!(Color::from_xyz(c.to_xyz()), c);
+            assert_eq!(Color::from_lab(c.to_lab()), c);
+            assert_eq!(Color::from_oklab(c.to_oklab()), c);
+        }
+    }
+}
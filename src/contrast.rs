@@ -0,0 +1,283 @@
+// WCAG 2.x relative luminance and contrast ratio for `Color` pairs.
+use crate::colors::srgb_to_linear;
+use crate::structs::Color;
+use std::fmt;
+
+// minimum ratios from WCAG 2.x success criteria 1.4.3 (AA) and 1.4.6 (AAA),
+// "large" text is at least 18pt, or 14pt bold
+pub const AA_NORMAL: f64 = 4.5;
+pub const AA_LARGE: f64 = 3.0;
+pub const AAA_NORMAL: f64 = 7.0;
+pub const AAA_LARGE: f64 = 4.5;
+
+// 0.0 for black up to 1.0 for white
+pub fn relative_luminance(color: Color) -> f64 {
+    let [r, g, b] = color.to_srgb();
+    0.2126 * srgb_to_linear(r) + 0.7152 * srgb_to_linear(g) + 0.0722 * srgb_to_linear(b)
+}
+
+// from 1.0 (no contrast) to 21.0 (black on white), the order of the arguments does not matter
+pub fn contrast_ratio(a: Color, b: Color) -> f64 {
+    let la = relative_luminance(a);
+    let lb = relative_luminance(b);
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContrastReport {
+    pub ratio: f64,
+    pub aa_normal: bool,
+    pub aa_large: bool,
+    pub aaa_normal: bool,
+    pub aaa_large: bool,
+}
+
+pub fn check(foreground: Color, background: Color) -> ContrastReport {
+    let ratio = contrast_ratio(foreground, background);
+    ContrastReport {
+        ratio,
+        aa_normal: ratio >= AA_NORMAL,
+        aa_large: ratio >= AA_LARGE,
+        aaa_normal: ratio >= AAA_NORMAL,
+        aaa_large: ratio >= AAA_LARGE,
+    }
+}
+
+// The closest colour to `foreground` (same hue and saturation, lightness moved as little as
+// possible in either direction) that reaches `target` against `background`.
+// Returns None when no lightness can reach the target, e.g. anything above 21:1.
+pub fn suggest_accessible(foreground: Color, background: Color, target: f64) -> Option<Color> {
+    if contrast_ratio(foreground, background) >= target {
+        return Some(foreground);
+    }
+    let [h, s, l] = foreground.to_hsl();
+    let steps = 1000;
+    for step in 1..=steps {
+        let delta = step as f64 / steps as f64;
+        let candidates = [
+            Color::from_hsl([h, s, (l - delta).max(0.0)]),
+            Color::from_hsl([h, s, (l + delta).min(1.0)]),
+        ];
+        let best = candidates
+            .iter()
+            .copied()
+            .filter(|&c| contrast_ratio(c, background) >= target)
+            .max_by(|&a, &b| {
+                contrast_ratio(a, background)
+                    .partial_cmp(&contrast_ratio(b, background))
+                    .unwrap()
+            });
+        if best.is_some() {
+            return best;
+        }
+    }
+    None
+}
+
+// A theme file has one pair per line, `name: #foreground #background`.
+// Blank lines and lines starting with `#` followed by a space are ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemePair {
+    pub name: String,
+    pub foreground: Color,
+    pub background: Color,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+pub fn parse_theme(text: &str) -> Result<Vec<ThemePair>, ThemeError> {
+    let mut pairs = Vec::new();
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line == "#" || line.starts_with("# ") {
+            continue;
+        }
+        let error = |message: String| ThemeError {
+            line: index + 1,
+            message,
+        };
+        let colon = line
+            .find(':')
+            .ok_or_else(|| error("expected `name: #foreground #background`".to_string()))?;
+        let name = line[..colon].trim();
+        if name.is_empty() {
+            return Err(error("missing name".to_string()));
+        }
+        let colors: Vec<&str> = line[colon + 1..].split_whitespace().collect();
+        if colors.len() != 2 {
+            return Err(error(format!("expected 2 colours, found {}", colors.len())));
+        }
+        let parse = |hex: &str| {
+            Color::from_hex(hex).ok_or_else(|| error(format!("invalid colour `{}`", hex)))
+        };
+        pairs.push(ThemePair {
+            name: name.to_string(),
+            foreground: parse(colors[0])?,
+            background: parse(colors[1])?,
+        });
+    }
+    Ok(pairs)
+}
+
+fn mark(pass: bool) -> &'static str {
+    if pass {
+        "pass"
+    } else {
+        "FAIL"
+    }
+}
+
+// one line per pair, with a suggestion for every pair that misses `target`;
+// the flag is true when every pair meets `target`
+pub fn audit_theme(pairs: &[ThemePair], target: f64) -> (String, bool) {
+    let mut out = String::new();
+    let mut all_pass = true;
+    for pair in pairs {
+        let report = check(pair.foreground, pair.background);
+        out.push_str(&format!(
+            "{}: {:.2}:1 AA {} AA-large {} AAA {} AAA-large {}",
+            pair.name,
+            report.ratio,
+            mark(report.aa_normal),
+            mark(report.aa_large),
+            mark(report.aaa_normal),
+            mark(report.aaa_large)
+        ));
+        if report.ratio < target {
+            all_pass = false;
+            match suggest_accessible(pair.foreground, pair.background, target) {
+                Some(color) => out.push_str(&format!(" (try {})", color.to_hex())),
+                None => out.push_str(" (no accessible lightness)"),
+            }
+        }
+        out.push('\n');
+    }
+    (out, all_pass)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contrast::{
+        audit_theme, check, contrast_ratio, parse_theme, relative_luminance, suggest_accessible,
+        ThemeError, ThemePair, AAA_NORMAL, AA_NORMAL,
+    };
+    use crate::structs::Color;
+
+    const BLACK: Color = Color(0, 0, 0);
+    const WHITE: Color = Color(255, 255, 255);
+
+    #[test]
+    fn test_relative_luminance() {
+        assert_eq!(relative_luminance(BLACK), 0.0);
+        assert!((relative_luminance(WHITE) - 1.0).abs() < 1e-9);
+        assert!((relative_luminance(Color(255, 0, 0)) - 0.2126).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        assert!((contrast_ratio(BLACK, WHITE) - 21.0).abs() < 1e-9);
+        assert!((contrast_ratio(WHITE, BLACK) - 21.0).abs() < 1e-9);
+        assert_eq!(contrast_ratio(WHITE, WHITE), 1.0);
+        // #777 on white is the well known near miss at about 4.48:1
+        let grey = contrast_ratio(Color(0x77, 0x77, 0x77), WHITE);
+        assert!((grey - 4.48).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_check_levels() {
+        let report = check(Color(0x77, 0x77, 0x77), WHITE);
+        assert!(!report.aa_normal);
+        assert!(report.aa_large);
+        assert!(!report.aaa_normal);
+        assert!(!report.aaa_large);
+        let report = check(BLACK, WHITE);
+        assert!(report.aa_normal && report.aa_large && report.aaa_normal && report.aaa_large);
+    }
+
+    #[test]
+    fn test_suggest_accessible() {
+        let grey = Color(0x77, 0x77, 0x77);
+        let suggestion = suggest_accessible(grey, WHITE, AA_NORMAL).unwrap();
+        assert!(contrast_ratio(suggestion, WHITE) >= AA_NORMAL);
+        // only a nudge darker is needed
+        assert!(suggestion.0 < 0x77 && suggestion.0 > 0x70);
+        // on black the same grey has to go lighter
+        let suggestion = suggest_accessible(Color(0x30, 0x30, 0x30), BLACK, AAA_NORMAL).unwrap();
+        assert!(suggestion.0 > 0x30);
+        assert!(contrast_ratio(suggestion, BLACK) >= AAA_NORMAL);
+        // already fine
+        assert_eq!(suggest_accessible(BLACK, WHITE, AA_NORMAL), Some(BLACK));
+        // impossible
+        assert_eq!(suggest_accessible(grey, WHITE, 22.0), None);
+    }
+
+    #[test]
+    fn test_suggest_keeps_hue() {
+        let blue = Color(0x66, 0x99, 0xff);
+        let suggestion = suggest_accessible(blue, WHITE, AA_NORMAL).unwrap();
+        assert!((suggestion.to_hsl()[0] - blue.to_hsl()[0]).abs() < 2.0);
+    }
+
+    #[test]
+    fn test_parse_theme() {
+        let text = "# editor theme\n\nbody: #000 #ffffff\n  muted : #777777 #fff  \n";
+        assert_eq!(
+            parse_theme(text),
+            Ok(vec![
+                ThemePair {
+                    name: "body".to_string(),
+                    foreground: BLACK,
+                    background: WHITE,
+                },
+                ThemePair {
+                    name: "muted".to_string(),
+                    foreground: Color(0x77, 0x77, 0x77),
+                    background: WHITE,
+                },
+            ])
+        );
+        assert_eq!(
+            parse_theme("body: #000\n"),
+            Err(ThemeError {
+                line: 1,
+                message: "expected 2 colours, found 1".to_string()
+            })
+        );
+        assert_eq!(
+            parse_theme("ok: #000 #fff\nlink: #000 blue\n"),
+            Err(ThemeError {
+                line: 2,
+                message: "invalid colour `blue`".to_string()
+            })
+        );
+        assert!(parse_theme("no colon here").is_err());
+    }
+
+    #[test]
+    fn test_audit_theme() {
+        let pairs = parse_theme("body: #000 #fff\nmuted: #777 #fff\n").unwrap();
+        let (report, all_pass) = audit_theme(&pairs, AA_NORMAL);
+        assert!(!all_pass);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(
+            lines[0],
+            "body: 21.00:1 AA pass AA-large pass AAA pass AAA-large pass"
+        );
+        assert!(lines[1].starts_with("muted: 4.48:1 AA FAIL AA-large pass"));
+        assert!(lines[1].contains("(try #"));
+        let (_, all_pass) = audit_theme(&pairs[..1], AA_NORMAL);
+        assert!(all_pass);
+    }
+}
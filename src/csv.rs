@@ -0,0 +1,287 @@
+// Comma-separated values as in RFC 4180.
+//
+// Fields containing a comma, quote or line break are quoted, with quotes doubled inside. The
+// reader also takes bare LF line ends and a leading byte order mark, as spreadsheet exports
+// often have them; the writer always uses CRLF.
+use std::fmt;
+
+const BOM: char = '\u{feff}';
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    // the line the record starts on, counting from 1
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+// the records of a text one at a time; after a malformed record the reader starts over on
+// the next line, so one bad row doesn't take the rest of the file with it
+pub struct Records<'a> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Records<'a> {
+    // carries on from the line after `line`, which `from` is on
+    fn skip_line(&mut self, from: usize, line: usize) {
+        self.pos = match self.text[from..].find('\n') {
+            Some(i) => from + i + 1,
+            None => self.text.len(),
+        };
+        self.line = line + 1;
+    }
+
+    fn record(&mut self) -> Result<Record, CsvError> {
+        let text = self.text;
+        let (from, start) = (self.pos, self.line);
+        let mut chars = text[from..]
+            .char_indices()
+            .map(|(i, c)| (from + i, c))
+            .peekable();
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        loop {
+            match chars.next() {
+                Some((_, '"')) if field.is_empty() && !quoted => {
+                    quoted = true;
+                    // read up to the closing quote, line breaks and all
+                    loop {
+                        match chars.next() {
+                            Some((_, '"')) if chars.peek().map(|&(_, c)| c) == Some('"') => {
+                                chars.next();
+                                field.push('"');
+                            }
+                            Some((_, '"')) => break,
+                            Some((_, c)) => {
+                                if c == '\n' {
+                                    self.line += 1;
+                                }
+                                field.push(c);
+                            }
+                            None => {
+                                // the quote may have been a stray one, so only its line is lost
+                                self.skip_line(from, start);
+                                return Err(CsvError {
+                                    line: start,
+                                    message: "unterminated quoted field".to_string(),
+                                });
+                            }
+                        }
+                    }
+                }
+                Some((_, ',')) => {
+                    fields.push(std::mem::take(&mut field));
+                    quoted = false;
+                }
+                Some((_, '\r')) if chars.peek().map(|&(_, c)| c) == Some('\n') => {}
+                Some((i, '\n')) => {
+                    self.pos = i + 1;
+                    self.line += 1;
+                    fields.push(field);
+                    break;
+                }
+                None => {
+                    self.pos = text.len();
+                    fields.push(field);
+                    break;
+                }
+                Some((i, c)) if quoted => {
+                    let line = self.line;
+                    self.skip_line(i, line);
+                    return Err(CsvError {
+                        line,
+                        message: format!("unexpected {:?} after a closing quote", c),
+                    });
+                }
+                Some((_, c)) => field.push(c),
+            }
+        }
+        Ok(Record {
+            line: start,
+            fields,
+        })
+    }
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<Record, CsvError>;
+
+    fn next(&mut self) -> Option<Result<Record, CsvError>> {
+        if self.pos >= self.text.len() {
+            return None;
+        }
+        Some(self.record())
+    }
+}
+
+pub fn records(text: &str) -> Records<'_> {
+    Records {
+        text: text.strip_prefix(BOM).unwrap_or(text),
+        pos: 0,
+        line: 1,
+    }
+}
+
+// every record, or the first error
+pub fn parse(text: &str) -> Result<Vec<Record>, CsvError> {
+    records(text).collect()
+}
+
+fn needs_quotes(field: &str) -> bool {
+    field.contains(&[',', '"', '\r', '\n'][..])
+}
+
+pub fn write_record<S: AsRef<str>>(out: &mut String, fields: &[S]) {
+    for (i, field) in fields.iter().enumerate() {
+        let field = field.as_ref();
+        if i > 0 {
+            out.push(',');
+        }
+        if needs_quotes(field) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+// `bom` makes spreadsheet programs read the text as UTF-8
+pub fn write<S: AsRef<str>>(records: &[Vec<S>], bom: bool) -> String {
+    let mut out = String::new();
+    if bom {
+        out.push(BOM);
+    }
+    for fields in records {
+        write_record(&mut out, fields);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::csv::{parse, records, write, CsvError, Record};
+
+    fn fields(text: &str) -> Vec<Vec<String>> {
+        parse(text)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.fields)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(fields("a,b\r\n1,2\r\n"), [["a", "b"], ["1", "2"]]);
+        assert_eq!(fields("a,b\n1,2"), [["a", "b"], ["1", "2"]]);
+        assert_eq!(fields("\u{feff}a,,\n"), [["a", "", ""]]);
+        assert_eq!(fields(",\n"), [["", ""]]);
+        assert!(parse("").unwrap().is_empty());
+        // a blank line is a record with one empty field
+        assert_eq!(fields("a\n\nb\n"), [vec!["a"], vec![""], vec!["b"]]);
+    }
+
+    #[test]
+    fn test_quoted_fields() {
+        assert_eq!(
+            fields("\"a,b\",\"say \"\"hi\"\"\",\"\"\r\n"),
+            [["a,b", "say \"hi\"", ""]]
+        );
+        let records = parse("name,note\n\"ray\",\"two\r\nlines\"\nsam,x\n").unwrap();
+        assert_eq!(records[1].fields, ["ray", "two\r\nlines"]);
+        // records know where they start, even after a field spanning lines
+        assert_eq!(records[2].line, 4);
+        // a quote in the middle of a field is just a character
+        assert_eq!(fields("5\" disk\n"), [["5\" disk"]]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse("a\n\"open,b\n"),
+            Err(CsvError {
+                line: 2,
+                message: "unterminated quoted field".to_string()
+            })
+        );
+        assert_eq!(
+            parse("\"closed\"x\n"),
+            Err(CsvError {
+                line: 1,
+                message: "unexpected 'x' after a closing quote".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_records_recover() {
+        let text = "a,b\n\"x\"y,z\n\"two\nlines\"!\nc,d\n\"open,b\ne,f";
+        let found: Vec<_> = records(text)
+            .map(|record| record.map(|record| (record.line, record.fields)))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                Ok((1, vec!["a".to_string(), "b".to_string()])),
+                Err(CsvError {
+                    line: 2,
+                    message: "unexpected 'y' after a closing quote".to_string()
+                }),
+                Err(CsvError {
+                    line: 4,
+                    message: "unexpected '!' after a closing quote".to_string()
+                }),
+                Ok((5, vec!["c".to_string(), "d".to_string()])),
+                Err(CsvError {
+                    line: 6,
+                    message: "unterminated quoted field".to_string()
+                }),
+                // the stray quote only cost its own line
+                Ok((7, vec!["e".to_string(), "f".to_string()])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let rows = vec![
+            vec!["plain", "with,comma", "with \"quotes\""],
+            vec!["multi\nline", "", "crlf\r\n"],
+        ];
+        let text = write(&rows, true);
+        assert!(text.starts_with('\u{feff}'));
+        assert!(text.ends_with("\"crlf\r\n\"\r\n"));
+        assert_eq!(
+            parse(&text).unwrap(),
+            vec![
+                Record {
+                    line: 1,
+                    fields: rows[0].iter().map(|s| s.to_string()).collect()
+                },
+                Record {
+                    line: 2,
+                    fields: rows[1].iter().map(|s| s.to_string()).collect()
+                },
+            ]
+        );
+        assert_eq!(write(&[vec!["a", "b"]], false), "a,b\r\n");
+    }
+}
@@ -0,0 +1,313 @@
+// Multi-stop colour gradients, interpolated in a choice of colour spaces.
+use crate::structs::Color;
+use std::fmt;
+
+// the space two neighbouring stops are blended in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorSpace {
+    Srgb,
+    LinearRgb,
+    // hue takes the shortest way round the colour wheel
+    Hsl,
+    Lab,
+    Oklab,
+}
+
+// easing is applied to the local position between two stops
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    SmoothStep,
+}
+
+impl Easing {
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - 2.0 * (1.0 - t) * (1.0 - t)
+                }
+            }
+            Easing::SmoothStep => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GradientError {
+    TooFewStops,
+    PositionOutOfRange(f64),
+    UnsortedStops,
+}
+
+impl fmt::Display for GradientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GradientError::TooFewStops => write!(f, "a gradient needs at least two stops"),
+            GradientError::PositionOutOfRange(p) => {
+                write!(f, "stop position {} is outside 0.0..=1.0", p)
+            }
+            GradientError::UnsortedStops => write!(f, "stop positions must be ascending"),
+        }
+    }
+}
+
+impl std::error::Error for GradientError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(f64, Color)>,
+    space: ColorSpace,
+    easing: Easing,
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+fn lerp3(a: [f64; 3], b: [f64; 3], t: f64) -> [f64; 3] {
+    [
+        lerp(a[0], b[0], t),
+        lerp(a[1], b[1], t),
+        lerp(a[2], b[2], t),
+    ]
+}
+
+fn mix_hsl(from: Color, to: Color, t: f64) -> Color {
+    let [mut h1, s1, l1] = from.to_hsl();
+    let [mut h2, s2, l2] = to.to_hsl();
+    // grey has no hue of its own, borrow the other end's so we don't swing through red
+    if s1 == 0.0 {
+        h1 = h2;
+    }
+    if s2 == 0.0 {
+        h2 = h1;
+    }
+    let mut delta = h2 - h1;
+    if delta > 180.0 {
+        delta -= 360.0;
+    } else if delta < -180.0 {
+        delta += 360.0;
+    }
+    Color::from_hsl([
+        (h1 + delta * t).rem_euclid(360.0),
+        lerp(s1, s2, t),
+        lerp(l1, l2, t),
+    ])
+}
+
+// blend two colours in the given space, t = 0.0 gives `from` and t = 1.0 gives `to`
+pub fn mix(from: Color, to: Color, t: f64, space: ColorSpace) -> Color {
+    match space {
+        ColorSpace::Srgb => Color::from_srgb(lerp3(from.to_srgb(), to.to_srgb(), t)),
+        ColorSpace::LinearRgb => Color::from_linear(lerp3(from.to_linear(), to.to_linear(), t)),
+        ColorSpace::Hsl => mix_hsl(from, to, t),
+        ColorSpace::Lab => Color::from_lab(lerp3(from.to_lab(), to.to_lab(), t)),
+        ColorSpace::Oklab => Color::from_oklab(lerp3(from.to_oklab(), to.to_oklab(), t)),
+    }
+}
+
+impl Gradient {
+    // stops are (position, colour) pairs with positions ascending in 0.0..=1.0
+    pub fn new(stops: Vec<(f64, Color)>) -> Result<Gradient, GradientError> {
+        if stops.len() < 2 {
+            return Err(GradientError::TooFewStops);
+        }
+        for &(position, _) in stops.iter() {
+            if !(0.0..=1.0).contains(&position) {
+                return Err(GradientError::PositionOutOfRange(position));
+            }
+        }
+        if stops.windows(2).any(|pair| pair[1].0 < pair[0].0) {
+            return Err(GradientError::UnsortedStops);
+        }
+        Ok(Gradient {
+            stops,
+            space: ColorSpace::Oklab,
+            easing: Easing::Linear,
+        })
+    }
+
+    // spread the colours evenly from 0.0 to 1.0
+    pub fn evenly_spaced(colors: &[Color]) -> Result<Gradient, GradientError> {
+        let last = colors.len().saturating_sub(1).max(1) as f64;
+        let stops = colors
+            .iter()
+            .enumerate()
+            .map(|(i, &color)| (i as f64 / last, color))
+            .collect();
+        Gradient::new(stops)
+    }
+
+    pub fn in_space(mut self, space: ColorSpace) -> Gradient {
+        self.space = space;
+        self
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Gradient {
+        self.easing = easing;
+        self
+    }
+
+    // t is clamped into 0.0..=1.0, before the first stop and after the last the end colours hold
+    pub fn sample(&self, t: f64) -> Color {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let first = self.stops[0];
+        let last = self.stops[self.stops.len() - 1];
+        if t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+        // the first segment whose end lies at or beyond t, always found as t < last.0
+        let i = self
+            .stops
+            .windows(2)
+            .position(|pair| t <= pair[1].0)
+            .unwrap_or(self.stops.len() - 2);
+        let (start, from) = self.stops[i];
+        let (end, to) = self.stops[i + 1];
+        let local = if end > start {
+            (t - start) / (end - start)
+        } else {
+            1.0
+        };
+        mix(from, to, self.easing.apply(local), self.space)
+    }
+
+    // n colours evenly spaced over the whole gradient, both ends included
+    pub fn colors(&self, n: usize) -> Vec<Color> {
+        match n {
+            0 => Vec::new(),
+            1 => vec![self.sample(0.0)],
+            _ => (0..n)
+                .map(|i| self.sample(i as f64 / (n - 1) as f64))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gradients::{mix, ColorSpace, Easing, Gradient, GradientError};
+    use crate::structs::Color;
+
+    const BLACK: Color = Color(0, 0, 0);
+    const WHITE: Color = Color(255, 255, 255);
+    const RED: Color = Color(255, 0, 0);
+    const BLUE: Color = Color(0, 0, 255);
+
+    #[test]
+    fn test_invalid_stops() {
+        assert_eq!(
+            Gradient::new(vec![(0.0, BLACK)]),
+            Err(GradientError::TooFewStops)
+        );
+        assert_eq!(
+            Gradient::new(vec![(0.0, BLACK), (1.5, WHITE)]),
+            Err(GradientError::PositionOutOfRange(1.5))
+        );
+        assert_eq!(
+            Gradient::new(vec![(0.8, BLACK), (0.2, WHITE)]),
+            Err(GradientError::UnsortedStops)
+        );
+    }
+
+    #[test]
+    fn test_endpoints_and_clamping() {
+        let gradient = Gradient::evenly_spaced(&[RED, WHITE, BLUE]).unwrap();
+        assert_eq!(gradient.sample(0.0), RED);
+        assert_eq!(gradient.sample(0.5), WHITE);
+        assert_eq!(gradient.sample(1.0), BLUE);
+        assert_eq!(gradient.sample(-3.0), RED);
+        assert_eq!(gradient.sample(7.0), BLUE);
+        assert_eq!(gradient.sample(f64::NAN), RED);
+    }
+
+    #[test]
+    fn test_srgb_and_linear_midpoints() {
+        // naive sRGB averaging gives 128, averaging light intensity gives a brighter 188
+        assert_eq!(
+            mix(BLACK, WHITE, 0.5, ColorSpace::Srgb),
+            Color(128, 128, 128)
+        );
+        assert_eq!(
+            mix(BLACK, WHITE, 0.5, ColorSpace::LinearRgb),
+            Color(188, 188, 188)
+        );
+    }
+
+    #[test]
+    fn test_hsl_takes_shortest_hue_path() {
+        // from hue 350 to hue 10 should pass through 0 (red), not 180 (cyan)
+        let from = Color::from_hsl([350.0, 1.0, 0.5]);
+        let to = Color::from_hsl([10.0, 1.0, 0.5]);
+        assert_eq!(mix(from, to, 0.5, ColorSpace::Hsl), RED);
+        // a grey end keeps the hue of the coloured end
+        let halfway = mix(Color(128, 128, 128), BLUE, 0.5, ColorSpace::Hsl).to_hsl();
+        assert!((halfway[0] - 240.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_perceptual_spaces_stay_between_endpoints() {
+        for &space in [ColorSpace::Lab, ColorSpace::Oklab].iter() {
+            let mid = mix(BLACK, WHITE, 0.5, space);
+            assert_eq!(mid.0, mid.1);
+            assert_eq!(mid.1, mid.2);
+            assert!(mid.0 > 0 && mid.0 < 255);
+        }
+    }
+
+    #[test]
+    fn test_colors_are_evenly_spaced() {
+        let gradient = Gradient::new(vec![(0.0, BLACK), (1.0, WHITE)])
+            .unwrap()
+            .in_space(ColorSpace::Srgb);
+        assert_eq!(gradient.colors(0), vec![]);
+        assert_eq!(gradient.colors(1), vec![BLACK]);
+        assert_eq!(gradient.colors(3), vec![BLACK, Color(128, 128, 128), WHITE]);
+        assert_eq!(gradient.colors(11).len(), 11);
+    }
+
+    #[test]
+    fn test_easing() {
+        for &easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+            Easing::SmoothStep,
+        ]
+        .iter()
+        {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+        assert_eq!(Easing::SmoothStep.apply(0.5), 0.5);
+        let gradient = Gradient::new(vec![(0.0, BLACK), (1.0, WHITE)])
+            .unwrap()
+            .in_space(ColorSpace::Srgb)
+            .with_easing(Easing::EaseIn);
+        assert_eq!(gradient.sample(0.5), Color(64, 64, 64));
+    }
+
+    #[test]
+    fn test_coincident_stops_make_a_hard_edge() {
+        let gradient =
+            Gradient::new(vec![(0.0, RED), (0.5, RED), (0.5, BLUE), (1.0, BLUE)]).unwrap();
+        assert_eq!(gradient.sample(0.49), RED);
+        assert_eq!(gradient.sample(0.51), BLUE);
+    }
+}
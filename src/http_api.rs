@@ -0,0 +1,881 @@
+// User management over HTTP/1.1, on a plain `TcpListener`.
+//
+//   GET    /users        all users, in id order, as a chunked JSON array
+//   POST   /users        {"username", "email", "roles"?} -> 201 with a Location
+//   GET    /users/{id}
+//   PUT    /users/{id}   {"username", "email", "roles"?}, state and counts are kept
+//   DELETE /users/{id}   -> 204
+//
+// Each connection gets a thread and is kept alive until the client says `Connection: close`,
+// goes quiet for `IDLE_TIMEOUT` or sends something we can't read. Request bodies may come
+// with a Content-Length or chunked. Errors are JSON too: `{"error": "..."}`. Password hashes
+// are never sent out.
+use crate::json::{self, Json};
+use crate::structs::User;
+use crate::user_repository::{RepositoryError, UserId};
+use crate::user_shards::ShardedUserStore;
+use crate::user_validation::{try_build_user, validate_email, validate_username};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// the request line and headers together
+pub const MAX_HEAD: usize = 8 * 1024;
+pub const MAX_BODY: usize = 1024 * 1024;
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum HttpError {
+    Io(io::Error),
+    Malformed(String),
+    TooLarge,
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HttpError::Io(e) => write!(f, "{}", e),
+            HttpError::Malformed(message) => write!(f, "malformed message: {}", message),
+            HttpError::TooLarge => write!(f, "message too large"),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+impl From<io::Error> for HttpError {
+    fn from(e: io::Error) -> HttpError {
+        HttpError::Io(e)
+    }
+}
+
+fn malformed<T>(message: &str) -> Result<T, HttpError> {
+    Err(HttpError::Malformed(message.to_string()))
+}
+
+pub type Headers = Vec<(String, String)>;
+
+// the value of the first header called `name`, in any case
+pub fn header<'h>(headers: &'h Headers, name: &str) -> Option<&'h str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+// one line without its CRLF (or bare LF), counting against `budget`
+fn read_line<R: BufRead>(reader: &mut R, budget: &mut usize) -> Result<Option<String>, HttpError> {
+    let mut line = Vec::new();
+    let limit = *budget as u64 + 1;
+    let read = reader.by_ref().take(limit).read_until(b'\n', &mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if line.last() != Some(&b'\n') {
+        if read as u64 == limit {
+            return Err(HttpError::TooLarge);
+        }
+        return malformed("unexpected end of stream");
+    }
+    *budget -= read.min(*budget);
+    line.pop();
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    match String::from_utf8(line) {
+        Ok(line) => Ok(Some(line)),
+        Err(_) => malformed("header is not UTF-8"),
+    }
+}
+
+// The start line and headers of a request or response, `None` if the stream ends before it
+// starts.
+pub fn read_head<R: BufRead>(reader: &mut R) -> Result<Option<(String, Headers)>, HttpError> {
+    let mut budget = MAX_HEAD;
+    let start = match read_line(reader, &mut budget)? {
+        Some(line) => line,
+        None => return Ok(None),
+    };
+    let mut headers = Vec::new();
+    loop {
+        let line = match read_line(reader, &mut budget)? {
+            Some(line) => line,
+            None => return malformed("unexpected end of stream"),
+        };
+        if line.is_empty() {
+            return Ok(Some((start, headers)));
+        }
+        let colon = match line.find(':') {
+            Some(colon) => colon,
+            None => return malformed("header without a colon"),
+        };
+        let name = &line[..colon];
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return malformed("bad header name");
+        }
+        headers.push((name.to_string(), line[colon + 1..].trim().to_string()));
+    }
+}
+
+fn read_chunked<R: BufRead>(reader: &mut R) -> Result<Vec<u8>, HttpError> {
+    let mut body = Vec::new();
+    let mut budget = MAX_HEAD;
+    loop {
+        let line = match read_line(reader, &mut budget)? {
+            Some(line) => line,
+            None => return malformed("unexpected end of stream"),
+        };
+        // chunk extensions after `;` are allowed and ignored
+        let size = line.split(';').next().unwrap_or("").trim();
+        let size = match usize::from_str_radix(size, 16) {
+            Ok(size) => size,
+            Err(_) => return malformed("bad chunk size"),
+        };
+        if size == 0 {
+            break;
+        }
+        // written so that a huge declared size can't overflow
+        if size > MAX_BODY - body.len() {
+            return Err(HttpError::TooLarge);
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        if read_line(reader, &mut budget)? != Some(String::new()) {
+            return malformed("chunk longer than its size");
+        }
+    }
+    // trailers, which we don't use
+    loop {
+        match read_line(reader, &mut budget)? {
+            Some(line) if line.is_empty() => return Ok(body),
+            Some(_) => {}
+            None => return malformed("unexpected end of stream"),
+        }
+    }
+}
+
+// The declared Content-Length, `None` if there is none. Several that disagree, or one next to
+// a Transfer-Encoding, could be read differently by a proxy in front of us, so they are refused.
+fn content_length(headers: &Headers) -> Result<Option<usize>, HttpError> {
+    let mut lengths = headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("Content-Length"))
+        .flat_map(|(_, value)| value.split(','))
+        .map(|length| length.trim().parse::<usize>());
+    let first = match lengths.next() {
+        None => return Ok(None),
+        Some(Ok(length)) => length,
+        Some(Err(_)) => return malformed("bad Content-Length"),
+    };
+    for length in lengths {
+        match length {
+            Ok(length) if length == first => {}
+            Ok(_) => return malformed("conflicting Content-Length headers"),
+            Err(_) => return malformed("bad Content-Length"),
+        }
+    }
+    if header(headers, "Transfer-Encoding").is_some() {
+        return malformed("both Transfer-Encoding and Content-Length");
+    }
+    Ok(Some(first))
+}
+
+pub fn read_body<R: BufRead>(reader: &mut R, headers: &Headers) -> Result<Vec<u8>, HttpError> {
+    let length = content_length(headers)?;
+    if let Some(encoding) = header(headers, "Transfer-Encoding") {
+        if !encoding.eq_ignore_ascii_case("chunked") {
+            return malformed("unsupported transfer encoding");
+        }
+        return read_chunked(reader);
+    }
+    let length = match length {
+        Some(length) => length,
+        None => return Ok(Vec::new()),
+    };
+    if length > MAX_BODY {
+        return Err(HttpError::TooLarge);
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(body)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub target: String,
+    pub version: String,
+    pub headers: Headers,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    // whether the client wants the connection closed after this
+    pub fn wants_close(&self) -> bool {
+        let connection = header(&self.headers, "Connection").unwrap_or("");
+        if self.version == "HTTP/1.0" {
+            !connection.eq_ignore_ascii_case("keep-alive")
+        } else {
+            connection.eq_ignore_ascii_case("close")
+        }
+    }
+}
+
+pub fn read_request<R: BufRead>(reader: &mut R) -> Result<Option<Request>, HttpError> {
+    let (line, headers) = match read_head(reader)? {
+        Some(head) => head,
+        None => return Ok(None),
+    };
+    let parts: Vec<&str> = line.split(' ').collect();
+    let (method, target, version) = match parts[..] {
+        [method, target, version] if !method.is_empty() && target.starts_with('/') => {
+            (method, target, version)
+        }
+        _ => return malformed("bad request line"),
+    };
+    if version != "HTTP/1.1" && version != "HTTP/1.0" {
+        return malformed("unsupported HTTP version");
+    }
+    let body = read_body(reader, &headers)?;
+    Ok(Some(Request {
+        method: method.to_string(),
+        target: target.to_string(),
+        version: version.to_string(),
+        headers,
+        body,
+    }))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Body {
+    Bytes(Vec<u8>),
+    // sent with `Transfer-Encoding: chunked`, one chunk each
+    Chunks(Vec<Vec<u8>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Headers,
+    pub body: Body,
+}
+
+impl Response {
+    fn empty(status: u16) -> Response {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: Body::Bytes(Vec::new()),
+        }
+    }
+
+    fn json(status: u16, body: &Json) -> Response {
+        Response {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: Body::Bytes(body.to_string().into_bytes()),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response::json(
+            status,
+            &Json::Object(vec![("error".to_string(), message.into())]),
+        )
+    }
+
+    fn with_header(mut self, name: &str, value: &str) -> Response {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        _ => "",
+    }
+}
+
+// `chunked` is false for HTTP/1.0 clients, which don't know chunks
+pub fn write_response<W: Write>(
+    writer: &mut W,
+    response: &Response,
+    close: bool,
+    chunked: bool,
+) -> io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason(response.status)
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if close {
+        head.push_str("Connection: close\r\n");
+    }
+    let mut out = Vec::new();
+    match &response.body {
+        Body::Chunks(chunks) if chunked => {
+            head.push_str("Transfer-Encoding: chunked\r\n\r\n");
+            out.extend_from_slice(head.as_bytes());
+            for chunk in chunks.iter().filter(|chunk| !chunk.is_empty()) {
+                out.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+                out.extend_from_slice(chunk);
+                out.extend_from_slice(b"\r\n");
+            }
+            out.extend_from_slice(b"0\r\n\r\n");
+        }
+        body => {
+            let bytes = match body {
+                Body::Bytes(bytes) => bytes.clone(),
+                Body::Chunks(chunks) => chunks.concat(),
+            };
+            if response.status != 204 {
+                head.push_str(&format!("Content-Length: {}\r\n", bytes.len()));
+            }
+            head.push_str("\r\n");
+            out.extend_from_slice(head.as_bytes());
+            out.extend_from_slice(&bytes);
+        }
+    }
+    writer.write_all(&out)?;
+    writer.flush()
+}
+
+fn user_json(id: UserId, user: &User) -> Json {
+    Json::Object(vec![
+        ("id".to_string(), id.into()),
+        ("username".to_string(), user.username.as_str().into()),
+        ("email".to_string(), user.email.as_str().into()),
+        ("sign_in_count".to_string(), user.sign_in_count.into()),
+        ("state".to_string(), user.lifecycle.state().name().into()),
+        ("active".to_string(), user.is_active().into()),
+        (
+            "roles".to_string(),
+            Json::Array(user.roles.iter().map(|r| r.as_str().into()).collect()),
+        ),
+    ])
+}
+
+// what a client may set; anything else in the body, such as `id` or `state`, is ignored
+struct UserInput {
+    username: String,
+    email: String,
+    roles: Vec<String>,
+}
+
+fn user_input(body: &[u8]) -> Result<UserInput, Response> {
+    let text = std::str::from_utf8(body).map_err(|_| Response::error(400, "body is not UTF-8"))?;
+    let doc = json::parse(text).map_err(|e| Response::error(400, &e.to_string()))?;
+    if !matches!(doc, Json::Object(_)) {
+        return Err(Response::error(400, "expected a JSON object"));
+    }
+    let text_field = |name: &str| match doc.get(name) {
+        Some(Json::String(s)) => Ok(s.clone()),
+        Some(_) => Err(Response::error(
+            400,
+            &format!("`{}` must be a string", name),
+        )),
+        None => Err(Response::error(400, &format!("`{}` is required", name))),
+    };
+    let username = text_field("username")?;
+    let email = text_field("email")?;
+    let roles = match doc.get("roles") {
+        None => Vec::new(),
+        Some(roles) => roles
+            .as_array()
+            .and_then(|roles| roles.iter().map(|r| r.as_str().map(String::from)).collect())
+            .ok_or_else(|| Response::error(400, "`roles` must be a list of strings"))?,
+    };
+    Ok(UserInput {
+        username,
+        email,
+        roles,
+    })
+}
+
+fn conflict(e: RepositoryError) -> Response {
+    match e {
+        RepositoryError::NotFound(_) => Response::error(404, &e.to_string()),
+        _ => Response::error(409, &e.to_string()),
+    }
+}
+
+fn list(store: &ShardedUserStore) -> Response {
+    let users = store.users();
+    let mut chunks = vec![b"[".to_vec()];
+    for (i, (id, user)) in users.iter().enumerate() {
+        let separator = if i > 0 { "," } else { "" };
+        chunks.push(format!("{}{}", separator, user_json(*id, user)).into_bytes());
+    }
+    chunks.push(b"]".to_vec());
+    Response {
+        status: 200,
+        headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+        body: Body::Chunks(chunks),
+    }
+}
+
+fn create(store: &ShardedUserStore, body: &[u8]) -> Result<Response, Response> {
+    let input = user_input(body)?;
+    let mut user = try_build_user(input.email, input.username)
+        .map_err(|e| Response::error(422, &e.to_string()))?;
+    user.roles = input.roles;
+    let id = store.create(user.clone()).map_err(conflict)?;
+    Ok(Response::json(201, &user_json(id, &user))
+        .with_header("Location", &format!("/users/{}", id)))
+}
+
+fn replace(store: &ShardedUserStore, id: UserId, body: &[u8]) -> Result<Response, Response> {
+    let input = user_input(body)?;
+    validate_username(&input.username)
+        .and_then(|_| validate_email(&input.email))
+        .map_err(|e| Response::error(422, &e.to_string()))?;
+    store
+        .modify(id, |user| {
+            user.username = input.username.clone();
+            user.email = input.email.clone();
+            user.roles = input.roles.clone();
+        })
+        .map_err(conflict)?;
+    let user = store
+        .get(id)
+        .ok_or_else(|| conflict(RepositoryError::NotFound(id)))?;
+    Ok(Response::json(200, &user_json(id, &user)))
+}
+
+// routes a request, without any I/O
+pub fn handle(request: &Request, store: &ShardedUserStore) -> Response {
+    let path = request.target.split('?').next().unwrap_or("");
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').skip(1).collect();
+    let method = request.method.as_str();
+    let result = match segments[..] {
+        ["users"] => match method {
+            "GET" => Ok(list(store)),
+            "POST" => create(store, &request.body),
+            _ => Err(Response::error(405, "method not allowed").with_header("Allow", "GET, POST")),
+        },
+        ["users", id] => {
+            let id = match id.parse::<UserId>() {
+                Ok(id) => id,
+                Err(_) => return Response::error(404, "no such user"),
+            };
+            match method {
+                "GET" => store
+                    .get(id)
+                    .map(|user| Response::json(200, &user_json(id, &user)))
+                    .ok_or_else(|| conflict(RepositoryError::NotFound(id))),
+                "PUT" => replace(store, id, &request.body),
+                "DELETE" => store
+                    .delete(id)
+                    .map(|_| Response::empty(204))
+                    .map_err(conflict),
+                _ => Err(Response::error(405, "method not allowed")
+                    .with_header("Allow", "GET, PUT, DELETE")),
+            }
+        }
+        _ => Err(Response::error(404, "not found")),
+    };
+    result.unwrap_or_else(|error| error)
+}
+
+// Answers and closes, reading what the client still sends for a moment first: closing with
+// unread data makes the socket reset, and the client could lose the answer.
+fn hang_up(
+    mut reader: BufReader<TcpStream>,
+    mut writer: TcpStream,
+    response: &Response,
+) -> io::Result<()> {
+    write_response(&mut writer, response, true, true)?;
+    writer.shutdown(Shutdown::Write)?;
+    writer.set_read_timeout(Some(Duration::from_secs(1)))?;
+    let _ = io::copy(&mut reader.by_ref().take(MAX_BODY as u64), &mut io::sink());
+    Ok(())
+}
+
+fn serve_connection(stream: TcpStream, store: &ShardedUserStore) -> io::Result<()> {
+    stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    loop {
+        let request = match read_request(&mut reader) {
+            Ok(Some(request)) => request,
+            Ok(None) => return Ok(()),
+            Err(HttpError::Io(e)) => return Err(e),
+            // we can't tell where the next request would start, so answer and hang up
+            Err(HttpError::TooLarge) => {
+                let response = Response::error(413, "request too large");
+                return hang_up(reader, writer, &response);
+            }
+            Err(e) => {
+                let response = Response::error(400, &e.to_string());
+                return hang_up(reader, writer, &response);
+            }
+        };
+        let close = request.wants_close();
+        let response = handle(&request, store);
+        write_response(&mut writer, &response, close, request.version != "HTTP/1.0")?;
+        if close {
+            return Ok(());
+        }
+    }
+}
+
+pub struct Server {
+    listener: TcpListener,
+    store: Arc<ShardedUserStore>,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(addr: A, store: Arc<ShardedUserStore>) -> io::Result<Server> {
+        Ok(Server {
+            listener: TcpListener::bind(addr)?,
+            store,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // accepts connections until the listener fails, a thread for each
+    pub fn serve(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let store = Arc::clone(&self.store);
+            thread::spawn(move || serve_connection(stream, &store));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http_api::{header, read_body, read_head, Headers, Server};
+    use crate::json::{self, Json};
+    use crate::user_shards::ShardedUserStore;
+    use std::io::{BufReader, Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::sync::Arc;
+    use std::thread;
+
+    fn start() -> SocketAddr {
+        let server = Server::bind("127.0.0.1:0", Arc::new(ShardedUserStore::new())).unwrap();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || server.serve());
+        addr
+    }
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    struct Reply {
+        status: u16,
+        headers: Headers,
+        body: String,
+    }
+
+    impl Reply {
+        fn json(&self) -> Json {
+            json::parse(&self.body).unwrap()
+        }
+    }
+
+    impl Client {
+        fn connect(addr: SocketAddr) -> Client {
+            let stream = TcpStream::connect(addr).unwrap();
+            Client {
+                reader: BufReader::new(stream.try_clone().unwrap()),
+                writer: stream,
+            }
+        }
+
+        fn send_raw(&mut self, raw: &str) -> Reply {
+            self.writer.write_all(raw.as_bytes()).unwrap();
+            let (status_line, headers) = read_head(&mut self.reader).unwrap().unwrap();
+            let status = status_line.split(' ').nth(1).unwrap().parse().unwrap();
+            let body = read_body(&mut self.reader, &headers).unwrap();
+            Reply {
+                status,
+                headers,
+                body: String::from_utf8(body).unwrap(),
+            }
+        }
+
+        fn send(&mut self, method: &str, path: &str, body: &str) -> Reply {
+            self.send_raw(&format!(
+                "{} {} HTTP/1.1\r\nHost: test\r\nContent-Length: {}\r\n\r\n{}",
+                method,
+                path,
+                body.len(),
+                body
+            ))
+        }
+
+        // true once the server has hung up
+        fn closed(&mut self) -> bool {
+            let mut rest = Vec::new();
+            self.reader.read_to_end(&mut rest).unwrap() == 0
+        }
+    }
+
+    #[test]
+    fn test_crud_over_one_connection() {
+        let mut client = Client::connect(start());
+        let created = client.send(
+            "POST",
+            "/users",
+            r#"{"username": "ray", "email": "ray@example.com", "roles": ["admin"]}"#,
+        );
+        assert_eq!(created.status, 201);
+        let id = created.json().get("id").and_then(Json::as_u64).unwrap();
+        let location = format!("/users/{}", id);
+        assert_eq!(
+            header(&created.headers, "location"),
+            Some(location.as_str())
+        );
+        assert_eq!(
+            created.json().get("state").and_then(Json::as_str),
+            Some("pending_verification")
+        );
+
+        let fetched = client.send("GET", &location, "");
+        assert_eq!(fetched.status, 200);
+        assert_eq!(fetched.json(), created.json());
+        assert!(fetched.json().get("password_hash").is_none());
+
+        let updated = client.send(
+            "PUT",
+            &location,
+            r#"{"username": "ray", "email": "ray@new.example.com"}"#,
+        );
+        assert_eq!(updated.status, 200);
+        assert_eq!(
+            updated.json().get("email").and_then(Json::as_str),
+            Some("ray@new.example.com")
+        );
+        assert_eq!(updated.json().get("roles"), Some(&Json::Array(vec![])));
+
+        client.send(
+            "POST",
+            "/users",
+            r#"{"username": "sam", "email": "sam@example.com"}"#,
+        );
+        let listed = client.send("GET", "/users", "");
+        assert_eq!(
+            header(&listed.headers, "Transfer-Encoding"),
+            Some("chunked")
+        );
+        let listed = listed.json();
+        let names: Vec<&str> = listed
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|u| u.get("username").and_then(Json::as_str).unwrap())
+            .collect();
+        assert_eq!(names, ["ray", "sam"]);
+
+        let deleted = client.send("DELETE", &location, "");
+        assert_eq!((deleted.status, deleted.body.as_str()), (204, ""));
+        assert_eq!(client.send("GET", &location, "").status, 404);
+        assert_eq!(client.send("DELETE", &location, "").status, 404);
+    }
+
+    #[test]
+    fn test_error_statuses() {
+        let mut client = Client::connect(start());
+        let ray = r#"{"username": "ray", "email": "ray@example.com"}"#;
+        assert_eq!(client.send("POST", "/users", ray).status, 201);
+        let cases = [
+            ("POST", "/users", ray, 409),
+            (
+                "POST",
+                "/users",
+                r#"{"username": "RAY", "email": "x@example.com"}"#,
+                409,
+            ),
+            (
+                "POST",
+                "/users",
+                r#"{"username": "x", "email": "x@example.com"}"#,
+                422,
+            ),
+            (
+                "POST",
+                "/users",
+                r#"{"username": "sam", "email": "no-at-sign"}"#,
+                422,
+            ),
+            ("POST", "/users", r#"{"username": "sam"}"#, 400),
+            (
+                "POST",
+                "/users",
+                r#"{"username": 7, "email": "s@example.com"}"#,
+                400,
+            ),
+            (
+                "POST",
+                "/users",
+                r#"{"username": "sam", "email": "s@example.com", "roles": [1]}"#,
+                400,
+            ),
+            ("POST", "/users", "{not json", 400),
+            ("POST", "/users", "[]", 400),
+            (
+                "PUT",
+                "/users/99",
+                r#"{"username": "sam", "email": "s@example.com"}"#,
+                404,
+            ),
+            ("GET", "/users/abc", "", 404),
+            ("GET", "/nothing", "", 404),
+            ("PATCH", "/users/1", "", 405),
+            ("DELETE", "/users", "", 405),
+        ];
+        for &(method, path, body, status) in cases.iter() {
+            let reply = client.send(method, path, body);
+            assert_eq!(reply.status, status, "{} {} {}", method, path, body);
+            assert!(reply.json().get("error").and_then(Json::as_str).is_some());
+        }
+        let reply = client.send("DELETE", "/users", "");
+        assert_eq!(header(&reply.headers, "Allow"), Some("GET, POST"));
+        // the connection survived all of that
+        assert_eq!(client.send("GET", "/users/1", "").status, 200);
+    }
+
+    #[test]
+    fn test_chunked_request_body() {
+        let mut client = Client::connect(start());
+        let mut raw = "POST /users HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n".to_string();
+        for chunk in [
+            r#"{"username": "r"#,
+            r#"ay", "email": "ray@example.com"#,
+            r#""}"#,
+        ]
+        .iter()
+        {
+            raw.push_str(&format!("{:x};ext=1\r\n{}\r\n", chunk.len(), chunk));
+        }
+        raw.push_str("0\r\nX-Trailer: ignored\r\n\r\n");
+        let reply = client.send_raw(&raw);
+        assert_eq!(reply.status, 201, "{}", reply.body);
+        assert_eq!(
+            reply.json().get("username").and_then(Json::as_str),
+            Some("ray")
+        );
+    }
+
+    #[test]
+    fn test_connection_close() {
+        let addr = start();
+        let mut client = Client::connect(addr);
+        let reply = client.send_raw("GET /users HTTP/1.1\r\nConnection: close\r\n\r\n");
+        assert_eq!(header(&reply.headers, "Connection"), Some("close"));
+        assert!(client.closed());
+
+        // HTTP/1.0 closes by default, and gets no chunks
+        let mut client = Client::connect(addr);
+        let reply = client.send_raw("GET /users HTTP/1.0\r\n\r\n");
+        assert_eq!((reply.status, reply.body.as_str()), (200, "[]"));
+        assert_eq!(header(&reply.headers, "Content-Length"), Some("2"));
+        assert!(client.closed());
+    }
+
+    #[test]
+    fn test_bad_requests_close_the_connection() {
+        let addr = start();
+        let mut client = Client::connect(addr);
+        assert_eq!(client.send_raw("NONSENSE\r\n\r\n").status, 400);
+        assert!(client.closed());
+
+        let mut client = Client::connect(addr);
+        let reply = client.send_raw("POST /users HTTP/1.1\r\nContent-Length: 99999999\r\n\r\n");
+        assert_eq!(reply.status, 413);
+        assert!(client.closed());
+
+        let mut client = Client::connect(addr);
+        let long = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(10_000));
+        assert_eq!(client.send_raw(&long).status, 413);
+
+        // a chunk size that would overflow the running total
+        let mut client = Client::connect(addr);
+        let huge = "POST /users HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+                    1\r\n{\r\nffffffffffffffff\r\n";
+        assert_eq!(client.send_raw(huge).status, 413);
+        assert!(client.closed());
+
+        // framing a proxy could read differently
+        let ambiguous = [
+            "POST /users HTTP/1.1\r\nContent-Length: 2\r\nTransfer-Encoding: chunked\r\n\r\n",
+            "POST /users HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 2\r\n\r\n",
+            "POST /users HTTP/1.1\r\nContent-Length: 2\r\nContent-Length: 3\r\n\r\n",
+            "POST /users HTTP/1.1\r\nContent-Length: 2, 3\r\n\r\n",
+        ];
+        for raw in ambiguous.iter() {
+            let mut client = Client::connect(addr);
+            assert_eq!(client.send_raw(raw).status, 400, "{:?}", raw);
+            assert!(client.closed());
+        }
+        // the same length twice says the same thing
+        let mut client = Client::connect(addr);
+        let repeated = "POST /users HTTP/1.1\r\nContent-Length: 2\r\nContent-Length: 2\r\n\r\n[]";
+        assert_eq!(client.send_raw(repeated).status, 400);
+        assert!(!client
+            .send_raw("GET /users HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .body
+            .is_empty());
+    }
+
+    #[test]
+    fn test_many_clients() {
+        let addr = start();
+        let created: Vec<u16> = (0..8)
+            .map(|t| {
+                thread::spawn(move || {
+                    let mut client = Client::connect(addr);
+                    (0..10)
+                        .map(|i| {
+                            // every thread wants the same ten names
+                            let body = format!(
+                                r#"{{"username": "user{}", "email": "{}-{}@example.com"}}"#,
+                                i, t, i
+                            );
+                            client.send("POST", "/users", &body).status
+                        })
+                        .collect::<Vec<u16>>()
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect();
+        assert_eq!(created.iter().filter(|&&s| s == 201).count(), 10);
+        assert_eq!(created.iter().filter(|&&s| s == 409).count(), 70);
+        let mut client = Client::connect(addr);
+        assert_eq!(
+            client
+                .send("GET", "/users", "")
+                .json()
+                .as_array()
+                .unwrap()
+                .len(),
+            10
+        );
+    }
+}
@@ -0,0 +1,516 @@
+// JSON as in RFC 8259, read and written by hand.
+//
+// Objects keep their members in the order they were written. Numbers are `f64`, like in
+// JavaScript, so integers are exact up to 2^53. Output is compact, with no whitespace between
+// tokens.
+use std::fmt;
+
+// deeper documents are refused rather than risking the stack
+pub const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    // the first member named `key`, if this is an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    // only for whole numbers that fit
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Number(n) if n.fract() == 0.0 && *n >= 0.0 && *n <= MAX_EXACT => Some(*n as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+// 2^53, the largest integer below which every integer is an exact `f64`
+const MAX_EXACT: f64 = 9_007_199_254_740_992.0;
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Json {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Json {
+        Json::String(s)
+    }
+}
+
+impl From<u64> for Json {
+    fn from(n: u64) -> Json {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Bool(b)
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\u{8}' => f.write_str("\\b")?,
+            '\u{c}' => f.write_str("\\f")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            // JSON has no NaN or infinity
+            Json::Number(n) if !n.is_finite() => f.write_str("null"),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < MAX_EXACT => {
+                write!(f, "{}", *n as i64)
+            }
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_string(f, s),
+            Json::Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(members) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonError {
+    // in bytes from the start of the text
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    at: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: &str) -> Result<T, JsonError> {
+        Err(JsonError {
+            offset: self.at,
+            message: message.to_string(),
+        })
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.bytes.get(self.at) {
+            self.at += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.at).copied()
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, JsonError> {
+        if self.bytes[self.at..].starts_with(word.as_bytes()) {
+            self.at += word.len();
+            Ok(value)
+        } else {
+            self.error("expected a value")
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => self.error("unexpected end of input"),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'[') => self.nested(Parser::array),
+            Some(b'{') => self.nested(Parser::object),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            Some(_) => self.error("expected a value"),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Parser<'a>) -> Result<Json, JsonError>,
+    ) -> Result<Json, JsonError> {
+        if self.depth == MAX_DEPTH {
+            return self.error("nested too deeply");
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn array(&mut self) -> Result<Json, JsonError> {
+        self.at += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.at += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b']') => {
+                    self.at += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return self.error("expected `,` or `]`"),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, JsonError> {
+        self.at += 1;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.at += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return self.error("expected a member name");
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            if self.peek() != Some(b':') {
+                return self.error("expected `:`");
+            }
+            self.at += 1;
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b'}') => {
+                    self.at += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return self.error("expected `,` or `}`"),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .bytes
+            .get(self.at..self.at + 4)
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u32::from_str_radix(d, 16).ok());
+        match digits {
+            Some(n) => {
+                self.at += 4;
+                Ok(n)
+            }
+            None => self.error("expected four hex digits"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        let start = self.at;
+        self.at += 1;
+        let mut out = String::new();
+        loop {
+            // copy the run up to the next quote, backslash or control character as is
+            let run = self.at;
+            while let Some(b) = self.peek() {
+                if b == b'"' || b == b'\\' || b < 0x20 {
+                    break;
+                }
+                self.at += 1;
+            }
+            match std::str::from_utf8(&self.bytes[run..self.at]) {
+                Ok(s) => out.push_str(s),
+                Err(_) => return self.error("invalid UTF-8"),
+            }
+            match self.peek() {
+                None => {
+                    self.at = start;
+                    return self.error("unterminated string");
+                }
+                Some(b'"') => {
+                    self.at += 1;
+                    return Ok(out);
+                }
+                Some(b'\\') => {
+                    self.at += 1;
+                    let escape = self.peek();
+                    self.at += 1;
+                    match escape {
+                        Some(b'"') => out.push('"'),
+                        Some(b'\\') => out.push('\\'),
+                        Some(b'/') => out.push('/'),
+                        Some(b'b') => out.push('\u{8}'),
+                        Some(b'f') => out.push('\u{c}'),
+                        Some(b'n') => out.push('\n'),
+                        Some(b'r') => out.push('\r'),
+                        Some(b't') => out.push('\t'),
+                        Some(b'u') => {
+                            let mut cp = self.hex4()?;
+                            // a surrogate pair spells one character outside the BMP
+                            if (0xd800..0xdc00).contains(&cp)
+                                && self.bytes[self.at..].starts_with(b"\\u")
+                            {
+                                self.at += 2;
+                                let low = self.hex4()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return self.error("unpaired surrogate");
+                                }
+                                cp = 0x10000 + ((cp - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            match std::char::from_u32(cp) {
+                                Some(c) => out.push(c),
+                                None => return self.error("unpaired surrogate"),
+                            }
+                        }
+                        _ => {
+                            self.at -= 1;
+                            return self.error("unknown escape");
+                        }
+                    }
+                }
+                Some(_) => return self.error("control character in string"),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.at;
+        let digits = |p: &mut Parser| {
+            let from = p.at;
+            while let Some(b'0'..=b'9') = p.peek() {
+                p.at += 1;
+            }
+            p.at - from
+        };
+        if self.peek() == Some(b'-') {
+            self.at += 1;
+        }
+        let whole = self.at;
+        match digits(self) {
+            0 => return self.error("expected a digit"),
+            n if n > 1 && self.bytes[whole] == b'0' => {
+                self.at = whole;
+                return self.error("leading zero");
+            }
+            _ => {}
+        }
+        if self.peek() == Some(b'.') {
+            self.at += 1;
+            if digits(self) == 0 {
+                return self.error("expected a digit");
+            }
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            self.at += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.at += 1;
+            }
+            if digits(self) == 0 {
+                return self.error("expected a digit");
+            }
+        }
+        // the text is all ASCII digits and signs by now
+        let text = std::str::from_utf8(&self.bytes[start..self.at]).unwrap();
+        Ok(Json::Number(text.parse().unwrap()))
+    }
+}
+
+pub fn parse(text: &str) -> Result<Json, JsonError> {
+    let mut parser = Parser {
+        bytes: text.as_bytes(),
+        at: 0,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.at != text.len() {
+        return parser.error("unexpected text after the value");
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{parse, Json, JsonError, MAX_DEPTH};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(" null "), Ok(Json::Null));
+        assert_eq!(parse("true"), Ok(Json::Bool(true)));
+        assert_eq!(parse("-12.5e1"), Ok(Json::Number(-125.0)));
+        assert_eq!(parse("0"), Ok(Json::Number(0.0)));
+        assert_eq!(
+            parse(r#"{"a": [1, "x", {}], "b" : false}"#),
+            Ok(Json::Object(vec![
+                (
+                    "a".to_string(),
+                    Json::Array(vec![
+                        Json::Number(1.0),
+                        Json::from("x"),
+                        Json::Object(vec![])
+                    ])
+                ),
+                ("b".to_string(), Json::Bool(false)),
+            ]))
+        );
+        let doc = parse(r#"{"n": 7, "s": "hi"}"#).unwrap();
+        assert_eq!(doc.get("n").and_then(Json::as_u64), Some(7));
+        assert_eq!(doc.get("s").and_then(Json::as_str), Some("hi"));
+        assert_eq!(doc.get("missing"), None);
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(
+            parse(r#""a\"b\\c\/d\n\t\u00e9\ud83d\ude00""#),
+            Ok(Json::from("a\"b\\c/d\n\té😀"))
+        );
+        assert_eq!(parse("\"René\""), Ok(Json::from("René")));
+        let tricky = "quote \" backslash \\ newline \n bell \u{7} emoji 😀";
+        assert_eq!(
+            Json::from(tricky).to_string(),
+            "\"quote \\\" backslash \\\\ newline \\n bell \\u0007 emoji 😀\""
+        );
+        assert_eq!(
+            parse(&Json::from(tricky).to_string()),
+            Ok(Json::from(tricky))
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let doc = Json::Object(vec![
+            ("id".to_string(), Json::from(3)),
+            ("ratio".to_string(), Json::Number(0.5)),
+            (
+                "tags".to_string(),
+                Json::Array(vec![Json::from("a"), Json::Null]),
+            ),
+        ]);
+        assert_eq!(doc.to_string(), r#"{"id":3,"ratio":0.5,"tags":["a",null]}"#);
+        assert_eq!(parse(&doc.to_string()), Ok(doc));
+        assert_eq!(Json::Number(f64::NAN).to_string(), "null");
+    }
+
+    fn error(text: &str) -> JsonError {
+        parse(text).unwrap_err()
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = [
+            ("", 0, "unexpected end of input"),
+            ("[1,]", 3, "expected a value"),
+            ("[1 2]", 3, "expected `,` or `]`"),
+            ("{\"a\" 1}", 5, "expected `:`"),
+            ("{1: 2}", 1, "expected a member name"),
+            ("\"abc", 0, "unterminated string"),
+            ("\"a\\qb\"", 3, "unknown escape"),
+            ("\"\\ud800\"", 7, "unpaired surrogate"),
+            ("\"tab\there\"", 4, "control character in string"),
+            ("012", 0, "leading zero"),
+            ("1.", 2, "expected a digit"),
+            ("-", 1, "expected a digit"),
+            ("tru", 0, "expected a value"),
+            ("{} x", 3, "unexpected text after the value"),
+        ];
+        for &(text, offset, message) in cases.iter() {
+            assert_eq!(
+                error(text),
+                JsonError {
+                    offset,
+                    message: message.to_string()
+                },
+                "{:?}",
+                text
+            );
+        }
+        let deep = "[".repeat(MAX_DEPTH + 1);
+        assert_eq!(error(&deep).message, "nested too deeply");
+        assert!(parse(&format!(
+            "{}{}",
+            "[".repeat(MAX_DEPTH),
+            "]".repeat(MAX_DEPTH)
+        ))
+        .is_ok());
+    }
+}
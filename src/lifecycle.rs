@@ -0,0 +1,315 @@
+// The account lifecycle of a `User`, as a state machine that only allows legal moves:
+//
+//   PendingVerification -> Active
+//   Active              -> Suspended, Deactivated
+//   Suspended           -> Active, Suspended (to change the end date)
+//   Deactivated         -> Active
+//   any but Deleted     -> Deleted
+//
+// Deleted is a tombstone, nothing leaves it. Every move is kept with its reason and time.
+use crate::structs::User;
+use std::fmt;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccountState {
+    PendingVerification,
+    Active,
+    // `None` means until further notice
+    Suspended { until: Option<SystemTime> },
+    // closed by the user, who may come back
+    Deactivated,
+    Deleted,
+}
+
+impl AccountState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccountState::PendingVerification => "pending_verification",
+            AccountState::Active => "active",
+            AccountState::Suspended { .. } => "suspended",
+            AccountState::Deactivated => "deactivated",
+            AccountState::Deleted => "deleted",
+        }
+    }
+
+    pub fn can_become(&self, to: &AccountState) -> bool {
+        use AccountState::*;
+        matches!(
+            (self, to),
+            (PendingVerification, Active)
+                | (Active, Suspended { .. })
+                | (Active, Deactivated)
+                | (Suspended { .. }, Active)
+                | (Suspended { .. }, Suspended { .. })
+                | (Deactivated, Active)
+                | (PendingVerification, Deleted)
+                | (Active, Deleted)
+                | (Suspended { .. }, Deleted)
+                | (Deactivated, Deleted)
+        )
+    }
+}
+
+impl fmt::Display for AccountState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    pub from: AccountState,
+    pub to: AccountState,
+    pub reason: String,
+    pub at: SystemTime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LifecycleError {
+    IllegalTransition {
+        from: AccountState,
+        to: AccountState,
+    },
+    // a suspension has to end after it starts
+    SuspensionEndsInPast,
+}
+
+impl fmt::Display for LifecycleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LifecycleError::IllegalTransition { from, to } => {
+                write!(f, "an account cannot go from {} to {}", from, to)
+            }
+            LifecycleError::SuspensionEndsInPast => write!(f, "suspension ends in the past"),
+        }
+    }
+}
+
+impl std::error::Error for LifecycleError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lifecycle {
+    state: AccountState,
+    history: Vec<Transition>,
+}
+
+impl Lifecycle {
+    // starts somewhere without a recorded transition, e.g. when an account is first created
+    pub fn new(state: AccountState) -> Lifecycle {
+        Lifecycle {
+            state,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn active() -> Lifecycle {
+        Lifecycle::new(AccountState::Active)
+    }
+
+    pub fn pending() -> Lifecycle {
+        Lifecycle::new(AccountState::PendingVerification)
+    }
+
+    // puts back a lifecycle that was saved earlier, without checking the history
+    pub fn restore(state: AccountState, history: Vec<Transition>) -> Lifecycle {
+        Lifecycle { state, history }
+    }
+
+    pub fn state(&self) -> AccountState {
+        self.state
+    }
+
+    // oldest first
+    pub fn history(&self) -> &[Transition] {
+        &self.history
+    }
+
+    pub fn transition(
+        &mut self,
+        to: AccountState,
+        reason: &str,
+        at: SystemTime,
+    ) -> Result<(), LifecycleError> {
+        if !self.state.can_become(&to) {
+            return Err(LifecycleError::IllegalTransition {
+                from: self.state,
+                to,
+            });
+        }
+        if let AccountState::Suspended { until: Some(until) } = to {
+            if until <= at {
+                return Err(LifecycleError::SuspensionEndsInPast);
+            }
+        }
+        self.history.push(Transition {
+            from: self.state,
+            to,
+            reason: reason.to_string(),
+            at,
+        });
+        self.state = to;
+        Ok(())
+    }
+
+    pub fn activate(&mut self, reason: &str, at: SystemTime) -> Result<(), LifecycleError> {
+        self.transition(AccountState::Active, reason, at)
+    }
+
+    pub fn suspend(
+        &mut self,
+        until: Option<SystemTime>,
+        reason: &str,
+        at: SystemTime,
+    ) -> Result<(), LifecycleError> {
+        self.transition(AccountState::Suspended { until }, reason, at)
+    }
+
+    pub fn deactivate(&mut self, reason: &str, at: SystemTime) -> Result<(), LifecycleError> {
+        self.transition(AccountState::Deactivated, reason, at)
+    }
+
+    pub fn delete(&mut self, reason: &str, at: SystemTime) -> Result<(), LifecycleError> {
+        self.transition(AccountState::Deleted, reason, at)
+    }
+
+    // Ends a suspension whose end date has passed, recorded at the end date.
+    // Returns whether anything changed.
+    pub fn expire_suspension(&mut self, now: SystemTime) -> bool {
+        match self.state {
+            AccountState::Suspended { until: Some(until) } if until <= now => self
+                .transition(AccountState::Active, "suspension expired", until)
+                .is_ok(),
+            _ => false,
+        }
+    }
+
+    // Active, or suspended with the suspension already over
+    pub fn is_active_at(&self, now: SystemTime) -> bool {
+        match self.state {
+            AccountState::Active => true,
+            AccountState::Suspended { until: Some(until) } => until <= now,
+            _ => false,
+        }
+    }
+}
+
+impl User {
+    pub fn is_active(&self) -> bool {
+        self.lifecycle.state() == AccountState::Active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lifecycle::{AccountState, Lifecycle, LifecycleError, Transition};
+    use crate::structs::build_user;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_happy_path_is_recorded() {
+        let mut lifecycle = Lifecycle::pending();
+        lifecycle.activate("email verified", at(10)).unwrap();
+        lifecycle.deactivate("closed by user", at(20)).unwrap();
+        lifecycle.activate("came back", at(30)).unwrap();
+        lifecycle.delete("erasure request", at(40)).unwrap();
+        assert_eq!(lifecycle.state(), AccountState::Deleted);
+        assert_eq!(lifecycle.history().len(), 4);
+        assert_eq!(
+            lifecycle.history()[0],
+            Transition {
+                from: AccountState::PendingVerification,
+                to: AccountState::Active,
+                reason: "email verified".to_string(),
+                at: at(10),
+            }
+        );
+    }
+
+    #[test]
+    fn test_illegal_transitions() {
+        let mut lifecycle = Lifecycle::pending();
+        assert_eq!(
+            lifecycle.suspend(None, "spam", at(1)),
+            Err(LifecycleError::IllegalTransition {
+                from: AccountState::PendingVerification,
+                to: AccountState::Suspended { until: None },
+            })
+        );
+        assert!(lifecycle.deactivate("bye", at(1)).is_err());
+        lifecycle.delete("never verified", at(2)).unwrap();
+        // nothing comes back from the tombstone
+        for &to in [
+            AccountState::Active,
+            AccountState::PendingVerification,
+            AccountState::Deactivated,
+            AccountState::Deleted,
+        ]
+        .iter()
+        {
+            assert!(lifecycle.transition(to, "undo", at(3)).is_err());
+        }
+        // failed moves leave no trace
+        assert_eq!(lifecycle.history().len(), 1);
+    }
+
+    #[test]
+    fn test_same_state_is_not_a_transition() {
+        let mut lifecycle = Lifecycle::active();
+        assert!(lifecycle.activate("again", at(1)).is_err());
+        let mut lifecycle = Lifecycle::new(AccountState::Deactivated);
+        assert!(lifecycle.deactivate("again", at(1)).is_err());
+    }
+
+    #[test]
+    fn test_suspension() {
+        let mut lifecycle = Lifecycle::active();
+        assert_eq!(
+            lifecycle.suspend(Some(at(5)), "abuse", at(10)),
+            Err(LifecycleError::SuspensionEndsInPast)
+        );
+        lifecycle.suspend(Some(at(100)), "abuse", at(10)).unwrap();
+        assert!(!lifecycle.is_active_at(at(50)));
+        assert!(lifecycle.is_active_at(at(100)));
+        // extended before it ran out
+        lifecycle
+            .suspend(Some(at(200)), "more abuse", at(50))
+            .unwrap();
+        assert!(!lifecycle.expire_suspension(at(150)));
+        assert!(lifecycle.expire_suspension(at(250)));
+        assert_eq!(lifecycle.state(), AccountState::Active);
+        let last = lifecycle.history().last().unwrap();
+        assert_eq!(last.reason, "suspension expired");
+        assert_eq!(last.at, at(200));
+        // indefinite suspensions never expire on their own
+        lifecycle.suspend(None, "ban", at(300)).unwrap();
+        assert!(!lifecycle.expire_suspension(at(u32::MAX as u64)));
+        assert!(!lifecycle.is_active_at(at(u32::MAX as u64)));
+    }
+
+    #[test]
+    fn test_user_is_active() {
+        let mut ray = build_user("ray@example.com".to_string(), "Ray".to_string());
+        assert!(!ray.is_active());
+        ray.lifecycle.activate("email verified", at(1)).unwrap();
+        assert!(ray.is_active());
+        ray.lifecycle.suspend(None, "abuse", at(1)).unwrap();
+        assert!(!ray.is_active());
+    }
+
+    #[test]
+    fn test_error_message() {
+        let error = LifecycleError::IllegalTransition {
+            from: AccountState::Deleted,
+            to: AccountState::Active,
+        };
+        assert_eq!(
+            error.to_string(),
+            "an account cannot go from deleted to active"
+        );
+    }
+}
@@ -1,11 +1,92 @@
+mod anonymise;
+mod base64;
+mod clock;
+mod color_blindness;
+mod colors;
 mod comments;
+mod contrast;
 mod control_flows;
+mod csv;
 mod data_types;
 mod functions;
+mod gradients;
+mod http_api;
+mod json;
+mod lifecycle;
 mod ownership;
+mod password;
+mod random;
+mod rate_limit;
+mod rbac;
+mod sessions;
+mod sha256;
+mod sign_in;
 mod structs;
+mod text_metrics;
+mod tokenizer;
+mod unicode_tables;
+mod user_builder;
+mod user_csv;
+mod user_events;
+mod user_query;
+mod user_record;
+mod user_repository;
+mod user_shards;
+mod user_store;
+mod user_validation;
+mod usernames;
 mod variables;
+mod verification;
+mod words;
+
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::Arc;
+
+// cargo run -- contrast <theme-file> [aa|aaa]
+fn run_contrast(args: &[String]) -> Result<bool, String> {
+    let path = args
+        .first()
+        .ok_or("usage: contrast <theme-file> [aa|aaa]")?;
+    let target = match args.get(1).map(String::as_str) {
+        None | Some("aa") => contrast::AA_NORMAL,
+        Some("aaa") => contrast::AAA_NORMAL,
+        Some(other) => return Err(format!("unknown level `{}`, expected aa or aaa", other)),
+    };
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let pairs = contrast::parse_theme(&text).map_err(|e| format!("{}: {}", path, e))?;
+    let (report, all_pass) = contrast::audit_theme(&pairs, target);
+    print!("{}", report);
+    Ok(all_pass)
+}
+
+// cargo run -- serve [address], on 127.0.0.1:8080 by default
+fn run_serve(args: &[String]) -> Result<(), String> {
+    let addr = args.first().map(String::as_str).unwrap_or("127.0.0.1:8080");
+    let store = Arc::new(user_shards::ShardedUserStore::new());
+    let server = http_api::Server::bind(addr, store).map_err(|e| format!("{}: {}", addr, e))?;
+    println!("listening on http://{}", addr);
+    server.serve().map_err(|e| e.to_string())
+}
 
 fn main() {
-    println!("Rust Practice!");
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("contrast") => match run_contrast(&args[1..]) {
+            Ok(true) => {}
+            Ok(false) => process::exit(1),
+            Err(message) => {
+                eprintln!("{}", message);
+                process::exit(2);
+            }
+        },
+        Some("serve") => {
+            if let Err(message) = run_serve(&args[1..]) {
+                eprintln!("{}", message);
+                process::exit(2);
+            }
+        }
+        _ => println!("Rust Practice!"),
+    }
 }
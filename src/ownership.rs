@@ -28,6 +28,7 @@ fn takes_and_gives_back(a_string: String) -> String {
     // a_string is returned and moves out to the calling function
 }
 
+// the length in bytes, `text_metrics::measure` counts characters and columns too
 fn calculate_length(s: String) -> (String, usize) {
     let length = s.len();
 
@@ -47,6 +48,7 @@ fn change(some_string: &mut String) {
 }
 
 // &str allows us to use the same function on both &String values and &str values.
+// It only splits on ASCII spaces, `words::first_word` handles any text.
 fn first_world(s: &str) -> &str {
     let bytes = s.as_bytes();
     // enumerate method returns a tuple, we can use patterns to destructure that tuple
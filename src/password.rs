@@ -0,0 +1,270 @@
+// Salted PBKDF2-HMAC-SHA256 password hashes, stored on `User` as PHC strings:
+// $pbkdf2-sha256$i=<iterations>$<salt>$<hash>, salt and hash in unpadded standard base64.
+use crate::base64;
+use crate::random::random_bytes;
+use crate::sha256::{constant_time_eq, pbkdf2_hmac_sha256};
+use crate::structs::User;
+use std::fmt;
+use std::io;
+
+const ALGORITHM: &str = "pbkdf2-sha256";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PasswordParams {
+    pub iterations: u32,
+    pub salt_len: usize,
+    pub hash_len: usize,
+}
+
+impl Default for PasswordParams {
+    // OWASP's 2023 recommendation for PBKDF2-HMAC-SHA256
+    fn default() -> PasswordParams {
+        PasswordParams {
+            iterations: 600_000,
+            salt_len: 16,
+            hash_len: 32,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PasswordError {
+    // the stored string is not a PHC string we can read
+    Malformed,
+    UnsupportedAlgorithm(String),
+    // the user has no password set
+    NotSet,
+    Random(io::Error),
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PasswordError::Malformed => write!(f, "malformed password hash"),
+            PasswordError::UnsupportedAlgorithm(name) => {
+                write!(f, "unsupported password hash algorithm {:?}", name)
+            }
+            PasswordError::NotSet => write!(f, "no password is set"),
+            PasswordError::Random(e) => write!(f, "could not generate a salt: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PasswordError {}
+
+// a parsed PHC string
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordHash {
+    pub iterations: u32,
+    pub salt: Vec<u8>,
+    pub hash: Vec<u8>,
+}
+
+impl PasswordHash {
+    pub fn parse(phc: &str) -> Result<PasswordHash, PasswordError> {
+        let parts: Vec<&str> = phc.split('$').collect();
+        // the string starts with `$`, so the first part is empty
+        if parts.len() != 5 || !parts[0].is_empty() {
+            return Err(PasswordError::Malformed);
+        }
+        if parts[1] != ALGORITHM {
+            return Err(PasswordError::UnsupportedAlgorithm(parts[1].to_string()));
+        }
+        let iterations = parts[2]
+            .strip_prefix("i=")
+            .and_then(|i| i.parse::<u32>().ok())
+            .filter(|&i| i > 0)
+            .ok_or(PasswordError::Malformed)?;
+        let salt = base64::decode(parts[3]).ok_or(PasswordError::Malformed)?;
+        let hash = base64::decode(parts[4]).ok_or(PasswordError::Malformed)?;
+        if hash.is_empty() {
+            return Err(PasswordError::Malformed);
+        }
+        Ok(PasswordHash {
+            iterations,
+            salt,
+            hash,
+        })
+    }
+
+    pub fn derive(password: &str, salt: Vec<u8>, iterations: u32, hash_len: usize) -> PasswordHash {
+        let mut hash = vec![0u8; hash_len];
+        pbkdf2_hmac_sha256(password.as_bytes(), &salt, iterations, &mut hash);
+        PasswordHash {
+            iterations,
+            salt,
+            hash,
+        }
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        let candidate = PasswordHash::derive(
+            password,
+            self.salt.clone(),
+            self.iterations,
+            self.hash.len(),
+        );
+        constant_time_eq(&candidate.hash, &self.hash)
+    }
+
+    // true when the hash was made with weaker or different settings than `params`
+    pub fn needs_rehash(&self, params: &PasswordParams) -> bool {
+        self.iterations < params.iterations
+            || self.salt.len() != params.salt_len
+            || self.hash.len() != params.hash_len
+    }
+}
+
+impl fmt::Display for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "${}$i={}${}${}",
+            ALGORITHM,
+            self.iterations,
+            base64::encode(&self.salt),
+            base64::encode(&self.hash)
+        )
+    }
+}
+
+pub fn hash_password(password: &str, params: &PasswordParams) -> Result<String, PasswordError> {
+    let salt = random_bytes(params.salt_len).map_err(PasswordError::Random)?;
+    Ok(PasswordHash::derive(password, salt, params.iterations, params.hash_len).to_string())
+}
+
+// a malformed or foreign hash never verifies
+pub fn verify_password(password: &str, phc: &str) -> bool {
+    match PasswordHash::parse(phc) {
+        Ok(hash) => hash.verify(password),
+        Err(_) => false,
+    }
+}
+
+impl User {
+    pub fn set_password(
+        &mut self,
+        password: &str,
+        params: &PasswordParams,
+    ) -> Result<(), PasswordError> {
+        self.password_hash = Some(hash_password(password, params)?);
+        Ok(())
+    }
+
+    pub fn verify_password(&self, password: &str) -> bool {
+        match &self.password_hash {
+            Some(phc) => verify_password(password, phc),
+            None => false,
+        }
+    }
+
+    // call after a successful `verify_password`, while the plain password is still at hand
+    pub fn password_needs_rehash(&self, params: &PasswordParams) -> Result<bool, PasswordError> {
+        let phc = self.password_hash.as_ref().ok_or(PasswordError::NotSet)?;
+        Ok(PasswordHash::parse(phc)?.needs_rehash(params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::password::{
+        hash_password, verify_password, PasswordError, PasswordHash, PasswordParams,
+    };
+    use crate::structs::build_user;
+
+    // keeps the tests quick, real deployments use the default
+    const FAST: PasswordParams = PasswordParams {
+        iterations: 1_000,
+        salt_len: 16,
+        hash_len: 32,
+    };
+
+    #[test]
+    fn test_phc_format() {
+        // RFC 7914 section 11 vector, truncated to 32 bytes
+        let hash = PasswordHash::derive("passwd", b"salt".to_vec(), 1, 32);
+        let phc = hash.to_string();
+        assert_eq!(
+            phc,
+            "$pbkdf2-sha256$i=1$c2FsdA$VawEblbjCJ/sFpHCJUS2BflBhSFt3gRl5oudV8INrLw"
+        );
+        assert_eq!(PasswordHash::parse(&phc).unwrap(), hash);
+        assert!(verify_password("passwd", &phc));
+        assert!(!verify_password("Passwd", &phc));
+    }
+
+    #[test]
+    fn test_hash_and_verify() {
+        let phc = hash_password("correct horse battery staple", &FAST).unwrap();
+        assert!(phc.starts_with("$pbkdf2-sha256$i=1000$"));
+        assert!(verify_password("correct horse battery staple", &phc));
+        assert!(!verify_password("correct horse battery stapler", &phc));
+        assert!(!verify_password("", &phc));
+    }
+
+    #[test]
+    fn test_salt_is_random() {
+        let a = hash_password("same", &FAST).unwrap();
+        let b = hash_password("same", &FAST).unwrap();
+        assert_ne!(a, b);
+        assert!(verify_password("same", &a) && verify_password("same", &b));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            PasswordHash::parse("plaintext"),
+            Err(PasswordError::Malformed)
+        ));
+        assert!(matches!(
+            PasswordHash::parse("$pbkdf2-sha256$i=0$c2FsdA$VawE"),
+            Err(PasswordError::Malformed)
+        ));
+        assert!(matches!(
+            PasswordHash::parse("$pbkdf2-sha256$i=1$c2FsdA$not base64"),
+            Err(PasswordError::Malformed)
+        ));
+        assert!(matches!(
+            PasswordHash::parse("$argon2id$v=19$c2FsdA$VawE"),
+            Err(PasswordError::UnsupportedAlgorithm(ref name)) if name == "argon2id"
+        ));
+        assert!(!verify_password("anything", "$argon2id$v=19$c2FsdA$VawE"));
+    }
+
+    #[test]
+    fn test_needs_rehash() {
+        let hash = PasswordHash::parse(&hash_password("pw", &FAST).unwrap()).unwrap();
+        assert!(!hash.needs_rehash(&FAST));
+        assert!(hash.needs_rehash(&PasswordParams::default()));
+        assert!(hash.needs_rehash(&PasswordParams {
+            hash_len: 64,
+            ..FAST
+        }));
+        // fewer iterations than we already have is not a reason to rehash
+        assert!(!hash.needs_rehash(&PasswordParams {
+            iterations: 10,
+            ..FAST
+        }));
+    }
+
+    #[test]
+    fn test_user_password() {
+        let mut ray = build_user("ray@example.com".to_string(), "Ray".to_string());
+        assert!(!ray.verify_password(""));
+        assert!(matches!(
+            ray.password_needs_rehash(&FAST),
+            Err(PasswordError::NotSet)
+        ));
+        ray.set_password("hunter2", &FAST).unwrap();
+        assert!(ray.verify_password("hunter2"));
+        assert!(!ray.verify_password("hunter3"));
+        assert!(!ray.password_needs_rehash(&FAST).unwrap());
+        let stronger = PasswordParams {
+            iterations: 2_000,
+            ..FAST
+        };
+        assert!(ray.password_needs_rehash(&stronger).unwrap());
+        ray.set_password("hunter2", &stronger).unwrap();
+        assert!(!ray.password_needs_rehash(&stronger).unwrap());
+    }
+}
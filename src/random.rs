@@ -0,0 +1,29 @@
+// Cryptographically secure random bytes from the operating system.
+// The standard library has no CSPRNG of its own, so on Unix we read the kernel's.
+use std::fs::File;
+use std::io::{self, Read};
+
+pub fn fill_random(buf: &mut [u8]) -> io::Result<()> {
+    File::open("/dev/urandom")?.read_exact(buf)
+}
+
+pub fn random_bytes(len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    fill_random(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::random_bytes;
+
+    #[test]
+    fn test_random_bytes() {
+        let a = random_bytes(32).unwrap();
+        let b = random_bytes(32).unwrap();
+        assert_eq!(a.len(), 32);
+        // a collision here would mean something is badly wrong
+        assert_ne!(a, b);
+        assert!(random_bytes(0).unwrap().is_empty());
+    }
+}
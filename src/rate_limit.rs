@@ -0,0 +1,649 @@
+// Rate limits keyed by a string, usually a client address or a username. The token bucket
+// allows bursts and refills at a steady rate, the sliding window log allows at most `limit`
+// hits in any `window`. Both keep at most `max_keys` keys and forget the least recently used
+// one to make room, which lets that key start over, so size them above the number of keys
+// you expect to be busy at once.
+use crate::clock::Clock;
+use crate::user_repository::username_key;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::net::{IpAddr, Ipv6Addr};
+use std::time::{Duration, SystemTime};
+
+pub trait RateLimiter {
+    // Takes one hit for `key`, false when the key is over its limit. A refused hit isn't
+    // counted.
+    fn allow(&mut self, key: &str) -> bool;
+
+    // how long until `key` would be allowed again, `None` if it would be allowed now
+    fn retry_after(&self, key: &str) -> Option<Duration>;
+}
+
+// how far `later` is after `earlier`, zero if it isn't (the clock may go backwards)
+fn elapsed(earlier: SystemTime, later: SystemTime) -> Duration {
+    later.duration_since(earlier).unwrap_or_default()
+}
+
+struct Entry<S> {
+    state: S,
+    // position in `recency`
+    used: u64,
+}
+
+// per key state, bounded to `max_keys` keys by dropping the least recently used
+struct Keys<S> {
+    max_keys: usize,
+    entries: HashMap<String, Entry<S>>,
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl<S> Keys<S> {
+    fn new(max_keys: usize) -> Keys<S> {
+        Keys {
+            max_keys: max_keys.max(1),
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&S> {
+        self.entries.get(key).map(|entry| &entry.state)
+    }
+
+    // the state for `key`, marked as just used and made with `fresh` if there is none
+    fn touch(&mut self, key: &str, fresh: impl FnOnce() -> S) -> &mut S {
+        self.tick += 1;
+        let used = self.tick;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.used);
+            self.recency.insert(used, key.to_string());
+            entry.used = used;
+            return &mut self.entries.get_mut(key).unwrap().state;
+        }
+        while self.entries.len() >= self.max_keys {
+            let oldest = match self.recency.values().next() {
+                Some(oldest) => oldest.clone(),
+                None => break,
+            };
+            self.remove(&oldest);
+        }
+        self.recency.insert(used, key.to_string());
+        let entry = self.entries.entry(key.to_string()).or_insert(Entry {
+            state: fresh(),
+            used,
+        });
+        &mut entry.state
+    }
+
+    // marks `key` as just used, if it's there
+    fn bump(&mut self, key: &str) {
+        if self.entries.contains_key(key) {
+            self.touch(key, || unreachable!());
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.used);
+        }
+    }
+
+    // drops every key for which `idle` holds, returns how many went
+    fn purge(&mut self, idle: impl Fn(&S) -> bool) -> usize {
+        let keys: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| idle(&entry.state))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &keys {
+            self.remove(key);
+        }
+        keys.len()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenBucketPolicy {
+    // the most hits allowed in a burst
+    pub capacity: u32,
+    // one token comes back every `refill`
+    pub refill: Duration,
+    pub max_keys: usize,
+}
+
+impl Default for TokenBucketPolicy {
+    fn default() -> TokenBucketPolicy {
+        TokenBucketPolicy {
+            capacity: 20,
+            refill: Duration::from_secs(6),
+            max_keys: 100_000,
+        }
+    }
+}
+
+// Instead of a token count each key keeps the time its bucket is full again, which needs
+// no refill step and stays exact. A full bucket is one whose time has passed.
+pub struct TokenBucket<C: Clock> {
+    clock: C,
+    policy: TokenBucketPolicy,
+    keys: Keys<SystemTime>,
+}
+
+impl<C: Clock> TokenBucket<C> {
+    pub fn new(clock: C, policy: TokenBucketPolicy) -> TokenBucket<C> {
+        let keys = Keys::new(policy.max_keys);
+        TokenBucket {
+            clock,
+            policy,
+            keys,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.entries.is_empty()
+    }
+
+    // tokens left for `key` right now
+    pub fn tokens(&self, key: &str) -> u32 {
+        let capacity = self.policy.capacity;
+        let full_at = match self.keys.get(key) {
+            Some(&full_at) => full_at,
+            None => return capacity,
+        };
+        let missing = elapsed(self.clock.now(), full_at).as_nanos();
+        let refill = self.policy.refill.as_nanos().max(1);
+        // a token that's partly back doesn't count yet
+        let missing = missing.div_ceil(refill);
+        capacity.saturating_sub(missing.min(u128::from(capacity)) as u32)
+    }
+
+    // how far into the future the bucket may be emptied, a hit needs one token left after it
+    fn headroom(&self) -> Duration {
+        self.policy.refill * self.policy.capacity.saturating_sub(1)
+    }
+
+    fn wait(&self, now: SystemTime, full_at: SystemTime) -> Option<Duration> {
+        if self.policy.capacity == 0 {
+            // nothing ever gets through, the best answer is one refill
+            return Some(self.policy.refill);
+        }
+        let missing = elapsed(now, full_at);
+        let headroom = self.headroom();
+        if missing > headroom {
+            Some(missing - headroom)
+        } else {
+            None
+        }
+    }
+
+    // forgets keys whose bucket is full again, returns how many
+    pub fn purge(&mut self) -> usize {
+        let now = self.clock.now();
+        self.keys.purge(|&full_at| full_at <= now)
+    }
+}
+
+impl<C: Clock> RateLimiter for TokenBucket<C> {
+    fn allow(&mut self, key: &str) -> bool {
+        if self.retry_after(key).is_some() {
+            // a key that keeps getting refused is busy, not one to forget
+            self.keys.bump(key);
+            return false;
+        }
+        let now = self.clock.now();
+        let refill = self.policy.refill;
+        let full_at = self.keys.touch(key, || now);
+        *full_at = (*full_at).max(now) + refill;
+        true
+    }
+
+    fn retry_after(&self, key: &str) -> Option<Duration> {
+        let now = self.clock.now();
+        let full_at = self.keys.get(key).copied().unwrap_or(now);
+        self.wait(now, full_at)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlidingWindowPolicy {
+    // at most this many hits in any `window`
+    pub limit: usize,
+    pub window: Duration,
+    pub max_keys: usize,
+}
+
+impl Default for SlidingWindowPolicy {
+    fn default() -> SlidingWindowPolicy {
+        SlidingWindowPolicy {
+            limit: 10,
+            window: Duration::from_secs(15 * 60),
+            max_keys: 100_000,
+        }
+    }
+}
+
+// Keeps the time of every allowed hit still inside the window, so a key never holds more
+// than `limit` of them. Exact, at the cost of that memory.
+pub struct SlidingWindowLog<C: Clock> {
+    clock: C,
+    policy: SlidingWindowPolicy,
+    keys: Keys<VecDeque<SystemTime>>,
+}
+
+impl<C: Clock> SlidingWindowLog<C> {
+    pub fn new(clock: C, policy: SlidingWindowPolicy) -> SlidingWindowLog<C> {
+        let keys = Keys::new(policy.max_keys);
+        SlidingWindowLog {
+            clock,
+            policy,
+            keys,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.entries.is_empty()
+    }
+
+    fn in_window(&self, now: SystemTime, at: SystemTime) -> bool {
+        elapsed(at, now) < self.policy.window
+    }
+
+    // hits counted against `key` right now
+    pub fn hits(&self, key: &str) -> usize {
+        let now = self.clock.now();
+        match self.keys.get(key) {
+            Some(log) => log.iter().filter(|&&at| self.in_window(now, at)).count(),
+            None => 0,
+        }
+    }
+
+    // forgets keys with nothing left in the window, returns how many
+    pub fn purge(&mut self) -> usize {
+        let now = self.clock.now();
+        let window = self.policy.window;
+        self.keys
+            .purge(|log| log.iter().all(|&at| elapsed(at, now) >= window))
+    }
+}
+
+impl<C: Clock> RateLimiter for SlidingWindowLog<C> {
+    fn allow(&mut self, key: &str) -> bool {
+        if self.retry_after(key).is_some() {
+            // a key that keeps getting refused is busy, not one to forget
+            self.keys.bump(key);
+            return false;
+        }
+        let now = self.clock.now();
+        let window = self.policy.window;
+        let limit = self.policy.limit;
+        let log = self.keys.touch(key, VecDeque::new);
+        while let Some(&oldest) = log.front() {
+            if elapsed(oldest, now) < window {
+                break;
+            }
+            log.pop_front();
+        }
+        log.push_back(now);
+        while log.len() > limit {
+            log.pop_front();
+        }
+        true
+    }
+
+    fn retry_after(&self, key: &str) -> Option<Duration> {
+        if self.policy.limit == 0 {
+            return Some(self.policy.window);
+        }
+        let now = self.clock.now();
+        let log = self.keys.get(key)?;
+        let live: Vec<SystemTime> = log
+            .iter()
+            .copied()
+            .filter(|&at| self.in_window(now, at))
+            .collect();
+        if live.len() < self.policy.limit {
+            return None;
+        }
+        // wait for enough of the oldest hits to leave the window
+        let leaving = live[live.len() - self.policy.limit];
+        Some(self.policy.window - elapsed(leaving, now))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimited {
+    pub retry_after: Duration,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "too many sign-in attempts, retry after {}s",
+            // round up, "retry after 0s" would be a lie
+            self.retry_after.as_secs() + u64::from(self.retry_after.subsec_nanos() > 0)
+        )
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+// The key a client address is limited under: an IPv4 address as it is, an IPv6 one by its /64
+// prefix, since a single host is usually handed a whole /64 and can pick any address in it.
+// IPv4 addresses mapped into IPv6 count as the IPv4 address.
+pub fn address_key(ip: IpAddr) -> String {
+    let v6 = match ip {
+        IpAddr::V4(v4) => return v4.to_string(),
+        IpAddr::V6(v6) => v6,
+    };
+    if let Some(v4) = v6.to_ipv4_mapped() {
+        return v4.to_string();
+    }
+    let prefix = Ipv6Addr::from(u128::from(v6) & !(u128::MAX >> 64));
+    format!("{}/64", prefix)
+}
+
+// Limits sign-in attempts per client address and per username, so neither one address trying
+// many accounts nor many addresses trying one account get far. Sits in front of
+// `SignInTracker`, which locks accounts on failures; this limits attempts of any outcome.
+pub struct SignInLimiter<I: RateLimiter, U: RateLimiter> {
+    pub per_ip: I,
+    pub per_username: U,
+}
+
+impl<I: RateLimiter, U: RateLimiter> SignInLimiter<I, U> {
+    pub fn new(per_ip: I, per_username: U) -> SignInLimiter<I, U> {
+        SignInLimiter {
+            per_ip,
+            per_username,
+        }
+    }
+
+    // the longer of the two waits, if either limit is used up
+    pub fn check(&self, ip: IpAddr, username: &str) -> Result<(), RateLimited> {
+        let wait = self
+            .per_ip
+            .retry_after(&address_key(ip))
+            .max(self.per_username.retry_after(&username_key(username)));
+        match wait {
+            Some(retry_after) => Err(RateLimited { retry_after }),
+            None => Ok(()),
+        }
+    }
+
+    // Counts an attempt against both limits, or against neither when either is used up, so
+    // a blocked address doesn't also use up the account's allowance.
+    pub fn attempt(&mut self, ip: IpAddr, username: &str) -> Result<(), RateLimited> {
+        self.check(ip, username)?;
+        self.per_ip.allow(&address_key(ip));
+        self.per_username.allow(&username_key(username));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::ManualClock;
+    use crate::rate_limit::{
+        address_key, RateLimited, RateLimiter, SignInLimiter, SlidingWindowLog,
+        SlidingWindowPolicy, TokenBucket, TokenBucketPolicy,
+    };
+    use std::net::IpAddr;
+    use std::time::Duration;
+
+    fn bucket_policy(capacity: u32, refill_secs: u64) -> TokenBucketPolicy {
+        TokenBucketPolicy {
+            capacity,
+            refill: Duration::from_secs(refill_secs),
+            max_keys: 100,
+        }
+    }
+
+    fn window_policy(limit: usize, window_secs: u64) -> SlidingWindowPolicy {
+        SlidingWindowPolicy {
+            limit,
+            window: Duration::from_secs(window_secs),
+            max_keys: 100,
+        }
+    }
+
+    #[test]
+    fn test_token_bucket_bursts_then_refills() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut bucket = TokenBucket::new(&clock, bucket_policy(3, 10));
+        assert_eq!(bucket.tokens("a"), 3);
+        assert!(bucket.allow("a"));
+        assert!(bucket.allow("a"));
+        assert!(bucket.allow("a"));
+        assert_eq!(bucket.tokens("a"), 0);
+        assert!(!bucket.allow("a"));
+        assert_eq!(bucket.retry_after("a"), Some(Duration::from_secs(10)));
+        // other keys have their own bucket
+        assert!(bucket.allow("b"));
+
+        clock.advance(Duration::from_secs(4));
+        assert_eq!(bucket.retry_after("a"), Some(Duration::from_secs(6)));
+        assert!(!bucket.allow("a"));
+        clock.advance(Duration::from_secs(6));
+        assert_eq!(bucket.retry_after("a"), None);
+        assert!(bucket.allow("a"));
+        assert!(!bucket.allow("a"));
+
+        // tokens never pile up beyond the capacity
+        clock.advance(Duration::from_secs(3_600));
+        assert_eq!(bucket.tokens("a"), 3);
+        for _ in 0..3 {
+            assert!(bucket.allow("a"));
+        }
+        assert!(!bucket.allow("a"));
+    }
+
+    #[test]
+    fn test_token_bucket_refused_hits_are_free() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut bucket = TokenBucket::new(&clock, bucket_policy(1, 10));
+        assert!(bucket.allow("a"));
+        for _ in 0..50 {
+            assert!(!bucket.allow("a"));
+        }
+        clock.advance(Duration::from_secs(10));
+        assert!(bucket.allow("a"));
+    }
+
+    #[test]
+    fn test_sliding_window_log() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut log = SlidingWindowLog::new(&clock, window_policy(3, 60));
+        assert!(log.allow("a"));
+        clock.advance(Duration::from_secs(10));
+        assert!(log.allow("a"));
+        assert!(log.allow("a"));
+        assert_eq!(log.hits("a"), 3);
+        assert!(!log.allow("a"));
+        // the first hit leaves the window 60s after it was made
+        assert_eq!(log.retry_after("a"), Some(Duration::from_secs(50)));
+
+        clock.advance(Duration::from_secs(50));
+        assert_eq!(log.hits("a"), 2);
+        assert!(log.allow("a"));
+        assert!(!log.allow("a"));
+        // the next two both went in at 1_010
+        assert_eq!(log.retry_after("a"), Some(Duration::from_secs(10)));
+        assert_eq!(log.retry_after("b"), None);
+    }
+
+    #[test]
+    fn test_keys_are_bounded() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut bucket = TokenBucket::new(
+            &clock,
+            TokenBucketPolicy {
+                max_keys: 3,
+                ..bucket_policy(1, 60)
+            },
+        );
+        for key in &["a", "b", "c"] {
+            assert!(bucket.allow(key));
+        }
+        // a refused hit still keeps "a", "b" is now the least recently used
+        assert!(!bucket.allow("a"));
+        assert!(bucket.allow("d"));
+        assert_eq!(bucket.len(), 3);
+        assert!(bucket.allow("b"));
+        assert!(!bucket.allow("a"));
+
+        let mut log = SlidingWindowLog::new(
+            &clock,
+            SlidingWindowPolicy {
+                max_keys: 2,
+                ..window_policy(2, 60)
+            },
+        );
+        for i in 0..1_000 {
+            log.allow(&i.to_string());
+            log.allow("busy");
+        }
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.hits("busy"), 2);
+    }
+
+    #[test]
+    fn test_purge_forgets_idle_keys() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut bucket = TokenBucket::new(&clock, bucket_policy(2, 10));
+        let mut log = SlidingWindowLog::new(&clock, window_policy(2, 30));
+        bucket.allow("a");
+        log.allow("a");
+        clock.advance(Duration::from_secs(5));
+        bucket.allow("b");
+        log.allow("b");
+        assert_eq!(bucket.purge(), 0);
+        assert_eq!(log.purge(), 0);
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(bucket.purge(), 1);
+        assert_eq!(bucket.len(), 1);
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(log.purge(), 1);
+        assert_eq!(log.hits("b"), 1);
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(bucket.purge(), 1);
+        assert_eq!(log.purge(), 1);
+        assert!(bucket.is_empty());
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn test_zero_limits_refuse_everything() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut bucket = TokenBucket::new(&clock, bucket_policy(0, 10));
+        let mut log = SlidingWindowLog::new(&clock, window_policy(0, 30));
+        assert!(!bucket.allow("a"));
+        assert!(!log.allow("a"));
+        assert_eq!(bucket.retry_after("a"), Some(Duration::from_secs(10)));
+        assert_eq!(log.retry_after("a"), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_clock_going_backwards() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut bucket = TokenBucket::new(&clock, bucket_policy(1, 10));
+        let mut log = SlidingWindowLog::new(&clock, window_policy(1, 10));
+        assert!(bucket.allow("a"));
+        assert!(log.allow("a"));
+        clock.set(std::time::UNIX_EPOCH + Duration::from_secs(990));
+        // the hits stay counted until the clock catches up with the window
+        assert!(!bucket.allow("a"));
+        assert!(!log.allow("a"));
+        assert_eq!(bucket.retry_after("a"), Some(Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn test_limiters_as_trait_objects() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut limiters: Vec<Box<dyn RateLimiter + '_>> = vec![
+            Box::new(TokenBucket::new(&clock, bucket_policy(2, 60))),
+            Box::new(SlidingWindowLog::new(&clock, window_policy(2, 60))),
+        ];
+        for limiter in limiters.iter_mut() {
+            assert!(limiter.allow("key"));
+            assert!(limiter.allow("key"));
+            assert!(!limiter.allow("key"));
+            assert_eq!(limiter.retry_after("key"), Some(Duration::from_secs(60)));
+        }
+    }
+
+    #[test]
+    fn test_sign_in_limiter() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut limiter = SignInLimiter::new(
+            TokenBucket::new(&clock, bucket_policy(3, 60)),
+            SlidingWindowLog::new(&clock, window_policy(2, 600)),
+        );
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let other: IpAddr = "2001:db8::1".parse().unwrap();
+
+        assert_eq!(limiter.attempt(ip, "ray"), Ok(()));
+        // usernames are limited by their normalized form
+        assert_eq!(limiter.attempt(other, "RAY"), Ok(()));
+        assert_eq!(
+            limiter.attempt(ip, "Ray"),
+            Err(RateLimited {
+                retry_after: Duration::from_secs(600)
+            })
+        );
+        // the refused attempt didn't use up the address's allowance
+        assert_eq!(limiter.per_ip.tokens(&address_key(ip)), 2);
+        assert_eq!(limiter.attempt(ip, "alice"), Ok(()));
+        assert_eq!(limiter.attempt(ip, "bob"), Ok(()));
+        let limited = limiter.attempt(ip, "carol").unwrap_err();
+        assert_eq!(limited.retry_after, Duration::from_secs(60));
+        assert_eq!(
+            limited.to_string(),
+            "too many sign-in attempts, retry after 60s"
+        );
+        assert_eq!(limiter.per_username.hits("carol"), 0);
+        assert_eq!(limiter.check(other, "carol"), Ok(()));
+    }
+
+    #[test]
+    fn test_address_keys() {
+        let key = |ip: &str| address_key(ip.parse().unwrap());
+        assert_eq!(key("192.0.2.1"), "192.0.2.1");
+        assert_ne!(key("192.0.2.1"), key("192.0.2.2"));
+        assert_eq!(key("2001:db8:1:2:3:4:5:6"), "2001:db8:1:2::/64");
+        assert_eq!(key("2001:db8:1:2::1"), key("2001:db8:1:2:ffff::"));
+        assert_ne!(key("2001:db8:1:2::1"), key("2001:db8:1:3::1"));
+        assert_eq!(key("::ffff:192.0.2.1"), "192.0.2.1");
+    }
+
+    #[test]
+    fn test_sign_in_limiter_groups_ipv6_hosts() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut limiter = SignInLimiter::new(
+            TokenBucket::new(&clock, bucket_policy(2, 60)),
+            SlidingWindowLog::new(&clock, window_policy(10, 600)),
+        );
+        let host = |ip: &str| -> IpAddr { ip.parse().unwrap() };
+        // hopping around one /64 doesn't get a fresh allowance
+        assert_eq!(limiter.attempt(host("2001:db8::1"), "alice"), Ok(()));
+        assert_eq!(limiter.attempt(host("2001:db8::2"), "bob"), Ok(()));
+        assert!(limiter.attempt(host("2001:db8::3"), "carol").is_err());
+        assert_eq!(limiter.attempt(host("2001:db8:0:1::1"), "carol"), Ok(()));
+        // neighbouring IPv4 addresses are limited apart
+        assert_eq!(limiter.attempt(host("192.0.2.1"), "dave"), Ok(()));
+        assert_eq!(limiter.attempt(host("192.0.2.1"), "erin"), Ok(()));
+        assert_eq!(limiter.attempt(host("192.0.2.2"), "frank"), Ok(()));
+    }
+}
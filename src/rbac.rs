@@ -0,0 +1,680 @@
+// Role-based access control for `User`.
+//
+// A policy defines roles. A role may inherit other roles and carries `allow` and `deny`
+// permissions written `action:resource`, where either side is `*`, a prefix ending in `*`, or an
+// exact name. Users hold role names in `User::roles`.
+//
+// Deny overrides allow: one matching deny from any of the user's roles, inherited or not, or from
+// a resource section, wins. Resource sections adjust a single resource (or prefix) for
+// particular roles: they can grant what no role allows or close off what one does, but an
+// allow in them doesn't lift a role's deny.
+//
+// Policies are written in a small subset of TOML:
+//
+//   [role.editor]
+//   inherits = ["viewer"]
+//   allow = ["write:docs/*"]
+//   deny = ["write:docs/archive/*"]
+//
+//   [resource."docs/archive/errata"]
+//   allow = ["editor:write"]
+use crate::structs::User;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern(String);
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Pattern {
+        Pattern(pattern.to_string())
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        match self.0.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => self.0 == name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Permission {
+    pub action: Pattern,
+    pub resource: Pattern,
+}
+
+impl Permission {
+    // `action:resource`, split at the first colon so resources may contain colons
+    pub fn parse(text: &str) -> Option<Permission> {
+        let colon = text.find(':')?;
+        let (action, resource) = (&text[..colon], &text[colon + 1..]);
+        if action.is_empty() || resource.is_empty() {
+            return None;
+        }
+        Some(Permission {
+            action: Pattern::new(action),
+            resource: Pattern::new(resource),
+        })
+    }
+
+    pub fn matches(&self, action: &str, resource: &str) -> bool {
+        self.action.matches(action) && self.resource.matches(resource)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Role {
+    pub name: String,
+    pub inherits: Vec<String>,
+    pub allow: Vec<Permission>,
+    pub deny: Vec<Permission>,
+    // where the role was defined, for error messages
+    line: usize,
+}
+
+// rules for one resource, each entry a role name and the actions it covers
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceOverride {
+    pub resource: Pattern,
+    pub allow: Vec<(String, Pattern)>,
+    pub deny: Vec<(String, Pattern)>,
+    line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Policy {
+    roles: HashMap<String, Role>,
+    overrides: Vec<ResourceOverride>,
+}
+
+enum Section {
+    Role(String),
+    // index into `overrides`
+    Resource(usize),
+}
+
+impl Policy {
+    pub fn parse(text: &str) -> Result<Policy, PolicyError> {
+        let mut policy = Policy::default();
+        let lines: Vec<&str> = text.lines().collect();
+        let mut section = None;
+        let mut seen_keys = HashSet::new();
+        let mut index = 0;
+        while index < lines.len() {
+            let number = index + 1;
+            let line = strip_comment(lines[index]).trim();
+            index += 1;
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| PolicyError {
+                line: number,
+                message,
+            };
+
+            if line.starts_with('[') {
+                let inner = line
+                    .strip_prefix('[')
+                    .and_then(|l| l.strip_suffix(']'))
+                    .ok_or_else(|| error("unterminated section header".to_string()))?;
+                let key = parse_key(inner).map_err(error)?;
+                seen_keys.clear();
+                section = match (key[0].as_str(), key.len()) {
+                    ("role", 2) => {
+                        let name = key[1].clone();
+                        if policy.roles.contains_key(&name) {
+                            return Err(error(format!("role `{}` is defined twice", name)));
+                        }
+                        policy.roles.insert(
+                            name.clone(),
+                            Role {
+                                name: name.clone(),
+                                inherits: Vec::new(),
+                                allow: Vec::new(),
+                                deny: Vec::new(),
+                                line: number,
+                            },
+                        );
+                        Some(Section::Role(name))
+                    }
+                    ("resource", 2) => {
+                        policy.overrides.push(ResourceOverride {
+                            resource: Pattern::new(&key[1]),
+                            allow: Vec::new(),
+                            deny: Vec::new(),
+                            line: number,
+                        });
+                        Some(Section::Resource(policy.overrides.len() - 1))
+                    }
+                    _ => return Err(error(format!("unknown section `[{}]`", inner))),
+                };
+                continue;
+            }
+
+            let eq = line
+                .find('=')
+                .ok_or_else(|| error("expected `key = value`".to_string()))?;
+            let key = line[..eq].trim().to_string();
+            let mut value = line[eq + 1..].trim().to_string();
+            // arrays may span several lines
+            if value.starts_with('[') {
+                while !closes_array(&value) {
+                    let next = lines
+                        .get(index)
+                        .ok_or_else(|| error("unterminated array".to_string()))?;
+                    value.push('\n');
+                    value.push_str(strip_comment(next).trim());
+                    index += 1;
+                }
+            }
+            let items = parse_array(&value).map_err(error)?;
+            if !seen_keys.insert(key.clone()) {
+                return Err(error(format!("`{}` is set twice", key)));
+            }
+
+            match &section {
+                None => return Err(error(format!("`{}` is outside of a section", key))),
+                Some(Section::Role(name)) => {
+                    let role = policy.roles.get_mut(name).unwrap();
+                    match key.as_str() {
+                        "inherits" => role.inherits = items,
+                        "allow" | "deny" => {
+                            let mut permissions = Vec::new();
+                            for item in &items {
+                                permissions.push(Permission::parse(item).ok_or_else(|| {
+                                    error(format!("expected `action:resource`, found `{}`", item))
+                                })?);
+                            }
+                            if key == "allow" {
+                                role.allow = permissions;
+                            } else {
+                                role.deny = permissions;
+                            }
+                        }
+                        _ => return Err(error(format!("unknown role key `{}`", key))),
+                    }
+                }
+                Some(Section::Resource(at)) => {
+                    let entry = &mut policy.overrides[*at];
+                    let mut rules = Vec::new();
+                    for item in &items {
+                        let colon = item
+                            .find(':')
+                            .filter(|&colon| colon > 0 && colon + 1 < item.len())
+                            .ok_or_else(|| {
+                                error(format!("expected `role:action`, found `{}`", item))
+                            })?;
+                        rules.push((item[..colon].to_string(), Pattern::new(&item[colon + 1..])));
+                    }
+                    match key.as_str() {
+                        "allow" => entry.allow = rules,
+                        "deny" => entry.deny = rules,
+                        _ => return Err(error(format!("unknown resource key `{}`", key))),
+                    }
+                }
+            }
+        }
+        policy.check()?;
+        Ok(policy)
+    }
+
+    // every role named anywhere has to exist, and inheritance must not loop
+    fn check(&self) -> Result<(), PolicyError> {
+        let mut roles: Vec<&Role> = self.roles.values().collect();
+        roles.sort_by_key(|role| role.line);
+        for role in &roles {
+            for parent in &role.inherits {
+                if !self.roles.contains_key(parent) {
+                    return Err(PolicyError {
+                        line: role.line,
+                        message: format!("role `{}` inherits unknown role `{}`", role.name, parent),
+                    });
+                }
+            }
+        }
+        let mut done = HashSet::new();
+        for role in &roles {
+            let mut path = Vec::new();
+            if let Err(cycle) = self.visit(&role.name, &mut path, &mut done) {
+                return Err(PolicyError {
+                    line: role.line,
+                    message: format!("roles inherit in a cycle: {}", cycle.join(" -> ")),
+                });
+            }
+        }
+        for entry in &self.overrides {
+            for (name, _) in entry.allow.iter().chain(entry.deny.iter()) {
+                if !self.roles.contains_key(name) {
+                    return Err(PolicyError {
+                        line: entry.line,
+                        message: format!("unknown role `{}`", name),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // depth-first walk up the inheritance, `path` holds the roles being visited
+    fn visit<'a>(
+        &'a self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Result<(), Vec<&'a str>> {
+        if let Some(start) = path.iter().position(|&n| n == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name);
+            return Err(cycle);
+        }
+        if done.contains(name) {
+            return Ok(());
+        }
+        path.push(name);
+        for parent in &self.roles[name].inherits {
+            self.visit(parent, path, done)?;
+        }
+        path.pop();
+        done.insert(name);
+        Ok(())
+    }
+
+    pub fn role(&self, name: &str) -> Option<&Role> {
+        self.roles.get(name)
+    }
+
+    // the user's roles followed by everything they inherit, each once; unknown names are skipped
+    pub fn effective_roles(&self, user: &User) -> Vec<&Role> {
+        let mut found: Vec<&Role> = Vec::new();
+        let mut queue: Vec<&str> = user.roles.iter().map(|name| name.as_str()).collect();
+        let mut next = 0;
+        while next < queue.len() {
+            let name = queue[next];
+            next += 1;
+            if let Some(role) = self.roles.get(name) {
+                if !found.iter().any(|r| r.name == role.name) {
+                    found.push(role);
+                    queue.extend(role.inherits.iter().map(|name| name.as_str()));
+                }
+            }
+        }
+        found
+    }
+
+    // Accounts that are not active at `now` get nothing, as in `lifecycle::is_active_at`, so a
+    // suspension that has run out no longer counts. Then any deny, from a resource section or a
+    // role, beats any allow, and anything not allowed is denied.
+    pub fn authorize(&self, user: &User, action: &str, resource: &str, now: SystemTime) -> bool {
+        if !user.lifecycle.is_active_at(now) {
+            return false;
+        }
+        let roles = self.effective_roles(user);
+        let has_role = |name: &str| roles.iter().any(|role| role.name == name);
+
+        let matching = |rules: &[(String, Pattern)]| {
+            rules
+                .iter()
+                .any(|(role, actions)| has_role(role) && actions.matches(action))
+        };
+        let overrides: Vec<&ResourceOverride> = self
+            .overrides
+            .iter()
+            .filter(|entry| entry.resource.matches(resource))
+            .collect();
+        let any = |permissions: &[Permission]| {
+            permissions
+                .iter()
+                .any(|permission| permission.matches(action, resource))
+        };
+        if overrides.iter().any(|entry| matching(&entry.deny))
+            || roles.iter().any(|role| any(&role.deny))
+        {
+            return false;
+        }
+        overrides.iter().any(|entry| matching(&entry.allow))
+            || roles.iter().any(|role| any(&role.allow))
+    }
+}
+
+// drops a `#` comment, leaving any `#` inside a string alone
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == '#' {
+            return &line[..i];
+        }
+    }
+    line
+}
+
+fn closes_array(text: &str) -> bool {
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text.chars() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ']' {
+            return true;
+        }
+    }
+    false
+}
+
+// a quoted string at the start of `text`, and what follows it
+fn parse_string(text: &str) -> Result<(String, &str), String> {
+    let mut out = String::new();
+    let mut chars = text.char_indices();
+    if chars.next().map(|(_, c)| c) != Some('"') {
+        return Err("expected a quoted string".to_string());
+    }
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((out, &text[i + 1..])),
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                other => return Err(format!("unknown escape `\\{}`", other.unwrap_or(' '))),
+            },
+            '\n' => return Err("unterminated string".to_string()),
+            _ => out.push(c),
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+// `a.b."c.d"` becomes ["a", "b", "c.d"]
+fn parse_key(text: &str) -> Result<Vec<String>, String> {
+    let mut parts = Vec::new();
+    let mut rest = text.trim();
+    loop {
+        if rest.starts_with('"') {
+            let (part, after) = parse_string(rest)?;
+            parts.push(part);
+            rest = after.trim_start();
+        } else {
+            let end = rest.find(|c: char| c == '.' || c.is_whitespace());
+            let part = &rest[..end.unwrap_or(rest.len())];
+            if part.is_empty()
+                || !part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(format!("invalid key `{}`", text));
+            }
+            parts.push(part.to_string());
+            rest = rest[part.len()..].trim_start();
+        }
+        if rest.is_empty() {
+            return Ok(parts);
+        }
+        rest = rest
+            .strip_prefix('.')
+            .ok_or_else(|| format!("invalid key `{}`", text))?
+            .trim_start();
+    }
+}
+
+// `["a", "b",]`, the only kind of value a policy has
+fn parse_array(text: &str) -> Result<Vec<String>, String> {
+    let mut rest = text
+        .strip_prefix('[')
+        .ok_or_else(|| "expected an array of strings".to_string())?
+        .trim_start();
+    let mut items = Vec::new();
+    loop {
+        if let Some(after) = rest.strip_prefix(']') {
+            if !after.trim().is_empty() {
+                return Err(format!("unexpected `{}` after array", after.trim()));
+            }
+            return Ok(items);
+        }
+        let (item, after) = parse_string(rest)?;
+        items.push(item);
+        rest = after.trim_start();
+        if let Some(after) = rest.strip_prefix(',') {
+            rest = after.trim_start();
+        } else if !rest.starts_with(']') {
+            return Err("expected `,` or `]`".to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rbac::{Pattern, Permission, Policy, PolicyError};
+    use crate::structs::{build_user, User};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    const NOW: SystemTime = UNIX_EPOCH;
+
+    const POLICY: &str = r#"
+# who may do what
+[role.viewer]
+allow = ["read:*"]
+
+[role.editor]
+inherits = ["viewer"]
+allow = [
+    "write:docs/*",
+    "delete:docs/*",  # but see below
+]
+deny = ["delete:docs/archive/*"]
+
+[role.admin]
+inherits = ["editor"]
+allow = ["*:*"]
+
+[role.intern]
+inherits = ["viewer"]
+deny = ["read:payroll/*"]
+
+[resource."payroll/summary"]
+allow = ["intern:read"]
+
+[resource."docs/guide"]
+allow = ["viewer:write"]
+
+[resource."docs/frozen"]
+deny = ["editor:write", "admin:*"]
+"#;
+
+    fn user_with(roles: &[&str]) -> User {
+        let mut user = build_user("ray@example.com".to_string(), "Ray".to_string());
+        user.roles = roles.iter().map(|role| role.to_string()).collect();
+        user.lifecycle
+            .activate("email verified", UNIX_EPOCH)
+            .unwrap();
+        user
+    }
+
+    #[test]
+    fn test_patterns() {
+        assert!(Pattern::new("*").matches("anything"));
+        assert!(Pattern::new("docs/*").matches("docs/a/b"));
+        assert!(!Pattern::new("docs/*").matches("doc"));
+        assert!(Pattern::new("docs").matches("docs"));
+        assert!(!Pattern::new("docs").matches("docs/a"));
+        let permission = Permission::parse("read:urn:isbn:*").unwrap();
+        assert!(permission.matches("read", "urn:isbn:0451450523"));
+        assert_eq!(Permission::parse("read"), None);
+        assert_eq!(Permission::parse(":docs"), None);
+    }
+
+    #[test]
+    fn test_inheritance() {
+        let policy = Policy::parse(POLICY).unwrap();
+        let viewer = user_with(&["viewer"]);
+        assert!(policy.authorize(&viewer, "read", "docs/readme", NOW));
+        assert!(!policy.authorize(&viewer, "write", "docs/readme", NOW));
+        let editor = user_with(&["editor"]);
+        assert!(policy.authorize(&editor, "read", "docs/readme", NOW));
+        assert!(policy.authorize(&editor, "write", "docs/readme", NOW));
+        assert!(!policy.authorize(&editor, "write", "src/main.rs", NOW));
+        let admin = user_with(&["admin"]);
+        let names: Vec<&str> = policy
+            .effective_roles(&admin)
+            .iter()
+            .map(|role| role.name.as_str())
+            .collect();
+        assert_eq!(names, ["admin", "editor", "viewer"]);
+        assert!(policy.authorize(&admin, "deploy", "prod", NOW));
+    }
+
+    #[test]
+    fn test_deny_overrides_allow() {
+        let policy = Policy::parse(POLICY).unwrap();
+        let editor = user_with(&["editor"]);
+        assert!(policy.authorize(&editor, "delete", "docs/draft", NOW));
+        assert!(!policy.authorize(&editor, "delete", "docs/archive/2019", NOW));
+        // admin allows everything, but the deny it inherits from editor still wins
+        let admin = user_with(&["admin"]);
+        assert!(!policy.authorize(&admin, "delete", "docs/archive/2019", NOW));
+        // and so does a deny from a second role
+        let both = user_with(&["editor", "intern"]);
+        assert!(!policy.authorize(&both, "read", "payroll/march", NOW));
+        assert!(policy.authorize(&user_with(&["editor"]), "read", "payroll/march", NOW));
+    }
+
+    #[test]
+    fn test_resource_overrides() {
+        let policy = Policy::parse(POLICY).unwrap();
+        let viewer = user_with(&["viewer"]);
+        assert!(policy.authorize(&viewer, "write", "docs/guide", NOW));
+        assert!(!policy.authorize(&viewer, "write", "docs/guide/intro", NOW));
+        // an allow for the resource doesn't lift the intern role's deny
+        let intern = user_with(&["intern"]);
+        assert!(!policy.authorize(&intern, "read", "payroll/march", NOW));
+        assert!(!policy.authorize(&intern, "read", "payroll/summary", NOW));
+        assert!(!policy.authorize(&intern, "write", "payroll/summary", NOW));
+        let editor = user_with(&["editor"]);
+        assert!(!policy.authorize(&editor, "write", "docs/frozen", NOW));
+        assert!(policy.authorize(&editor, "read", "docs/frozen", NOW));
+        assert!(!policy.authorize(&user_with(&["admin"]), "read", "docs/frozen", NOW));
+    }
+
+    #[test]
+    fn test_unknown_or_inactive_users_get_nothing() {
+        let policy = Policy::parse(POLICY).unwrap();
+        assert!(!policy.authorize(&user_with(&[]), "read", "docs/readme", NOW));
+        assert!(!policy.authorize(&user_with(&["ghost"]), "read", "docs/readme", NOW));
+        let mut viewer = user_with(&["viewer"]);
+        viewer.lifecycle.suspend(None, "abuse", UNIX_EPOCH).unwrap();
+        assert!(!policy.authorize(&viewer, "read", "docs/readme", NOW));
+    }
+
+    #[test]
+    fn test_expired_suspensions_no_longer_count() {
+        let policy = Policy::parse(POLICY).unwrap();
+        let mut viewer = user_with(&["viewer"]);
+        let until = NOW + Duration::from_secs(60);
+        viewer.lifecycle.suspend(Some(until), "abuse", NOW).unwrap();
+        assert!(!policy.authorize(&viewer, "read", "docs/readme", NOW));
+        // over, though nothing has moved the account back to active yet
+        assert!(policy.authorize(&viewer, "read", "docs/readme", until));
+        assert!(!policy.authorize(&viewer, "write", "docs/readme", until));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |line: usize, message: &str| {
+            Err(PolicyError {
+                line,
+                message: message.to_string(),
+            })
+        };
+        assert_eq!(
+            Policy::parse("allow = [\"read:*\"]"),
+            error(1, "`allow` is outside of a section")
+        );
+        assert_eq!(
+            Policy::parse("[role.a]\nallow = [\"read\"]"),
+            error(2, "expected `action:resource`, found `read`")
+        );
+        assert_eq!(
+            Policy::parse("[role.a]\n[role.a]"),
+            error(2, "role `a` is defined twice")
+        );
+        assert_eq!(
+            Policy::parse("[role.a]\nallow = []\nallow = []"),
+            error(3, "`allow` is set twice")
+        );
+        assert_eq!(
+            Policy::parse("[group.a]"),
+            error(1, "unknown section `[group.a]`")
+        );
+        assert_eq!(
+            Policy::parse("[role.a]\nallow = \"read:*\""),
+            error(2, "expected an array of strings")
+        );
+        assert_eq!(
+            Policy::parse("[role.a]\nallow = [\n\"read:*\",\n"),
+            error(2, "unterminated array")
+        );
+        assert_eq!(
+            Policy::parse("[role.a]\ninherits = [\"b\"]"),
+            error(1, "role `a` inherits unknown role `b`")
+        );
+        assert_eq!(
+            Policy::parse("[resource.\"x\"]\nallow = [\"b:read\"]"),
+            error(1, "unknown role `b`")
+        );
+    }
+
+    #[test]
+    fn test_inheritance_cycle() {
+        let text = "[role.a]\ninherits = [\"b\"]\n[role.b]\ninherits = [\"c\"]\n[role.c]\ninherits = [\"a\"]\n";
+        assert_eq!(
+            Policy::parse(text),
+            Err(PolicyError {
+                line: 1,
+                message: "roles inherit in a cycle: a -> b -> c -> a".to_string()
+            })
+        );
+        assert!(Policy::parse("[role.a]\ninherits = [\"a\"]").is_err());
+    }
+
+    #[test]
+    fn test_quoted_keys_and_comments() {
+        let text = "[resource.\"a.b#c\"] # dots and hashes\n[role . \"x y\"]\nallow = [\"read:#1\"] # comment\n";
+        let policy = Policy::parse(text).unwrap();
+        assert_eq!(policy.role("x y").unwrap().allow.len(), 1);
+        assert!(policy.authorize(&user_with(&["x y"]), "read", "#1", NOW));
+    }
+}
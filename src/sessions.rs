@@ -0,0 +1,363 @@
+// Sessions for signed-in users, each identified by an opaque random token.
+//
+// A session ends after `idle_timeout` without use (sliding expiry) or `max_lifetime` after it
+// started (absolute expiry), whichever comes first. The store holds at most `capacity` sessions
+// and evicts the least recently used one to make room. Tokens are only kept as their SHA-256, so
+// someone who can read the store still can't present them.
+use crate::base64;
+use crate::clock::Clock;
+use crate::random::random_bytes;
+use crate::sha256::{sha256, DIGEST_LEN};
+use crate::user_repository::UserId;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::time::{Duration, SystemTime};
+
+// random bytes per token, 43 characters once encoded
+pub const TOKEN_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionPolicy {
+    pub idle_timeout: Duration,
+    pub max_lifetime: Duration,
+    pub capacity: usize,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> SessionPolicy {
+        SessionPolicy {
+            idle_timeout: Duration::from_secs(30 * 60),
+            max_lifetime: Duration::from_secs(24 * 60 * 60),
+            capacity: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Session {
+    pub user: UserId,
+    pub created: SystemTime,
+    pub last_seen: SystemTime,
+}
+
+impl Session {
+    pub fn expires_at(&self, policy: &SessionPolicy) -> SystemTime {
+        (self.last_seen + policy.idle_timeout).min(self.created + policy.max_lifetime)
+    }
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    // never issued, revoked or evicted
+    Invalid,
+    Expired,
+    Random(io::Error),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::Invalid => write!(f, "invalid session token"),
+            SessionError::Expired => write!(f, "session has expired"),
+            SessionError::Random(e) => write!(f, "could not generate a token: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+type Key = [u8; DIGEST_LEN];
+
+fn key(token: &str) -> Key {
+    sha256(token.as_bytes())
+}
+
+#[derive(Debug)]
+struct Entry {
+    session: Session,
+    // position in `recency`
+    used: u64,
+}
+
+pub struct SessionStore<C: Clock> {
+    clock: C,
+    policy: SessionPolicy,
+    sessions: HashMap<Key, Entry>,
+    // least recently used first
+    recency: BTreeMap<u64, Key>,
+    by_user: HashMap<UserId, HashSet<Key>>,
+    tick: u64,
+}
+
+impl<C: Clock> SessionStore<C> {
+    pub fn new(clock: C, policy: SessionPolicy) -> SessionStore<C> {
+        assert!(
+            policy.capacity > 0,
+            "a session store needs room for one session"
+        );
+        SessionStore {
+            clock,
+            policy,
+            sessions: HashMap::new(),
+            recency: BTreeMap::new(),
+            by_user: HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn remove(&mut self, key: &Key) -> Option<Session> {
+        let entry = self.sessions.remove(key)?;
+        self.recency.remove(&entry.used);
+        if let Some(keys) = self.by_user.get_mut(&entry.session.user) {
+            keys.remove(key);
+            if keys.is_empty() {
+                self.by_user.remove(&entry.session.user);
+            }
+        }
+        Some(entry.session)
+    }
+
+    // Starts a session for `user` and returns its token. Makes room by dropping expired
+    // sessions first and only then the least recently used one.
+    pub fn create(&mut self, user: UserId) -> Result<String, SessionError> {
+        let token =
+            base64::encode_url_safe(&random_bytes(TOKEN_LEN).map_err(SessionError::Random)?);
+        if self.sessions.len() >= self.policy.capacity {
+            self.purge_expired();
+        }
+        while self.sessions.len() >= self.policy.capacity {
+            let oldest = match self.recency.values().next() {
+                Some(&oldest) => oldest,
+                None => break,
+            };
+            self.remove(&oldest);
+        }
+        let now = self.clock.now();
+        let key = key(&token);
+        let used = self.next_tick();
+        self.sessions.insert(
+            key,
+            Entry {
+                session: Session {
+                    user,
+                    created: now,
+                    last_seen: now,
+                },
+                used,
+            },
+        );
+        self.recency.insert(used, key);
+        self.by_user.entry(user).or_default().insert(key);
+        Ok(token)
+    }
+
+    // Checks a token presented by a client and returns whose session it is. A valid session
+    // is extended by `idle_timeout` from now; an expired one is dropped.
+    pub fn validate(&mut self, token: &str) -> Result<UserId, SessionError> {
+        let now = self.clock.now();
+        let key = key(token);
+        let expires_at = match self.sessions.get(&key) {
+            Some(entry) => entry.session.expires_at(&self.policy),
+            None => return Err(SessionError::Invalid),
+        };
+        if expires_at <= now {
+            self.remove(&key);
+            return Err(SessionError::Expired);
+        }
+        let used = self.next_tick();
+        let entry = self.sessions.get_mut(&key).unwrap();
+        self.recency.remove(&entry.used);
+        self.recency.insert(used, key);
+        entry.used = used;
+        entry.session.last_seen = now;
+        Ok(entry.session.user)
+    }
+
+    // looks a session up without counting it as a use
+    pub fn get(&self, token: &str) -> Option<&Session> {
+        self.sessions.get(&key(token)).map(|entry| &entry.session)
+    }
+
+    // sign out; returns whether there was such a session
+    pub fn revoke(&mut self, token: &str) -> bool {
+        self.remove(&key(token)).is_some()
+    }
+
+    // sign out everywhere, e.g. after a password change; returns how many sessions ended
+    pub fn revoke_all(&mut self, user: UserId) -> usize {
+        let keys = self.by_user.remove(&user).unwrap_or_default();
+        for key in &keys {
+            self.remove(key);
+        }
+        keys.len()
+    }
+
+    // oldest first
+    pub fn sessions_of(&self, user: UserId) -> Vec<Session> {
+        let mut sessions: Vec<Session> = self
+            .by_user
+            .get(&user)
+            .into_iter()
+            .flatten()
+            .map(|key| self.sessions[key].session)
+            .collect();
+        sessions.sort_by_key(|session| session.created);
+        sessions
+    }
+
+    // drops every expired session, returns how many
+    pub fn purge_expired(&mut self) -> usize {
+        let now = self.clock.now();
+        let expired: Vec<Key> = self
+            .sessions
+            .iter()
+            .filter(|(_, entry)| entry.session.expires_at(&self.policy) <= now)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in &expired {
+            self.remove(key);
+        }
+        expired.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base64;
+    use crate::clock::{Clock, ManualClock};
+    use crate::sessions::{SessionError, SessionPolicy, SessionStore, TOKEN_LEN};
+    use std::time::Duration;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn policy(capacity: usize) -> SessionPolicy {
+        SessionPolicy {
+            idle_timeout: 30 * MINUTE,
+            max_lifetime: 120 * MINUTE,
+            capacity,
+        }
+    }
+
+    #[test]
+    fn test_tokens() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut store = SessionStore::new(&clock, policy(10));
+        let a = store.create(1).unwrap();
+        let b = store.create(1).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(a.len(), 43);
+        assert_eq!(base64::decode_url_safe(&a).unwrap().len(), TOKEN_LEN);
+        assert_eq!(store.validate(&a).unwrap(), 1);
+        assert!(matches!(
+            store.validate("forged"),
+            Err(SessionError::Invalid)
+        ));
+        let session = store.get(&b).unwrap();
+        assert_eq!(session.created, clock.now());
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn test_sliding_expiry() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut store = SessionStore::new(&clock, policy(10));
+        let token = store.create(7).unwrap();
+        for _ in 0..3 {
+            clock.advance(29 * MINUTE);
+            assert_eq!(store.validate(&token).unwrap(), 7);
+        }
+        assert_eq!(store.get(&token).unwrap().last_seen, clock.now());
+        clock.advance(30 * MINUTE);
+        assert!(matches!(store.validate(&token), Err(SessionError::Expired)));
+        // an expired session is gone for good
+        assert!(matches!(store.validate(&token), Err(SessionError::Invalid)));
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_absolute_expiry() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut store = SessionStore::new(&clock, policy(10));
+        let token = store.create(7).unwrap();
+        for _ in 0..11 {
+            clock.advance(10 * MINUTE);
+            store.validate(&token).unwrap();
+        }
+        // still in use, but two hours old
+        clock.advance(10 * MINUTE);
+        assert!(matches!(store.validate(&token), Err(SessionError::Expired)));
+    }
+
+    #[test]
+    fn test_revoke() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut store = SessionStore::new(&clock, policy(10));
+        let laptop = store.create(1).unwrap();
+        clock.advance(MINUTE);
+        let phone = store.create(1).unwrap();
+        let other = store.create(2).unwrap();
+        assert_eq!(store.sessions_of(1).len(), 2);
+        assert!(store.revoke(&laptop));
+        assert!(!store.revoke(&laptop));
+        assert!(matches!(
+            store.validate(&laptop),
+            Err(SessionError::Invalid)
+        ));
+        assert_eq!(store.sessions_of(1)[0].created, clock.now());
+        store.create(1).unwrap();
+        assert_eq!(store.revoke_all(1), 2);
+        assert_eq!(store.revoke_all(1), 0);
+        assert!(store.validate(&phone).is_err());
+        assert!(store.sessions_of(1).is_empty());
+        assert_eq!(store.validate(&other).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut store = SessionStore::new(&clock, policy(2));
+        let a = store.create(1).unwrap();
+        let b = store.create(2).unwrap();
+        store.validate(&a).unwrap();
+        let c = store.create(3).unwrap();
+        assert_eq!(store.len(), 2);
+        assert!(matches!(store.validate(&b), Err(SessionError::Invalid)));
+        assert!(store.sessions_of(2).is_empty());
+        assert!(store.validate(&a).is_ok() && store.validate(&c).is_ok());
+    }
+
+    #[test]
+    fn test_expired_sessions_make_room_first() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut store = SessionStore::new(&clock, policy(2));
+        let old = store.create(1).unwrap();
+        for _ in 0..10 {
+            clock.advance(10 * MINUTE);
+            store.validate(&old).unwrap();
+        }
+        let quiet = store.create(2).unwrap();
+        clock.advance(15 * MINUTE);
+        store.validate(&old).unwrap();
+        // `old` is the most recently used, but has reached its lifetime
+        clock.advance(6 * MINUTE);
+        let new = store.create(3).unwrap();
+        assert!(store.get(&old).is_none());
+        assert_eq!(store.validate(&quiet).unwrap(), 2);
+        assert_eq!(store.validate(&new).unwrap(), 3);
+        assert_eq!(store.purge_expired(), 0);
+    }
+}
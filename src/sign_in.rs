@@ -0,0 +1,383 @@
+// Records sign-in attempts, keeps `User::sign_in_count` up to date and locks accounts that
+// fail too often. Locks expire on their own, each repeated lockout lasts twice as long.
+use crate::clock::Clock;
+use crate::lifecycle::AccountState;
+use crate::structs::User;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockoutPolicy {
+    // this many failures within `window` locks the account
+    pub max_failures: usize,
+    pub window: Duration,
+    // the first lockout, doubled for every further one until `max_lockout`
+    pub lockout: Duration,
+    pub max_lockout: Duration,
+    // attempts kept per account for `attempts`, oldest dropped first
+    pub history_len: usize,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> LockoutPolicy {
+        LockoutPolicy {
+            max_failures: 5,
+            window: Duration::from_secs(15 * 60),
+            lockout: Duration::from_secs(60),
+            max_lockout: Duration::from_secs(60 * 60),
+            history_len: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Success,
+    Failure,
+    // the attempt arrived while the account was locked and was not checked
+    Rejected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attempt {
+    pub at: SystemTime,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignInError {
+    Locked { until: SystemTime },
+    // pending, suspended, deactivated and deleted accounts can't sign in
+    NotActive(AccountState),
+}
+
+impl fmt::Display for SignInError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignInError::Locked { until } => write!(f, "account is locked until {:?}", until),
+            SignInError::NotActive(state) => write!(f, "account is {}", state),
+        }
+    }
+}
+
+impl std::error::Error for SignInError {}
+
+#[derive(Debug, Default)]
+struct AccountAttempts {
+    attempts: VecDeque<Attempt>,
+    recent_failures: VecDeque<SystemTime>,
+    locked_until: Option<SystemTime>,
+    // lockouts since the last successful sign-in, drives the backoff
+    lockouts: u32,
+}
+
+pub struct SignInTracker<C: Clock> {
+    clock: C,
+    policy: LockoutPolicy,
+    accounts: HashMap<String, AccountAttempts>,
+}
+
+impl<C: Clock> SignInTracker<C> {
+    pub fn new(clock: C, policy: LockoutPolicy) -> SignInTracker<C> {
+        SignInTracker {
+            clock,
+            policy,
+            accounts: HashMap::new(),
+        }
+    }
+
+    fn lockout_for(&self, previous_lockouts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(previous_lockouts.min(31));
+        self.policy
+            .lockout
+            .checked_mul(factor)
+            .unwrap_or(self.policy.max_lockout)
+            .min(self.policy.max_lockout)
+    }
+
+    fn push_attempt(&mut self, username: &str, attempt: Attempt) {
+        let history_len = self.policy.history_len;
+        let state = self.accounts.entry(username.to_string()).or_default();
+        state.attempts.push_back(attempt);
+        while state.attempts.len() > history_len {
+            state.attempts.pop_front();
+        }
+    }
+
+    pub fn locked_until(&self, username: &str) -> Option<SystemTime> {
+        let until = self.accounts.get(username)?.locked_until?;
+        if self.clock.now() < until {
+            Some(until)
+        } else {
+            None
+        }
+    }
+
+    // call before checking credentials, so a locked account doesn't even get that far
+    pub fn check(&self, username: &str) -> Result<(), SignInError> {
+        match self.locked_until(username) {
+            Some(until) => Err(SignInError::Locked { until }),
+            None => Ok(()),
+        }
+    }
+
+    // The credentials were right. Counts the sign-in unless the account is locked or
+    // not active, in which case the attempt is rejected.
+    pub fn record_success(&mut self, user: &mut User) -> Result<(), SignInError> {
+        let now = self.clock.now();
+        let allowed = if user.lifecycle.is_active_at(now) {
+            self.check(&user.username)
+        } else {
+            Err(SignInError::NotActive(user.lifecycle.state()))
+        };
+        if let Err(error) = allowed {
+            self.push_attempt(
+                &user.username,
+                Attempt {
+                    at: now,
+                    outcome: Outcome::Rejected,
+                },
+            );
+            return Err(error);
+        }
+        self.push_attempt(
+            &user.username,
+            Attempt {
+                at: now,
+                outcome: Outcome::Success,
+            },
+        );
+        let state = self.accounts.entry(user.username.clone()).or_default();
+        state.recent_failures.clear();
+        state.locked_until = None;
+        state.lockouts = 0;
+        user.lifecycle.expire_suspension(now);
+        user.sign_in_count += 1;
+        Ok(())
+    }
+
+    // The credentials were wrong. Returns the lock if this failure caused one
+    // (or the account was already locked).
+    pub fn record_failure(&mut self, user: &User) -> Result<(), SignInError> {
+        let now = self.clock.now();
+        if let Err(error) = self.check(&user.username) {
+            self.push_attempt(
+                &user.username,
+                Attempt {
+                    at: now,
+                    outcome: Outcome::Rejected,
+                },
+            );
+            return Err(error);
+        }
+        self.push_attempt(
+            &user.username,
+            Attempt {
+                at: now,
+                outcome: Outcome::Failure,
+            },
+        );
+        let window = self.policy.window;
+        let max_failures = self.policy.max_failures;
+        let previous_lockouts = self.accounts[&user.username].lockouts;
+        let lockout = self.lockout_for(previous_lockouts);
+        let state = self.accounts.get_mut(&user.username).unwrap();
+        state.recent_failures.push_back(now);
+        while let Some(&oldest) = state.recent_failures.front() {
+            match now.duration_since(oldest) {
+                Ok(age) if age >= window => {
+                    state.recent_failures.pop_front();
+                }
+                _ => break,
+            }
+        }
+        if state.recent_failures.len() >= max_failures {
+            let until = now + lockout;
+            state.recent_failures.clear();
+            state.locked_until = Some(until);
+            state.lockouts += 1;
+            return Err(SignInError::Locked { until });
+        }
+        Ok(())
+    }
+
+    // oldest first
+    pub fn attempts(&self, username: &str) -> Vec<Attempt> {
+        self.accounts
+            .get(username)
+            .map(|state| state.attempts.iter().copied().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::{Clock, ManualClock};
+    use crate::lifecycle::AccountState;
+    use crate::sign_in::{Attempt, LockoutPolicy, Outcome, SignInError, SignInTracker};
+    use crate::structs::{build_user, User};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn verified_ray() -> User {
+        let mut ray = build_user("ray@example.com".to_string(), "Ray".to_string());
+        ray.lifecycle
+            .activate("email verified", UNIX_EPOCH)
+            .unwrap();
+        ray
+    }
+
+    fn policy() -> LockoutPolicy {
+        LockoutPolicy {
+            max_failures: 3,
+            window: Duration::from_secs(60),
+            lockout: Duration::from_secs(30),
+            max_lockout: Duration::from_secs(100),
+            history_len: 10,
+        }
+    }
+
+    #[test]
+    fn test_success_bumps_sign_in_count() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut tracker = SignInTracker::new(&clock, policy());
+        let mut ray = verified_ray();
+        assert_eq!(ray.sign_in_count, 1);
+        tracker.record_success(&mut ray).unwrap();
+        clock.advance(Duration::from_secs(5));
+        tracker.record_success(&mut ray).unwrap();
+        assert_eq!(ray.sign_in_count, 3);
+        let start = clock.now() - Duration::from_secs(5);
+        assert_eq!(
+            tracker.attempts("Ray"),
+            vec![
+                Attempt {
+                    at: start,
+                    outcome: Outcome::Success
+                },
+                Attempt {
+                    at: clock.now(),
+                    outcome: Outcome::Success
+                },
+            ]
+        );
+        assert!(tracker.attempts("nobody").is_empty());
+    }
+
+    #[test]
+    fn test_lock_after_failures_in_window() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut tracker = SignInTracker::new(&clock, policy());
+        let mut ray = verified_ray();
+        assert_eq!(tracker.record_failure(&ray), Ok(()));
+        assert_eq!(tracker.record_failure(&ray), Ok(()));
+        let until = clock.now() + Duration::from_secs(30);
+        assert_eq!(
+            tracker.record_failure(&ray),
+            Err(SignInError::Locked { until })
+        );
+        assert_eq!(tracker.check("Ray"), Err(SignInError::Locked { until }));
+        // even the right password is turned away, and not counted
+        assert_eq!(
+            tracker.record_success(&mut ray),
+            Err(SignInError::Locked { until })
+        );
+        assert_eq!(ray.sign_in_count, 1);
+        assert_eq!(
+            tracker.attempts("Ray").last().unwrap().outcome,
+            Outcome::Rejected
+        );
+        // other accounts are unaffected
+        assert_eq!(tracker.check("sam"), Ok(()));
+    }
+
+    #[test]
+    fn test_failures_outside_window_are_forgotten() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut tracker = SignInTracker::new(&clock, policy());
+        let ray = verified_ray();
+        for _ in 0..10 {
+            assert_eq!(tracker.record_failure(&ray), Ok(()));
+            clock.advance(Duration::from_secs(31));
+        }
+    }
+
+    #[test]
+    fn test_lock_expires_and_backs_off() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut tracker = SignInTracker::new(&clock, policy());
+        let mut ray = verified_ray();
+        let mut lock_lengths = Vec::new();
+        for _ in 0..4 {
+            let mut result = Ok(());
+            while result.is_ok() {
+                result = tracker.record_failure(&ray);
+            }
+            let until = match result {
+                Err(SignInError::Locked { until }) => until,
+                other => panic!("expected a lock, got {:?}", other),
+            };
+            let length = until.duration_since(clock.now()).unwrap();
+            lock_lengths.push(length.as_secs());
+            clock.advance(length);
+            assert_eq!(tracker.check("Ray"), Ok(()));
+        }
+        // 30s doubling, capped at 100s
+        assert_eq!(lock_lengths, vec![30, 60, 100, 100]);
+        // a successful sign-in resets the backoff
+        tracker.record_success(&mut ray).unwrap();
+        for _ in 0..2 {
+            tracker.record_failure(&ray).unwrap();
+        }
+        let until = clock.now() + Duration::from_secs(30);
+        assert_eq!(
+            tracker.record_failure(&ray),
+            Err(SignInError::Locked { until })
+        );
+    }
+
+    #[test]
+    fn test_only_active_accounts_sign_in() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut tracker = SignInTracker::new(&clock, policy());
+        let mut sam = build_user("sam@example.com".to_string(), "Sam".to_string());
+        assert_eq!(
+            tracker.record_success(&mut sam),
+            Err(SignInError::NotActive(AccountState::PendingVerification))
+        );
+        let mut ray = verified_ray();
+        let until = clock.now() + Duration::from_secs(60);
+        ray.lifecycle
+            .suspend(Some(until), "abuse", clock.now())
+            .unwrap();
+        assert_eq!(
+            tracker.record_success(&mut ray),
+            Err(SignInError::NotActive(AccountState::Suspended {
+                until: Some(until)
+            }))
+        );
+        assert_eq!(ray.sign_in_count, 1);
+        // once the suspension has run out the sign-in goes through and ends it
+        clock.advance(Duration::from_secs(60));
+        tracker.record_success(&mut ray).unwrap();
+        assert_eq!(ray.lifecycle.state(), AccountState::Active);
+        assert_eq!(ray.sign_in_count, 2);
+        ray.lifecycle.deactivate("closed", clock.now()).unwrap();
+        assert_eq!(
+            tracker.record_success(&mut ray),
+            Err(SignInError::NotActive(AccountState::Deactivated))
+        );
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let clock = ManualClock::at_unix(1_000);
+        let mut tracker = SignInTracker::new(&clock, policy());
+        let mut ray = verified_ray();
+        for _ in 0..25 {
+            tracker.record_success(&mut ray).unwrap();
+        }
+        assert_eq!(tracker.attempts("Ray").len(), 10);
+        assert_eq!(ray.sign_in_count, 26);
+    }
+}
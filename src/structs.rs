@@ -1,11 +1,32 @@
-struct User {
-    username: String,
-    email: String,
-    sign_in_count: u64,
-    active: bool,
+use crate::lifecycle::Lifecycle;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct User {
+    pub username: String,
+    pub email: String,
+    pub sign_in_count: u64,
+    // pending, active, suspended and so on, see lifecycle.rs
+    pub lifecycle: Lifecycle,
+    // a PHC string, see password.rs
+    pub password_hash: Option<String>,
+    // role names, resolved against a policy, see rbac.rs
+    pub roles: Vec<String>,
+    pub profile: Profile,
+}
+
+// what a user tells about themselves, all optional; see user_builder.rs for what is valid
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    pub display_name: Option<String>,
+    // a BCP 47 language tag, e.g. `en-GB`
+    pub locale: Option<String>,
+    // an IANA time zone, e.g. `Europe/London`
+    pub timezone: Option<String>,
+    pub avatar_url: Option<String>,
 }
 
-struct Color(i32, i32, i32);
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color(pub i32, pub i32, pub i32);
 
 struct Point(i32, i32, i32);
 
@@ -39,18 +60,23 @@ impl Rectangle {
     }
 }
 
-fn build_user(email: String, username: String) -> User {
+pub fn build_user(email: String, username: String) -> User {
     User {
         email,
         username,
-        active: true,
+        // until the email address is verified, see verification.rs
+        lifecycle: Lifecycle::pending(),
         sign_in_count: 1,
+        password_hash: None,
+        roles: Vec::new(),
+        profile: Profile::default(),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::structs::{area, build_user, Color, Point, Rectangle, User};
+    use crate::lifecycle::{AccountState, Lifecycle};
+    use crate::structs::{area, build_user, Color, Point, Profile, Rectangle, User};
 
     #[test]
     fn test_user_instance() {
@@ -59,14 +85,17 @@ mod tests {
             username: String::from("Ray"),
             email: "".to_string(),
             sign_in_count: 1,
-            active: true,
+            lifecycle: Lifecycle::active(),
+            password_hash: None,
+            roles: vec![],
+            profile: Profile::default(),
         };
         // since `ray` is mutable, we can change the email
         ray.email = String::from("rayyounghong@gmail.com");
         assert_eq!(ray.username, "Ray");
         assert_eq!(ray.email, "rayyounghong@gmail.com");
         assert_eq!(ray.sign_in_count, 1);
-        assert!(ray.active);
+        assert!(ray.is_active());
     }
 
     #[test]
@@ -75,7 +104,8 @@ mod tests {
         assert_eq!(ray.username, "Ray");
         assert_eq!(ray.email, "rayyounghong@gmail.com");
         assert_eq!(ray.sign_in_count, 1);
-        assert!(ray.active);
+        assert_eq!(ray.lifecycle.state(), AccountState::PendingVerification);
+        assert!(!ray.is_active());
     }
 
     #[test]
@@ -84,7 +114,7 @@ mod tests {
         assert_eq!(ray.username, "Ray");
         assert_eq!(ray.email, "rayyounghong@gmail.com");
         assert_eq!(ray.sign_in_count, 1);
-        assert!(ray.active);
+        assert!(!ray.is_active());
         let someone: User = User {
             email: "someone@some.com".to_string(),
             username: "someone".to_string(),
@@ -93,7 +123,7 @@ mod tests {
         assert_eq!(someone.username, "someone");
         assert_eq!(someone.email, "someone@some.com");
         assert_eq!(someone.sign_in_count, 1);
-        assert!(someone.active);
+        assert!(!someone.is_active());
     }
 
     #[test]
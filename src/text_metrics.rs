@@ -0,0 +1,311 @@
+// How long a piece of text is, depending on who asks: bytes for storage, `char`s for Unicode
+// scalar values, extended grapheme clusters (UAX #29) for what a reader counts as characters
+// and display width for how many terminal columns it takes.
+//
+// Display width is per grapheme cluster: East Asian Wide and Fullwidth characters take two
+// columns, marks, joiners and other characters that attach to the one before take none, and
+// an emoji takes two whether it's one character, a flag or a whole zero-width-joiner sequence.
+// Ambiguous width characters count as narrow and control characters, tabs included, as
+// nothing, so expand tabs before measuring.
+use crate::unicode_tables::{
+    GraphemeBreak, EAST_ASIAN_WIDE, EXTENDED_PICTOGRAPHIC, GRAPHEME_BREAK,
+};
+use std::cmp::Ordering;
+
+const HANGUL_SYLLABLES: (u32, u32) = (0xAC00, 0xD7A3);
+// every syllable block starts with the LV syllable, followed by 27 LVT ones
+const HANGUL_T_COUNT: u32 = 28;
+const EMOJI_PRESENTATION: char = '\u{fe0f}';
+
+fn compare_range(first: u32, last: u32, cp: u32) -> Ordering {
+    if last < cp {
+        Ordering::Less
+    } else if first > cp {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+fn grapheme_break(c: char) -> GraphemeBreak {
+    let cp = c as u32;
+    if HANGUL_SYLLABLES.0 <= cp && cp <= HANGUL_SYLLABLES.1 {
+        return if (cp - HANGUL_SYLLABLES.0).is_multiple_of(HANGUL_T_COUNT) {
+            GraphemeBreak::Lv
+        } else {
+            GraphemeBreak::Lvt
+        };
+    }
+    GRAPHEME_BREAK
+        .binary_search_by(|&(first, last, _)| compare_range(first, last, cp))
+        .map(|i| GRAPHEME_BREAK[i].2)
+        .unwrap_or(GraphemeBreak::Other)
+}
+
+fn is_extended_pictographic(c: char) -> bool {
+    let cp = c as u32;
+    EXTENDED_PICTOGRAPHIC
+        .binary_search_by(|&(first, last)| compare_range(first, last, cp))
+        .is_ok()
+}
+
+fn is_wide(c: char) -> bool {
+    let cp = c as u32;
+    EAST_ASIAN_WIDE
+        .binary_search_by(|&(first, last)| compare_range(first, last, cp))
+        .is_ok()
+}
+
+// the byte offset of the next grapheme cluster boundary after `start`, which has to be one
+fn next_boundary(text: &str, start: usize) -> usize {
+    use crate::unicode_tables::GraphemeBreak::*;
+    let mut chars = text[start..].char_indices();
+    let first = match chars.next() {
+        Some((_, c)) => c,
+        None => return text.len(),
+    };
+    let mut prev = grapheme_break(first);
+    // whether the cluster so far ends in a pictograph and its extenders, and then a joiner
+    let mut pictograph = is_extended_pictographic(first);
+    let mut joined = false;
+    let mut regional = usize::from(prev == RegionalIndicator);
+    for (i, c) in chars {
+        let next = grapheme_break(c);
+        let keep = match (prev, next) {
+            (CR, LF) => true,
+            (CR, _) | (LF, _) | (Control, _) => false,
+            (_, CR) | (_, LF) | (_, Control) => false,
+            (L, L) | (L, V) | (L, Lv) | (L, Lvt) => true,
+            (Lv, V) | (Lv, T) | (V, V) | (V, T) => true,
+            (Lvt, T) | (T, T) => true,
+            (_, Extend) | (_, Zwj) | (_, SpacingMark) => true,
+            (Prepend, _) => true,
+            (Zwj, _) => joined && is_extended_pictographic(c),
+            (RegionalIndicator, RegionalIndicator) => regional % 2 == 1,
+            _ => false,
+        };
+        if !keep {
+            return start + i;
+        }
+        joined = pictograph && next == Zwj;
+        pictograph = is_extended_pictographic(c) || (pictograph && next == Extend);
+        regional = if next == RegionalIndicator {
+            regional + 1
+        } else {
+            0
+        };
+        prev = next;
+    }
+    text.len()
+}
+
+// the extended grapheme clusters of a text, in order
+pub struct Graphemes<'a> {
+    text: &'a str,
+    start: usize,
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.start >= self.text.len() {
+            return None;
+        }
+        let end = next_boundary(self.text, self.start);
+        let grapheme = &self.text[self.start..end];
+        self.start = end;
+        Some(grapheme)
+    }
+}
+
+pub fn graphemes(text: &str) -> Graphemes<'_> {
+    Graphemes { text, start: 0 }
+}
+
+pub fn grapheme_count(text: &str) -> usize {
+    graphemes(text).count()
+}
+
+fn char_width(c: char) -> usize {
+    use crate::unicode_tables::GraphemeBreak::*;
+    match grapheme_break(c) {
+        CR | LF | Control | Extend | Zwj | Prepend => 0,
+        // vowel and final jamo join the leading consonant
+        V | T => 0,
+        _ if is_wide(c) => 2,
+        _ => 1,
+    }
+}
+
+// the columns one grapheme cluster takes
+pub fn grapheme_width(grapheme: &str) -> usize {
+    let mut chars = grapheme.chars();
+    let first = match chars.next() {
+        Some(c) => c,
+        None => return 0,
+    };
+    let width = grapheme.chars().map(char_width).max().unwrap_or(0);
+    let emoji = is_extended_pictographic(first) && grapheme.contains(EMOJI_PRESENTATION);
+    let flag = grapheme_break(first) == GraphemeBreak::RegionalIndicator
+        && chars.any(|c| grapheme_break(c) == GraphemeBreak::RegionalIndicator);
+    if emoji || flag {
+        width.max(2)
+    } else {
+        width
+    }
+}
+
+pub fn display_width(text: &str) -> usize {
+    graphemes(text).map(grapheme_width).sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextMetrics {
+    pub bytes: usize,
+    pub chars: usize,
+    pub graphemes: usize,
+    pub width: usize,
+}
+
+pub fn measure(text: &str) -> TextMetrics {
+    let mut metrics = TextMetrics {
+        bytes: text.len(),
+        chars: text.chars().count(),
+        ..TextMetrics::default()
+    };
+    for grapheme in graphemes(text) {
+        metrics.graphemes += 1;
+        metrics.width += grapheme_width(grapheme);
+    }
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::text_metrics::{
+        display_width, grapheme_count, grapheme_width, graphemes, measure, TextMetrics,
+    };
+
+    // text, bytes, chars, graphemes, width
+    const CASES: &[(&str, usize, usize, usize, usize)] = &[
+        ("", 0, 0, 0, 0),
+        ("hello", 5, 5, 5, 5),
+        ("😻", 4, 1, 1, 2),
+        ("héllo", 6, 5, 5, 5),
+        // e and a combining acute accent
+        ("he\u{301}llo", 7, 6, 5, 5),
+        ("日本語", 9, 3, 3, 6),
+        ("ｆｕｌｌ", 12, 4, 4, 8),
+        ("ﾊﾝｶｸ", 12, 4, 4, 4),
+        // a precomposed syllable and the same one made of jamo
+        ("한", 3, 1, 1, 2),
+        ("\u{1112}\u{1161}\u{11ab}", 9, 3, 1, 2),
+        // man, woman, girl joined by zero width joiners
+        ("👨\u{200d}👩\u{200d}👧", 18, 5, 1, 2),
+        ("👋🏽", 8, 2, 1, 2),
+        // flags are pairs of regional indicators
+        ("🇩🇪🇫🇷", 16, 4, 2, 4),
+        // a heart in text style, then in emoji style
+        ("\u{2764}", 3, 1, 1, 1),
+        ("\u{2764}\u{fe0f}", 6, 2, 1, 2),
+        ("\r\n", 2, 2, 1, 0),
+        ("a\tb", 3, 3, 3, 2),
+        ("zero\u{200b}width", 12, 10, 10, 9),
+        ("नमस्ते", 18, 6, 4, 4),
+        ("Ω≈ç√", 10, 4, 4, 4),
+    ];
+
+    #[test]
+    fn test_bytes() {
+        for &(text, bytes, _, _, _) in CASES {
+            assert_eq!(text.len(), bytes, "{:?}", text);
+            assert_eq!(measure(text).bytes, bytes, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_chars() {
+        for &(text, _, chars, _, _) in CASES {
+            assert_eq!(measure(text).chars, chars, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_graphemes() {
+        for &(text, _, _, count, _) in CASES {
+            assert_eq!(grapheme_count(text), count, "{:?}", text);
+            assert_eq!(measure(text).graphemes, count, "{:?}", text);
+            assert_eq!(graphemes(text).collect::<String>(), text);
+        }
+    }
+
+    #[test]
+    fn test_display_width() {
+        for &(text, _, _, _, width) in CASES {
+            assert_eq!(display_width(text), width, "{:?}", text);
+            assert_eq!(measure(text).width, width, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_grapheme_boundaries() {
+        let cases: &[(&str, &[&str])] = &[
+            ("e\u{301}x", &["e\u{301}", "x"]),
+            ("\r\n\n", &["\r\n", "\n"]),
+            // a lone mark after a line break has nothing to attach to
+            ("\n\u{301}", &["\n", "\u{301}"]),
+            ("🇩🇪🇫", &["🇩🇪", "🇫"]),
+            ("🇩\u{301}🇪", &["🇩\u{301}", "🇪"]),
+            // a joiner only joins two pictographs
+            ("a\u{200d}👧", &["a\u{200d}", "👧"]),
+            ("👨\u{1f3fd}\u{200d}👧", &["👨\u{1f3fd}\u{200d}👧"]),
+            (
+                "\u{1100}\u{1100}\u{ac00}\u{11a8}",
+                &["\u{1100}\u{1100}\u{ac00}\u{11a8}"],
+            ),
+            ("\u{ac01}\u{1161}", &["\u{ac01}", "\u{1161}"]),
+            // Devanagari ka, virama, ssa, vowel sign i
+            (
+                "\u{915}\u{94d}\u{937}\u{93f}",
+                &["\u{915}\u{94d}", "\u{937}\u{93f}"],
+            ),
+            // an Arabic number sign goes with what follows it
+            ("\u{600}1", &["\u{600}1"]),
+        ];
+        for &(text, expected) in cases {
+            assert_eq!(graphemes(text).collect::<Vec<_>>(), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_grapheme_width() {
+        let cases: &[(&str, usize)] = &[
+            ("", 0),
+            ("a", 1),
+            ("\u{301}", 0),
+            ("\u{200d}", 0),
+            ("中", 2),
+            ("👍", 2),
+            ("🇩", 1),
+            ("🇩🇪", 2),
+            ("\u{a9}\u{fe0f}", 2),
+        ];
+        for &(grapheme, width) in cases {
+            assert_eq!(grapheme_width(grapheme), width, "{:?}", grapheme);
+        }
+    }
+
+    #[test]
+    fn test_measure() {
+        assert_eq!(
+            measure("Ray 😻"),
+            TextMetrics {
+                bytes: 8,
+                chars: 5,
+                graphemes: 5,
+                width: 6,
+            }
+        );
+    }
+}
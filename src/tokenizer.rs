@@ -0,0 +1,330 @@
+// Splits text into words, numbers, whitespace and punctuation, keeping where each token was.
+// Tokens follow the word boundaries of `words`, with runs of whitespace, line breaks included,
+// merged into one token. Anything that isn't a word, number or whitespace, symbols and emoji
+// too, counts as punctuation.
+//
+// `LineIndex` turns byte offsets into lines and columns and back, with columns in bytes and in
+// UTF-16 code units, which is what editors speaking LSP count in. Lines and columns start at 0,
+// and lines end at "\r\n", "\n" or a lone "\r", as LSP has them.
+use crate::words::{segments, Segments};
+use std::iter::Peekable;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Word,
+    Number,
+    Whitespace,
+    Punctuation,
+}
+
+fn kind(segment: &str) -> TokenKind {
+    if segment.chars().all(char::is_whitespace) {
+        TokenKind::Whitespace
+    } else if segment.chars().any(char::is_alphabetic) {
+        TokenKind::Word
+    } else if segment.chars().any(char::is_numeric) {
+        TokenKind::Number
+    } else {
+        TokenKind::Punctuation
+    }
+}
+
+pub struct Tokenizer<'a> {
+    text: &'a str,
+    segments: Peekable<Segments<'a>>,
+    offset: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(text: &'a str) -> Tokenizer<'a> {
+        Tokenizer::at(text, 0)
+    }
+
+    // Starts at `offset` as if the text began there. From the start of a token this gives the
+    // same tokens as going through from the beginning, so a tokenizer can pick up where an
+    // earlier one stopped or where an edit begins. Panics if `offset` isn't on a char boundary.
+    pub fn at(text: &'a str, offset: usize) -> Tokenizer<'a> {
+        Tokenizer {
+            text,
+            segments: segments(&text[offset..]).peekable(),
+            offset,
+        }
+    }
+
+    // where the next token starts
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = (TokenKind, &'a str, Range<usize>);
+
+    fn next(&mut self) -> Option<(TokenKind, &'a str, Range<usize>)> {
+        let segment = self.segments.next()?;
+        let kind = kind(segment);
+        let start = self.offset;
+        let mut end = start + segment.len();
+        if kind == TokenKind::Whitespace {
+            while let Some(next) = self.segments.peek() {
+                if self::kind(next) != TokenKind::Whitespace {
+                    break;
+                }
+                end += next.len();
+                self.segments.next();
+            }
+        }
+        self.offset = end;
+        Some((kind, &self.text[start..end], start..end))
+    }
+}
+
+pub fn tokens(text: &str) -> Tokenizer<'_> {
+    Tokenizer::new(text)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    // bytes since the start of the line
+    pub utf8_column: usize,
+    // UTF-16 code units since the start of the line
+    pub utf16_column: usize,
+}
+
+// the start of every line in a text
+pub struct LineIndex<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> LineIndex<'a> {
+        let bytes = text.as_bytes();
+        let mut starts = vec![0];
+        for (i, &b) in bytes.iter().enumerate() {
+            // the '\r' of a "\r\n" leaves it to the '\n'
+            if b == b'\n' || (b == b'\r' && bytes.get(i + 1) != Some(&b'\n')) {
+                starts.push(i + 1);
+            }
+        }
+        LineIndex { text, starts }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    // the line `offset` is on and its columns, `None` past the end or inside a character
+    pub fn position(&self, offset: usize) -> Option<Position> {
+        if !self.text.is_char_boundary(offset) {
+            return None;
+        }
+        let line = match self.starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let start = self.starts[line];
+        Some(Position {
+            line,
+            utf8_column: offset - start,
+            utf16_column: self.text[start..offset].encode_utf16().count(),
+        })
+    }
+
+    // the text of a line, without its line break
+    pub fn line(&self, line: usize) -> Option<&'a str> {
+        let start = *self.starts.get(line)?;
+        let end = match self.starts.get(line + 1) {
+            Some(&next) => next - 1,
+            None => self.text.len(),
+        };
+        // `end` is past a lone '\r' or the '\n', which may follow a '\r'
+        let text = &self.text[start..end];
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    // the byte offset of a line and UTF-16 column, `None` if the line is too short or the
+    // column falls inside a surrogate pair
+    pub fn offset_utf16(&self, line: usize, utf16_column: usize) -> Option<usize> {
+        let start = *self.starts.get(line)?;
+        let mut units = 0;
+        for (i, c) in self.line(line)?.char_indices() {
+            if units == utf16_column {
+                return Some(start + i);
+            }
+            if units > utf16_column {
+                return None;
+            }
+            units += c.len_utf16();
+        }
+        if units == utf16_column {
+            Some(start + self.line(line)?.len())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::{tokens, LineIndex, Position, TokenKind, Tokenizer};
+
+    #[test]
+    fn test_tokens() {
+        use crate::tokenizer::TokenKind::*;
+        let text = "Hello,  wörld!\n\tcan't pay 1,000.50 for v2 😻";
+        let found: Vec<_> = tokens(text).collect();
+        assert_eq!(
+            found,
+            vec![
+                (Word, "Hello", 0..5),
+                (Punctuation, ",", 5..6),
+                (Whitespace, "  ", 6..8),
+                (Word, "wörld", 8..14),
+                (Punctuation, "!", 14..15),
+                (Whitespace, "\n\t", 15..17),
+                (Word, "can't", 17..22),
+                (Whitespace, " ", 22..23),
+                (Word, "pay", 23..26),
+                (Whitespace, " ", 26..27),
+                (Number, "1,000.50", 27..35),
+                (Whitespace, " ", 35..36),
+                (Word, "for", 36..39),
+                (Whitespace, " ", 39..40),
+                (Word, "v2", 40..42),
+                (Whitespace, " ", 42..43),
+                (Punctuation, "😻", 43..47),
+            ]
+        );
+        for (_, token, range) in found {
+            // slices of the input, not copies
+            assert_eq!(token.as_ptr(), text[range].as_ptr());
+        }
+        assert_eq!(tokens("").count(), 0);
+    }
+
+    #[test]
+    fn test_kinds() {
+        let cases: &[(&str, TokenKind)] = &[
+            ("word", TokenKind::Word),
+            ("日", TokenKind::Word),
+            ("x86_64", TokenKind::Word),
+            ("42", TokenKind::Number),
+            ("٣", TokenKind::Number),
+            ("3.14", TokenKind::Number),
+            ("\u{a0}", TokenKind::Whitespace),
+            ("\r\n", TokenKind::Whitespace),
+            ("\u{3000}", TokenKind::Whitespace),
+            ("-", TokenKind::Punctuation),
+            ("@", TokenKind::Punctuation),
+        ];
+        for &(text, kind) in cases {
+            let found: Vec<_> = tokens(text).collect();
+            assert_eq!(found, vec![(kind, text, 0..text.len())], "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_restart() {
+        let text = "one two, three\n4 five";
+        let all: Vec<_> = tokens(text).collect();
+        for (i, (_, _, range)) in all.iter().enumerate() {
+            let rest: Vec<_> = Tokenizer::at(text, range.start).collect();
+            assert_eq!(rest, all[i..]);
+        }
+        let mut tokenizer = Tokenizer::new(text);
+        tokenizer.next();
+        tokenizer.next();
+        assert_eq!(tokenizer.offset(), 4);
+        let resumed = Tokenizer::at(text, tokenizer.offset()).next();
+        assert_eq!(resumed, Some((TokenKind::Word, "two", 4..7)));
+        assert_eq!(Tokenizer::at(text, text.len()).next(), None);
+    }
+
+    #[test]
+    fn test_line_index() {
+        let text = "ab\r\nπ😻x\n\nend";
+        let index = LineIndex::new(text);
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line(0), Some("ab"));
+        assert_eq!(index.line(1), Some("π😻x"));
+        assert_eq!(index.line(2), Some(""));
+        assert_eq!(index.line(3), Some("end"));
+        assert_eq!(index.line(4), None);
+
+        let cases: &[(usize, usize, usize, usize)] = &[
+            // offset, line, UTF-8 column, UTF-16 column
+            (0, 0, 0, 0),
+            (2, 0, 2, 2),
+            (4, 1, 0, 0),
+            (6, 1, 2, 1),
+            (10, 1, 6, 3),
+            (11, 1, 7, 4),
+            (12, 2, 0, 0),
+            (13, 3, 0, 0),
+            (16, 3, 3, 3),
+        ];
+        for &(offset, line, utf8_column, utf16_column) in cases {
+            let position = Position {
+                line,
+                utf8_column,
+                utf16_column,
+            };
+            assert_eq!(index.position(offset), Some(position), "{}", offset);
+            assert_eq!(index.offset_utf16(line, utf16_column), Some(offset));
+        }
+        // inside π, past the end
+        assert_eq!(index.position(5), None);
+        assert_eq!(index.position(17), None);
+        // inside the surrogate pair of 😻, past the end of the line
+        assert_eq!(index.offset_utf16(1, 2), None);
+        assert_eq!(index.offset_utf16(1, 5), None);
+        assert_eq!(index.offset_utf16(9, 0), None);
+    }
+
+    #[test]
+    fn test_line_endings() {
+        // old Mac line ends, then a blank line of each kind
+        let text = "one\rtwo\r\nthree\n\r\r\n\nend\r";
+        let index = LineIndex::new(text);
+        let lines: Vec<_> = (0..index.line_count())
+            .map(|line| index.line(line).unwrap())
+            .collect();
+        assert_eq!(lines, ["one", "two", "three", "", "", "", "end", ""]);
+        assert_eq!(index.position(4).unwrap().line, 1);
+        assert_eq!(index.offset_utf16(1, 0), Some(4));
+        // between the '\r' and '\n' is still the end of the line
+        let crlf = index.position(8).unwrap();
+        assert_eq!((crlf.line, crlf.utf8_column), (1, 4));
+        assert_eq!(index.position(text.len()).unwrap().line, 7);
+    }
+
+    #[test]
+    fn test_token_positions() {
+        let text = "let π = 3.14;\nprint(π)";
+        let index = LineIndex::new(text);
+        let starts: Vec<_> = tokens(text)
+            .filter(|(kind, _, _)| *kind != TokenKind::Whitespace)
+            .map(|(_, token, range)| {
+                let position = index.position(range.start).unwrap();
+                (token, position.line, position.utf16_column)
+            })
+            .collect();
+        assert_eq!(
+            starts,
+            vec![
+                ("let", 0, 0),
+                ("π", 0, 4),
+                ("=", 0, 6),
+                ("3.14", 0, 8),
+                (";", 0, 12),
+                ("print", 1, 0),
+                ("(", 1, 5),
+                ("π", 1, 6),
+                (")", 1, 7),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1038 @@
+// Unicode data tables, generated from the Unicode Character Database (14.0) for the parts of
+// Unicode the rest of the crate looks at. Each says what it covers: the normalization tables
+// are hand-picked subsets, so username normalization is only a partial check, the
+// segmentation tables cover all of Unicode.
+
+// NFKC compatibility mappings to a single character, for Latin-1, Latin Extended-A, general
+// punctuation, super- and subscripts, letterlike symbols, number forms, enclosed alphanumerics,
+// the ideographic space, fullwidth ASCII and the mathematical alphanumerics.
+// `(first, last, target)`: first..=last map to target, target + 1 and so on.
+#[rustfmt::skip]
+pub const COMPATIBILITY_RUNS: &[(u32, u32, u32)] = &[
+    (0x00A0, 0x00A0, 0x0020), (0x00AA, 0x00AA, 0x0061), (0x00B2, 0x00B3, 0x0032),
+    (0x00B5, 0x00B5, 0x03BC), (0x00B9, 0x00B9, 0x0031), (0x00BA, 0x00BA, 0x006F),
+    (0x017F, 0x017F, 0x0073), (0x2000, 0x2000, 0x0020), (0x2001, 0x2001, 0x0020),
+    (0x2002, 0x2002, 0x0020), (0x2003, 0x2003, 0x0020), (0x2004, 0x2004, 0x0020),
+    (0x2005, 0x2005, 0x0020), (0x2006, 0x2006, 0x0020), (0x2007, 0x2007, 0x0020),
+    (0x2008, 0x2008, 0x0020), (0x2009, 0x2009, 0x0020), (0x200A, 0x200A, 0x0020),
+    (0x2011, 0x2011, 0x2010), (0x2024, 0x2024, 0x002E), (0x202F, 0x202F, 0x0020),
+    (0x205F, 0x205F, 0x0020), (0x2070, 0x2070, 0x0030), (0x2071, 0x2071, 0x0069),
+    (0x2074, 0x2079, 0x0034), (0x207A, 0x207A, 0x002B), (0x207B, 0x207B, 0x2212),
+    (0x207C, 0x207C, 0x003D), (0x207D, 0x207E, 0x0028), (0x207F, 0x207F, 0x006E),
+    (0x2080, 0x2089, 0x0030), (0x208A, 0x208A, 0x002B), (0x208B, 0x208B, 0x2212),
+    (0x208C, 0x208C, 0x003D), (0x208D, 0x208E, 0x0028), (0x2090, 0x2090, 0x0061),
+    (0x2091, 0x2091, 0x0065), (0x2092, 0x2092, 0x006F), (0x2093, 0x2093, 0x0078),
+    (0x2094, 0x2094, 0x0259), (0x2095, 0x2095, 0x0068), (0x2096, 0x2099, 0x006B),
+    (0x209A, 0x209A, 0x0070), (0x209B, 0x209C, 0x0073), (0x2102, 0x2102, 0x0043),
+    (0x2107, 0x2107, 0x0190), (0x210A, 0x210A, 0x0067), (0x210B, 0x210B, 0x0048),
+    (0x210C, 0x210C, 0x0048), (0x210D, 0x210D, 0x0048), (0x210E, 0x210E, 0x0068),
+    (0x210F, 0x210F, 0x0127), (0x2110, 0x2110, 0x0049), (0x2111, 0x2111, 0x0049),
+    (0x2112, 0x2112, 0x004C), (0x2113, 0x2113, 0x006C), (0x2115, 0x2115, 0x004E),
+    (0x2119, 0x211B, 0x0050), (0x211C, 0x211C, 0x0052), (0x211D, 0x211D, 0x0052),
+    (0x2124, 0x2124, 0x005A), (0x2126, 0x2126, 0x03A9), (0x2128, 0x2128, 0x005A),
+    (0x212A, 0x212A, 0x004B), (0x212B, 0x212B, 0x00C5), (0x212C, 0x212D, 0x0042),
+    (0x212F, 0x212F, 0x0065), (0x2130, 0x2131, 0x0045), (0x2133, 0x2133, 0x004D),
+    (0x2134, 0x2134, 0x006F), (0x2135, 0x2138, 0x05D0), (0x2139, 0x2139, 0x0069),
+    (0x213C, 0x213C, 0x03C0), (0x213D, 0x213D, 0x03B3), (0x213E, 0x213E, 0x0393),
+    (0x213F, 0x213F, 0x03A0), (0x2140, 0x2140, 0x2211), (0x2145, 0x2145, 0x0044),
+    (0x2146, 0x2147, 0x0064), (0x2148, 0x2149, 0x0069), (0x2160, 0x2160, 0x0049),
+    (0x2164, 0x2164, 0x0056), (0x2169, 0x2169, 0x0058), (0x216C, 0x216C, 0x004C),
+    (0x216D, 0x216E, 0x0043), (0x216F, 0x216F, 0x004D), (0x2170, 0x2170, 0x0069),
+    (0x2174, 0x2174, 0x0076), (0x2179, 0x2179, 0x0078), (0x217C, 0x217C, 0x006C),
+    (0x217D, 0x217E, 0x0063), (0x217F, 0x217F, 0x006D), (0x2460, 0x2468, 0x0031),
+    (0x24B6, 0x24CF, 0x0041), (0x24D0, 0x24E9, 0x0061), (0x24EA, 0x24EA, 0x0030),
+    (0x3000, 0x3000, 0x0020), (0xFF01, 0xFF5E, 0x0021), (0x1D400, 0x1D419, 0x0041),
+    (0x1D41A, 0x1D433, 0x0061), (0x1D434, 0x1D44D, 0x0041), (0x1D44E, 0x1D454, 0x0061),
+    (0x1D456, 0x1D467, 0x0069), (0x1D468, 0x1D481, 0x0041), (0x1D482, 0x1D49B, 0x0061),
+    (0x1D49C, 0x1D49C, 0x0041), (0x1D49E, 0x1D49F, 0x0043), (0x1D4A2, 0x1D4A2, 0x0047),
+    (0x1D4A5, 0x1D4A6, 0x004A), (0x1D4A9, 0x1D4AC, 0x004E), (0x1D4AE, 0x1D4B5, 0x0053),
+    (0x1D4B6, 0x1D4B9, 0x0061), (0x1D4BB, 0x1D4BB, 0x0066), (0x1D4BD, 0x1D4C3, 0x0068),
+    (0x1D4C5, 0x1D4CF, 0x0070), (0x1D4D0, 0x1D4E9, 0x0041), (0x1D4EA, 0x1D503, 0x0061),
+    (0x1D504, 0x1D505, 0x0041), (0x1D507, 0x1D50A, 0x0044), (0x1D50D, 0x1D514, 0x004A),
+    (0x1D516, 0x1D51C, 0x0053), (0x1D51E, 0x1D537, 0x0061), (0x1D538, 0x1D539, 0x0041),
+    (0x1D53B, 0x1D53E, 0x0044), (0x1D540, 0x1D544, 0x0049), (0x1D546, 0x1D546, 0x004F),
+    (0x1D54A, 0x1D550, 0x0053), (0x1D552, 0x1D56B, 0x0061), (0x1D56C, 0x1D585, 0x0041),
+    (0x1D586, 0x1D59F, 0x0061), (0x1D5A0, 0x1D5B9, 0x0041), (0x1D5BA, 0x1D5D3, 0x0061),
+    (0x1D5D4, 0x1D5ED, 0x0041), (0x1D5EE, 0x1D607, 0x0061), (0x1D608, 0x1D621, 0x0041),
+    (0x1D622, 0x1D63B, 0x0061), (0x1D63C, 0x1D655, 0x0041), (0x1D656, 0x1D66F, 0x0061),
+    (0x1D670, 0x1D689, 0x0041), (0x1D68A, 0x1D6A3, 0x0061), (0x1D6A4, 0x1D6A4, 0x0131),
+    (0x1D6A5, 0x1D6A5, 0x0237), (0x1D6A8, 0x1D6B8, 0x0391), (0x1D6B9, 0x1D6B9, 0x0398),
+    (0x1D6BA, 0x1D6C0, 0x03A3), (0x1D6C1, 0x1D6C1, 0x2207), (0x1D6C2, 0x1D6DA, 0x03B1),
+    (0x1D6DB, 0x1D6DB, 0x2202), (0x1D6DC, 0x1D6DC, 0x03B5), (0x1D6DD, 0x1D6DD, 0x03B8),
+    (0x1D6DE, 0x1D6DE, 0x03BA), (0x1D6DF, 0x1D6DF, 0x03C6), (0x1D6E0, 0x1D6E0, 0x03C1),
+    (0x1D6E1, 0x1D6E1, 0x03C0), (0x1D6E2, 0x1D6F2, 0x0391), (0x1D6F3, 0x1D6F3, 0x0398),
+    (0x1D6F4, 0x1D6FA, 0x03A3), (0x1D6FB, 0x1D6FB, 0x2207), (0x1D6FC, 0x1D714, 0x03B1),
+    (0x1D715, 0x1D715, 0x2202), (0x1D716, 0x1D716, 0x03B5), (0x1D717, 0x1D717, 0x03B8),
+    (0x1D718, 0x1D718, 0x03BA), (0x1D719, 0x1D719, 0x03C6), (0x1D71A, 0x1D71A, 0x03C1),
+    (0x1D71B, 0x1D71B, 0x03C0), (0x1D71C, 0x1D72C, 0x0391), (0x1D72D, 0x1D72D, 0x0398),
+    (0x1D72E, 0x1D734, 0x03A3), (0x1D735, 0x1D735, 0x2207), (0x1D736, 0x1D74E, 0x03B1),
+    (0x1D74F, 0x1D74F, 0x2202), (0x1D750, 0x1D750, 0x03B5), (0x1D751, 0x1D751, 0x03B8),
+    (0x1D752, 0x1D752, 0x03BA), (0x1D753, 0x1D753, 0x03C6), (0x1D754, 0x1D754, 0x03C1),
+    (0x1D755, 0x1D755, 0x03C0), (0x1D756, 0x1D766, 0x0391), (0x1D767, 0x1D767, 0x0398),
+    (0x1D768, 0x1D76E, 0x03A3), (0x1D76F, 0x1D76F, 0x2207), (0x1D770, 0x1D788, 0x03B1),
+    (0x1D789, 0x1D789, 0x2202), (0x1D78A, 0x1D78A, 0x03B5), (0x1D78B, 0x1D78B, 0x03B8),
+    (0x1D78C, 0x1D78C, 0x03BA), (0x1D78D, 0x1D78D, 0x03C6), (0x1D78E, 0x1D78E, 0x03C1),
+    (0x1D78F, 0x1D78F, 0x03C0), (0x1D790, 0x1D7A0, 0x0391), (0x1D7A1, 0x1D7A1, 0x0398),
+    (0x1D7A2, 0x1D7A8, 0x03A3), (0x1D7A9, 0x1D7A9, 0x2207), (0x1D7AA, 0x1D7C2, 0x03B1),
+    (0x1D7C3, 0x1D7C3, 0x2202), (0x1D7C4, 0x1D7C4, 0x03B5), (0x1D7C5, 0x1D7C5, 0x03B8),
+    (0x1D7C6, 0x1D7C6, 0x03BA), (0x1D7C7, 0x1D7C7, 0x03C6), (0x1D7C8, 0x1D7C8, 0x03C1),
+    (0x1D7C9, 0x1D7C9, 0x03C0), (0x1D7CA, 0x1D7CB, 0x03DC), (0x1D7CE, 0x1D7D7, 0x0030),
+    (0x1D7D8, 0x1D7E1, 0x0030), (0x1D7E2, 0x1D7EB, 0x0030), (0x1D7EC, 0x1D7F5, 0x0030),
+    (0x1D7F6, 0x1D7FF, 0x0030),
+];
+
+// NFKC compatibility mappings to several characters, same blocks as above
+#[rustfmt::skip]
+pub const COMPATIBILITY_STRINGS: &[(char, &str)] = &[
+    ('\u{a8}', " \u{308}"), ('\u{af}', " \u{304}"), ('\u{b4}', " \u{301}"),
+    ('\u{b8}', " \u{327}"), ('\u{bc}', "1\u{2044}4"), ('\u{bd}', "1\u{2044}2"),
+    ('\u{be}', "3\u{2044}4"), ('\u{132}', "IJ"), ('\u{133}', "ij"), ('\u{13f}', "L\u{b7}"),
+    ('\u{140}', "l\u{b7}"), ('\u{149}', "\u{2bc}n"), ('\u{2017}', " \u{333}"),
+    ('\u{2025}', ".."), ('\u{2026}', "..."), ('\u{2033}', "\u{2032}\u{2032}"),
+    ('\u{2034}', "\u{2032}\u{2032}\u{2032}"), ('\u{2036}', "\u{2035}\u{2035}"),
+    ('\u{2037}', "\u{2035}\u{2035}\u{2035}"), ('\u{203c}', "!!"), ('\u{203e}', " \u{305}"),
+    ('\u{2047}', "??"), ('\u{2048}', "?!"), ('\u{2049}', "!?"),
+    ('\u{2057}', "\u{2032}\u{2032}\u{2032}\u{2032}"), ('\u{2100}', "a/c"), ('\u{2101}', "a/s"),
+    ('\u{2103}', "\u{b0}C"), ('\u{2105}', "c/o"), ('\u{2106}', "c/u"), ('\u{2109}', "\u{b0}F"),
+    ('\u{2116}', "No"), ('\u{2120}', "SM"), ('\u{2121}', "TEL"), ('\u{2122}', "TM"),
+    ('\u{213b}', "FAX"), ('\u{2150}', "1\u{2044}7"), ('\u{2151}', "1\u{2044}9"),
+    ('\u{2152}', "1\u{2044}10"), ('\u{2153}', "1\u{2044}3"), ('\u{2154}', "2\u{2044}3"),
+    ('\u{2155}', "1\u{2044}5"), ('\u{2156}', "2\u{2044}5"), ('\u{2157}', "3\u{2044}5"),
+    ('\u{2158}', "4\u{2044}5"), ('\u{2159}', "1\u{2044}6"), ('\u{215a}', "5\u{2044}6"),
+    ('\u{215b}', "1\u{2044}8"), ('\u{215c}', "3\u{2044}8"), ('\u{215d}', "5\u{2044}8"),
+    ('\u{215e}', "7\u{2044}8"), ('\u{215f}', "1\u{2044}"), ('\u{2161}', "II"),
+    ('\u{2162}', "III"), ('\u{2163}', "IV"), ('\u{2165}', "VI"), ('\u{2166}', "VII"),
+    ('\u{2167}', "VIII"), ('\u{2168}', "IX"), ('\u{216a}', "XI"), ('\u{216b}', "XII"),
+    ('\u{2171}', "ii"), ('\u{2172}', "iii"), ('\u{2173}', "iv"), ('\u{2175}', "vi"),
+    ('\u{2176}', "vii"), ('\u{2177}', "viii"), ('\u{2178}', "ix"), ('\u{217a}', "xi"),
+    ('\u{217b}', "xii"), ('\u{2189}', "0\u{2044}3"), ('\u{2469}', "10"), ('\u{246a}', "11"),
+    ('\u{246b}', "12"), ('\u{246c}', "13"), ('\u{246d}', "14"), ('\u{246e}', "15"),
+    ('\u{246f}', "16"), ('\u{2470}', "17"), ('\u{2471}', "18"), ('\u{2472}', "19"),
+    ('\u{2473}', "20"), ('\u{2474}', "(1)"), ('\u{2475}', "(2)"), ('\u{2476}', "(3)"),
+    ('\u{2477}', "(4)"), ('\u{2478}', "(5)"), ('\u{2479}', "(6)"), ('\u{247a}', "(7)"),
+    ('\u{247b}', "(8)"), ('\u{247c}', "(9)"), ('\u{247d}', "(10)"), ('\u{247e}', "(11)"),
+    ('\u{247f}', "(12)"), ('\u{2480}', "(13)"), ('\u{2481}', "(14)"), ('\u{2482}', "(15)"),
+    ('\u{2483}', "(16)"), ('\u{2484}', "(17)"), ('\u{2485}', "(18)"), ('\u{2486}', "(19)"),
+    ('\u{2487}', "(20)"), ('\u{2488}', "1."), ('\u{2489}', "2."), ('\u{248a}', "3."),
+    ('\u{248b}', "4."), ('\u{248c}', "5."), ('\u{248d}', "6."), ('\u{248e}', "7."),
+    ('\u{248f}', "8."), ('\u{2490}', "9."), ('\u{2491}', "10."), ('\u{2492}', "11."),
+    ('\u{2493}', "12."), ('\u{2494}', "13."), ('\u{2495}', "14."), ('\u{2496}', "15."),
+    ('\u{2497}', "16."), ('\u{2498}', "17."), ('\u{2499}', "18."), ('\u{249a}', "19."),
+    ('\u{249b}', "20."), ('\u{249c}', "(a)"), ('\u{249d}', "(b)"), ('\u{249e}', "(c)"),
+    ('\u{249f}', "(d)"), ('\u{24a0}', "(e)"), ('\u{24a1}', "(f)"), ('\u{24a2}', "(g)"),
+    ('\u{24a3}', "(h)"), ('\u{24a4}', "(i)"), ('\u{24a5}', "(j)"), ('\u{24a6}', "(k)"),
+    ('\u{24a7}', "(l)"), ('\u{24a8}', "(m)"), ('\u{24a9}', "(n)"), ('\u{24aa}', "(o)"),
+    ('\u{24ab}', "(p)"), ('\u{24ac}', "(q)"), ('\u{24ad}', "(r)"), ('\u{24ae}', "(s)"),
+    ('\u{24af}', "(t)"), ('\u{24b0}', "(u)"), ('\u{24b1}', "(v)"), ('\u{24b2}', "(w)"),
+    ('\u{24b3}', "(x)"), ('\u{24b4}', "(y)"), ('\u{24b5}', "(z)"), ('\u{fb00}', "ff"),
+    ('\u{fb01}', "fi"), ('\u{fb02}', "fl"), ('\u{fb03}', "ffi"), ('\u{fb04}', "ffl"),
+    ('\u{fb05}', "st"), ('\u{fb06}', "st"),
+];
+
+// canonical compositions of a base letter and one combining mark, for Latin-1, Latin
+// Extended-A and Latin Extended Additional, sorted by base and mark
+#[rustfmt::skip]
+pub const COMPOSITIONS: &[(char, char, char)] = &[
+    ('A', '\u{300}', '\u{c0}'), ('A', '\u{301}', '\u{c1}'), ('A', '\u{302}', '\u{c2}'),
+    ('A', '\u{303}', '\u{c3}'), ('A', '\u{304}', '\u{100}'), ('A', '\u{306}', '\u{102}'),
+    ('A', '\u{308}', '\u{c4}'), ('A', '\u{309}', '\u{1ea2}'), ('A', '\u{30a}', '\u{c5}'),
+    ('A', '\u{323}', '\u{1ea0}'), ('A', '\u{325}', '\u{1e00}'), ('A', '\u{328}', '\u{104}'),
+    ('B', '\u{307}', '\u{1e02}'), ('B', '\u{323}', '\u{1e04}'), ('B', '\u{331}', '\u{1e06}'),
+    ('C', '\u{301}', '\u{106}'), ('C', '\u{302}', '\u{108}'), ('C', '\u{307}', '\u{10a}'),
+    ('C', '\u{30c}', '\u{10c}'), ('C', '\u{327}', '\u{c7}'), ('D', '\u{307}', '\u{1e0a}'),
+    ('D', '\u{30c}', '\u{10e}'), ('D', '\u{323}', '\u{1e0c}'), ('D', '\u{327}', '\u{1e10}'),
+    ('D', '\u{32d}', '\u{1e12}'), ('D', '\u{331}', '\u{1e0e}'), ('E', '\u{300}', '\u{c8}'),
+    ('E', '\u{301}', '\u{c9}'), ('E', '\u{302}', '\u{ca}'), ('E', '\u{303}', '\u{1ebc}'),
+    ('E', '\u{304}', '\u{112}'), ('E', '\u{306}', '\u{114}'), ('E', '\u{307}', '\u{116}'),
+    ('E', '\u{308}', '\u{cb}'), ('E', '\u{309}', '\u{1eba}'), ('E', '\u{30c}', '\u{11a}'),
+    ('E', '\u{323}', '\u{1eb8}'), ('E', '\u{328}', '\u{118}'), ('E', '\u{32d}', '\u{1e18}'),
+    ('E', '\u{330}', '\u{1e1a}'), ('F', '\u{307}', '\u{1e1e}'), ('G', '\u{302}', '\u{11c}'),
+    ('G', '\u{304}', '\u{1e20}'), ('G', '\u{306}', '\u{11e}'), ('G', '\u{307}', '\u{120}'),
+    ('G', '\u{327}', '\u{122}'), ('H', '\u{302}', '\u{124}'), ('H', '\u{307}', '\u{1e22}'),
+    ('H', '\u{308}', '\u{1e26}'), ('H', '\u{323}', '\u{1e24}'), ('H', '\u{327}', '\u{1e28}'),
+    ('H', '\u{32e}', '\u{1e2a}'), ('I', '\u{300}', '\u{cc}'), ('I', '\u{301}', '\u{cd}'),
+    ('I', '\u{302}', '\u{ce}'), ('I', '\u{303}', '\u{128}'), ('I', '\u{304}', '\u{12a}'),
+    ('I', '\u{306}', '\u{12c}'), ('I', '\u{307}', '\u{130}'), ('I', '\u{308}', '\u{cf}'),
+    ('I', '\u{309}', '\u{1ec8}'), ('I', '\u{323}', '\u{1eca}'), ('I', '\u{328}', '\u{12e}'),
+    ('I', '\u{330}', '\u{1e2c}'), ('J', '\u{302}', '\u{134}'), ('K', '\u{301}', '\u{1e30}'),
+    ('K', '\u{323}', '\u{1e32}'), ('K', '\u{327}', '\u{136}'), ('K', '\u{331}', '\u{1e34}'),
+    ('L', '\u{301}', '\u{139}'), ('L', '\u{30c}', '\u{13d}'), ('L', '\u{323}', '\u{1e36}'),
+    ('L', '\u{327}', '\u{13b}'), ('L', '\u{32d}', '\u{1e3c}'), ('L', '\u{331}', '\u{1e3a}'),
+    ('M', '\u{301}', '\u{1e3e}'), ('M', '\u{307}', '\u{1e40}'), ('M', '\u{323}', '\u{1e42}'),
+    ('N', '\u{301}', '\u{143}'), ('N', '\u{303}', '\u{d1}'), ('N', '\u{307}', '\u{1e44}'),
+    ('N', '\u{30c}', '\u{147}'), ('N', '\u{323}', '\u{1e46}'), ('N', '\u{327}', '\u{145}'),
+    ('N', '\u{32d}', '\u{1e4a}'), ('N', '\u{331}', '\u{1e48}'), ('O', '\u{300}', '\u{d2}'),
+    ('O', '\u{301}', '\u{d3}'), ('O', '\u{302}', '\u{d4}'), ('O', '\u{303}', '\u{d5}'),
+    ('O', '\u{304}', '\u{14c}'), ('O', '\u{306}', '\u{14e}'), ('O', '\u{308}', '\u{d6}'),
+    ('O', '\u{309}', '\u{1ece}'), ('O', '\u{30b}', '\u{150}'), ('O', '\u{323}', '\u{1ecc}'),
+    ('P', '\u{301}', '\u{1e54}'), ('P', '\u{307}', '\u{1e56}'), ('R', '\u{301}', '\u{154}'),
+    ('R', '\u{307}', '\u{1e58}'), ('R', '\u{30c}', '\u{158}'), ('R', '\u{323}', '\u{1e5a}'),
+    ('R', '\u{327}', '\u{156}'), ('R', '\u{331}', '\u{1e5e}'), ('S', '\u{301}', '\u{15a}'),
+    ('S', '\u{302}', '\u{15c}'), ('S', '\u{307}', '\u{1e60}'), ('S', '\u{30c}', '\u{160}'),
+    ('S', '\u{323}', '\u{1e62}'), ('S', '\u{327}', '\u{15e}'), ('T', '\u{307}', '\u{1e6a}'),
+    ('T', '\u{30c}', '\u{164}'), ('T', '\u{323}', '\u{1e6c}'), ('T', '\u{327}', '\u{162}'),
+    ('T', '\u{32d}', '\u{1e70}'), ('T', '\u{331}', '\u{1e6e}'), ('U', '\u{300}', '\u{d9}'),
+    ('U', '\u{301}', '\u{da}'), ('U', '\u{302}', '\u{db}'), ('U', '\u{303}', '\u{168}'),
+    ('U', '\u{304}', '\u{16a}'), ('U', '\u{306}', '\u{16c}'), ('U', '\u{308}', '\u{dc}'),
+    ('U', '\u{309}', '\u{1ee6}'), ('U', '\u{30a}', '\u{16e}'), ('U', '\u{30b}', '\u{170}'),
+    ('U', '\u{323}', '\u{1ee4}'), ('U', '\u{324}', '\u{1e72}'), ('U', '\u{328}', '\u{172}'),
+    ('U', '\u{32d}', '\u{1e76}'), ('U', '\u{330}', '\u{1e74}'), ('V', '\u{303}', '\u{1e7c}'),
+    ('V', '\u{323}', '\u{1e7e}'), ('W', '\u{300}', '\u{1e80}'), ('W', '\u{301}', '\u{1e82}'),
+    ('W', '\u{302}', '\u{174}'), ('W', '\u{307}', '\u{1e86}'), ('W', '\u{308}', '\u{1e84}'),
+    ('W', '\u{323}', '\u{1e88}'), ('X', '\u{307}', '\u{1e8a}'), ('X', '\u{308}', '\u{1e8c}'),
+    ('Y', '\u{300}', '\u{1ef2}'), ('Y', '\u{301}', '\u{dd}'), ('Y', '\u{302}', '\u{176}'),
+    ('Y', '\u{303}', '\u{1ef8}'), ('Y', '\u{307}', '\u{1e8e}'), ('Y', '\u{308}', '\u{178}'),
+    ('Y', '\u{309}', '\u{1ef6}'), ('Y', '\u{323}', '\u{1ef4}'), ('Z', '\u{301}', '\u{179}'),
+    ('Z', '\u{302}', '\u{1e90}'), ('Z', '\u{307}', '\u{17b}'), ('Z', '\u{30c}', '\u{17d}'),
+    ('Z', '\u{323}', '\u{1e92}'), ('Z', '\u{331}', '\u{1e94}'), ('a', '\u{300}', '\u{e0}'),
+    ('a', '\u{301}', '\u{e1}'), ('a', '\u{302}', '\u{e2}'), ('a', '\u{303}', '\u{e3}'),
+    ('a', '\u{304}', '\u{101}'), ('a', '\u{306}', '\u{103}'), ('a', '\u{308}', '\u{e4}'),
+    ('a', '\u{309}', '\u{1ea3}'), ('a', '\u{30a}', '\u{e5}'), ('a', '\u{323}', '\u{1ea1}'),
+    ('a', '\u{325}', '\u{1e01}'), ('a', '\u{328}', '\u{105}'), ('b', '\u{307}', '\u{1e03}'),
+    ('b', '\u{323}', '\u{1e05}'), ('b', '\u{331}', '\u{1e07}'), ('c', '\u{301}', '\u{107}'),
+    ('c', '\u{302}', '\u{109}'), ('c', '\u{307}', '\u{10b}'), ('c', '\u{30c}', '\u{10d}'),
+    ('c', '\u{327}', '\u{e7}'), ('d', '\u{307}', '\u{1e0b}'), ('d', '\u{30c}', '\u{10f}'),
+    ('d', '\u{323}', '\u{1e0d}'), ('d', '\u{327}', '\u{1e11}'), ('d', '\u{32d}', '\u{1e13}'),
+    ('d', '\u{331}', '\u{1e0f}'), ('e', '\u{300}', '\u{e8}'), ('e', '\u{301}', '\u{e9}'),
+    ('e', '\u{302}', '\u{ea}'), ('e', '\u{303}', '\u{1ebd}'), ('e', '\u{304}', '\u{113}'),
+    ('e', '\u{306}', '\u{115}'), ('e', '\u{307}', '\u{117}'), ('e', '\u{308}', '\u{eb}'),
+    ('e', '\u{309}', '\u{1ebb}'), ('e', '\u{30c}', '\u{11b}'), ('e', '\u{323}', '\u{1eb9}'),
+    ('e', '\u{328}', '\u{119}'), ('e', '\u{32d}', '\u{1e19}'), ('e', '\u{330}', '\u{1e1b}'),
+    ('f', '\u{307}', '\u{1e1f}'), ('g', '\u{302}', '\u{11d}'), ('g', '\u{304}', '\u{1e21}'),
+    ('g', '\u{306}', '\u{11f}'), ('g', '\u{307}', '\u{121}'), ('g', '\u{327}', '\u{123}'),
+    ('h', '\u{302}', '\u{125}'), ('h', '\u{307}', '\u{1e23}'), ('h', '\u{308}', '\u{1e27}'),
+    ('h', '\u{323}', '\u{1e25}'), ('h', '\u{327}', '\u{1e29}'), ('h', '\u{32e}', '\u{1e2b}'),
+    ('h', '\u{331}', '\u{1e96}'), ('i', '\u{300}', '\u{ec}'), ('i', '\u{301}', '\u{ed}'),
+    ('i', '\u{302}', '\u{ee}'), ('i', '\u{303}', '\u{129}'), ('i', '\u{304}', '\u{12b}'),
+    ('i', '\u{306}', '\u{12d}'), ('i', '\u{308}', '\u{ef}'), ('i', '\u{309}', '\u{1ec9}'),
+    ('i', '\u{323}', '\u{1ecb}'), ('i', '\u{328}', '\u{12f}'), ('i', '\u{330}', '\u{1e2d}'),
+    ('j', '\u{302}', '\u{135}'), ('k', '\u{301}', '\u{1e31}'), ('k', '\u{323}', '\u{1e33}'),
+    ('k', '\u{327}', '\u{137}'), ('k', '\u{331}', '\u{1e35}'), ('l', '\u{301}', '\u{13a}'),
+    ('l', '\u{30c}', '\u{13e}'), ('l', '\u{323}', '\u{1e37}'), ('l', '\u{327}', '\u{13c}'),
+    ('l', '\u{32d}', '\u{1e3d}'), ('l', '\u{331}', '\u{1e3b}'), ('m', '\u{301}', '\u{1e3f}'),
+    ('m', '\u{307}', '\u{1e41}'), ('m', '\u{323}', '\u{1e43}'), ('n', '\u{301}', '\u{144}'),
+    ('n', '\u{303}', '\u{f1}'), ('n', '\u{307}', '\u{1e45}'), ('n', '\u{30c}', '\u{148}'),
+    ('n', '\u{323}', '\u{1e47}'), ('n', '\u{327}', '\u{146}'), ('n', '\u{32d}', '\u{1e4b}'),
+    ('n', '\u{331}', '\u{1e49}'), ('o', '\u{300}', '\u{f2}'), ('o', '\u{301}', '\u{f3}'),
+    ('o', '\u{302}', '\u{f4}'), ('o', '\u{303}', '\u{f5}'), ('o', '\u{304}', '\u{14d}'),
+    ('o', '\u{306}', '\u{14f}'), ('o', '\u{308}', '\u{f6}'), ('o', '\u{309}', '\u{1ecf}'),
+    ('o', '\u{30b}', '\u{151}'), ('o', '\u{323}', '\u{1ecd}'), ('p', '\u{301}', '\u{1e55}'),
+    ('p', '\u{307}', '\u{1e57}'), ('r', '\u{301}', '\u{155}'), ('r', '\u{307}', '\u{1e59}'),
+    ('r', '\u{30c}', '\u{159}'), ('r', '\u{323}', '\u{1e5b}'), ('r', '\u{327}', '\u{157}'),
+    ('r', '\u{331}', '\u{1e5f}'), ('s', '\u{301}', '\u{15b}'), ('s', '\u{302}', '\u{15d}'),
+    ('s', '\u{307}', '\u{1e61}'), ('s', '\u{30c}', '\u{161}'), ('s', '\u{323}', '\u{1e63}'),
+    ('s', '\u{327}', '\u{15f}'), ('t', '\u{307}', '\u{1e6b}'), ('t', '\u{308}', '\u{1e97}'),
+    ('t', '\u{30c}', '\u{165}'), ('t', '\u{323}', '\u{1e6d}'), ('t', '\u{327}', '\u{163}'),
+    ('t', '\u{32d}', '\u{1e71}'), ('t', '\u{331}', '\u{1e6f}'), ('u', '\u{300}', '\u{f9}'),
+    ('u', '\u{301}', '\u{fa}'), ('u', '\u{302}', '\u{fb}'), ('u', '\u{303}', '\u{169}'),
+    ('u', '\u{304}', '\u{16b}'), ('u', '\u{306}', '\u{16d}'), ('u', '\u{308}', '\u{fc}'),
+    ('u', '\u{309}', '\u{1ee7}'), ('u', '\u{30a}', '\u{16f}'), ('u', '\u{30b}', '\u{171}'),
+    ('u', '\u{323}', '\u{1ee5}'), ('u', '\u{324}', '\u{1e73}'), ('u', '\u{328}', '\u{173}'),
+    ('u', '\u{32d}', '\u{1e77}'), ('u', '\u{330}', '\u{1e75}'), ('v', '\u{303}', '\u{1e7d}'),
+    ('v', '\u{323}', '\u{1e7f}'), ('w', '\u{300}', '\u{1e81}'), ('w', '\u{301}', '\u{1e83}'),
+    ('w', '\u{302}', '\u{175}'), ('w', '\u{307}', '\u{1e87}'), ('w', '\u{308}', '\u{1e85}'),
+    ('w', '\u{30a}', '\u{1e98}'), ('w', '\u{323}', '\u{1e89}'), ('x', '\u{307}', '\u{1e8b}'),
+    ('x', '\u{308}', '\u{1e8d}'), ('y', '\u{300}', '\u{1ef3}'), ('y', '\u{301}', '\u{fd}'),
+    ('y', '\u{302}', '\u{177}'), ('y', '\u{303}', '\u{1ef9}'), ('y', '\u{307}', '\u{1e8f}'),
+    ('y', '\u{308}', '\u{ff}'), ('y', '\u{309}', '\u{1ef7}'), ('y', '\u{30a}', '\u{1e99}'),
+    ('y', '\u{323}', '\u{1ef5}'), ('z', '\u{301}', '\u{17a}'), ('z', '\u{302}', '\u{1e91}'),
+    ('z', '\u{307}', '\u{17c}'), ('z', '\u{30c}', '\u{17e}'), ('z', '\u{323}', '\u{1e93}'),
+    ('z', '\u{331}', '\u{1e95}'), ('\u{c2}', '\u{300}', '\u{1ea6}'),
+    ('\u{c2}', '\u{301}', '\u{1ea4}'), ('\u{c2}', '\u{303}', '\u{1eaa}'),
+    ('\u{c2}', '\u{309}', '\u{1ea8}'), ('\u{c7}', '\u{301}', '\u{1e08}'),
+    ('\u{ca}', '\u{300}', '\u{1ec0}'), ('\u{ca}', '\u{301}', '\u{1ebe}'),
+    ('\u{ca}', '\u{303}', '\u{1ec4}'), ('\u{ca}', '\u{309}', '\u{1ec2}'),
+    ('\u{cf}', '\u{301}', '\u{1e2e}'), ('\u{d4}', '\u{300}', '\u{1ed2}'),
+    ('\u{d4}', '\u{301}', '\u{1ed0}'), ('\u{d4}', '\u{303}', '\u{1ed6}'),
+    ('\u{d4}', '\u{309}', '\u{1ed4}'), ('\u{d5}', '\u{301}', '\u{1e4c}'),
+    ('\u{d5}', '\u{308}', '\u{1e4e}'), ('\u{e2}', '\u{300}', '\u{1ea7}'),
+    ('\u{e2}', '\u{301}', '\u{1ea5}'), ('\u{e2}', '\u{303}', '\u{1eab}'),
+    ('\u{e2}', '\u{309}', '\u{1ea9}'), ('\u{e7}', '\u{301}', '\u{1e09}'),
+    ('\u{ea}', '\u{300}', '\u{1ec1}'), ('\u{ea}', '\u{301}', '\u{1ebf}'),
+    ('\u{ea}', '\u{303}', '\u{1ec5}'), ('\u{ea}', '\u{309}', '\u{1ec3}'),
+    ('\u{ef}', '\u{301}', '\u{1e2f}'), ('\u{f4}', '\u{300}', '\u{1ed3}'),
+    ('\u{f4}', '\u{301}', '\u{1ed1}'), ('\u{f4}', '\u{303}', '\u{1ed7}'),
+    ('\u{f4}', '\u{309}', '\u{1ed5}'), ('\u{f5}', '\u{301}', '\u{1e4d}'),
+    ('\u{f5}', '\u{308}', '\u{1e4f}'), ('\u{102}', '\u{300}', '\u{1eb0}'),
+    ('\u{102}', '\u{301}', '\u{1eae}'), ('\u{102}', '\u{303}', '\u{1eb4}'),
+    ('\u{102}', '\u{309}', '\u{1eb2}'), ('\u{103}', '\u{300}', '\u{1eb1}'),
+    ('\u{103}', '\u{301}', '\u{1eaf}'), ('\u{103}', '\u{303}', '\u{1eb5}'),
+    ('\u{103}', '\u{309}', '\u{1eb3}'), ('\u{112}', '\u{300}', '\u{1e14}'),
+    ('\u{112}', '\u{301}', '\u{1e16}'), ('\u{113}', '\u{300}', '\u{1e15}'),
+    ('\u{113}', '\u{301}', '\u{1e17}'), ('\u{14c}', '\u{300}', '\u{1e50}'),
+    ('\u{14c}', '\u{301}', '\u{1e52}'), ('\u{14d}', '\u{300}', '\u{1e51}'),
+    ('\u{14d}', '\u{301}', '\u{1e53}'), ('\u{15a}', '\u{307}', '\u{1e64}'),
+    ('\u{15b}', '\u{307}', '\u{1e65}'), ('\u{160}', '\u{307}', '\u{1e66}'),
+    ('\u{161}', '\u{307}', '\u{1e67}'), ('\u{168}', '\u{301}', '\u{1e78}'),
+    ('\u{169}', '\u{301}', '\u{1e79}'), ('\u{16a}', '\u{308}', '\u{1e7a}'),
+    ('\u{16b}', '\u{308}', '\u{1e7b}'), ('\u{17f}', '\u{307}', '\u{1e9b}'),
+    ('\u{1a0}', '\u{300}', '\u{1edc}'), ('\u{1a0}', '\u{301}', '\u{1eda}'),
+    ('\u{1a0}', '\u{303}', '\u{1ee0}'), ('\u{1a0}', '\u{309}', '\u{1ede}'),
+    ('\u{1a0}', '\u{323}', '\u{1ee2}'), ('\u{1a1}', '\u{300}', '\u{1edd}'),
+    ('\u{1a1}', '\u{301}', '\u{1edb}'), ('\u{1a1}', '\u{303}', '\u{1ee1}'),
+    ('\u{1a1}', '\u{309}', '\u{1edf}'), ('\u{1a1}', '\u{323}', '\u{1ee3}'),
+    ('\u{1af}', '\u{300}', '\u{1eea}'), ('\u{1af}', '\u{301}', '\u{1ee8}'),
+    ('\u{1af}', '\u{303}', '\u{1eee}'), ('\u{1af}', '\u{309}', '\u{1eec}'),
+    ('\u{1af}', '\u{323}', '\u{1ef0}'), ('\u{1b0}', '\u{300}', '\u{1eeb}'),
+    ('\u{1b0}', '\u{301}', '\u{1ee9}'), ('\u{1b0}', '\u{303}', '\u{1eef}'),
+    ('\u{1b0}', '\u{309}', '\u{1eed}'), ('\u{1b0}', '\u{323}', '\u{1ef1}'),
+    ('\u{228}', '\u{306}', '\u{1e1c}'), ('\u{229}', '\u{306}', '\u{1e1d}'),
+    ('\u{1e36}', '\u{304}', '\u{1e38}'), ('\u{1e37}', '\u{304}', '\u{1e39}'),
+    ('\u{1e5a}', '\u{304}', '\u{1e5c}'), ('\u{1e5b}', '\u{304}', '\u{1e5d}'),
+    ('\u{1e62}', '\u{307}', '\u{1e68}'), ('\u{1e63}', '\u{307}', '\u{1e69}'),
+    ('\u{1ea0}', '\u{302}', '\u{1eac}'), ('\u{1ea0}', '\u{306}', '\u{1eb6}'),
+    ('\u{1ea1}', '\u{302}', '\u{1ead}'), ('\u{1ea1}', '\u{306}', '\u{1eb7}'),
+    ('\u{1eb8}', '\u{302}', '\u{1ec6}'), ('\u{1eb9}', '\u{302}', '\u{1ec7}'),
+    ('\u{1ecc}', '\u{302}', '\u{1ed8}'), ('\u{1ecd}', '\u{302}', '\u{1ed9}'),
+];
+
+// Word_Break property values, as used by word segmentation (UAX #29)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordBreak {
+    Other,
+    CR,
+    LF,
+    Newline,
+    Extend,
+    Zwj,
+    RegionalIndicator,
+    Format,
+    Katakana,
+    HebrewLetter,
+    ALetter,
+    SingleQuote,
+    DoubleQuote,
+    MidNumLet,
+    MidLetter,
+    MidNum,
+    Numeric,
+    ExtendNumLet,
+    WSegSpace,
+}
+
+use self::WordBreak as W;
+
+// the Word_Break property for all of Unicode, sorted, characters not listed are `Other`
+#[rustfmt::skip]
+pub const WORD_BREAK: &[(u32, u32, WordBreak)] = &[
+    (0x000A, 0x000A, W::LF), (0x000B, 0x000C, W::Newline), (0x000D, 0x000D, W::CR),
+    (0x0020, 0x0020, W::WSegSpace), (0x0022, 0x0022, W::DoubleQuote),
+    (0x0027, 0x0027, W::SingleQuote), (0x002C, 0x002C, W::MidNum),
+    (0x002E, 0x002E, W::MidNumLet), (0x0030, 0x0039, W::Numeric),
+    (0x003A, 0x003A, W::MidLetter), (0x003B, 0x003B, W::MidNum), (0x0041, 0x005A, W::ALetter),
+    (0x005F, 0x005F, W::ExtendNumLet), (0x0061, 0x007A, W::ALetter),
+    (0x0085, 0x0085, W::Newline), (0x00AA, 0x00AA, W::ALetter), (0x00AD, 0x00AD, W::Format),
+    (0x00B5, 0x00B5, W::ALetter), (0x00B7, 0x00B7, W::MidLetter), (0x00BA, 0x00BA, W::ALetter),
+    (0x00C0, 0x00D6, W::ALetter), (0x00D8, 0x00F6, W::ALetter), (0x00F8, 0x02D7, W::ALetter),
+    (0x02DE, 0x02FF, W::ALetter), (0x0300, 0x036F, W::Extend), (0x0370, 0x0374, W::ALetter),
+    (0x0376, 0x0377, W::ALetter), (0x037A, 0x037D, W::ALetter), (0x037E, 0x037E, W::MidNum),
+    (0x037F, 0x037F, W::ALetter), (0x0386, 0x0386, W::ALetter), (0x0387, 0x0387, W::MidLetter),
+    (0x0388, 0x038A, W::ALetter), (0x038C, 0x038C, W::ALetter), (0x038E, 0x03A1, W::ALetter),
+    (0x03A3, 0x03F5, W::ALetter), (0x03F7, 0x0481, W::ALetter), (0x0483, 0x0489, W::Extend),
+    (0x048A, 0x052F, W::ALetter), (0x0531, 0x0556, W::ALetter), (0x0559, 0x055C, W::ALetter),
+    (0x055E, 0x055E, W::ALetter), (0x055F, 0x055F, W::MidLetter), (0x0560, 0x0588, W::ALetter),
+    (0x0589, 0x0589, W::MidNum), (0x058A, 0x058A, W::ALetter), (0x0591, 0x05BD, W::Extend),
+    (0x05BF, 0x05BF, W::Extend), (0x05C1, 0x05C2, W::Extend), (0x05C4, 0x05C5, W::Extend),
+    (0x05C7, 0x05C7, W::Extend), (0x05D0, 0x05EA, W::HebrewLetter),
+    (0x05EF, 0x05F2, W::HebrewLetter), (0x05F3, 0x05F3, W::ALetter),
+    (0x05F4, 0x05F4, W::MidLetter), (0x0600, 0x0605, W::Format), (0x060C, 0x060D, W::MidNum),
+    (0x0610, 0x061A, W::Extend), (0x061C, 0x061C, W::Format), (0x0620, 0x064A, W::ALetter),
+    (0x064B, 0x065F, W::Extend), (0x0660, 0x0669, W::Numeric), (0x066B, 0x066B, W::Numeric),
+    (0x066C, 0x066C, W::MidNum), (0x066E, 0x066F, W::ALetter), (0x0670, 0x0670, W::Extend),
+    (0x0671, 0x06D3, W::ALetter), (0x06D5, 0x06D5, W::ALetter), (0x06D6, 0x06DC, W::Extend),
+    (0x06DD, 0x06DD, W::Format), (0x06DF, 0x06E4, W::Extend), (0x06E5, 0x06E6, W::ALetter),
+    (0x06E7, 0x06E8, W::Extend), (0x06EA, 0x06ED, W::Extend), (0x06EE, 0x06EF, W::ALetter),
+    (0x06F0, 0x06F9, W::Numeric), (0x06FA, 0x06FC, W::ALetter), (0x06FF, 0x06FF, W::ALetter),
+    (0x070F, 0x070F, W::Format), (0x0710, 0x0710, W::ALetter), (0x0711, 0x0711, W::Extend),
+    (0x0712, 0x072F, W::ALetter), (0x0730, 0x074A, W::Extend), (0x074D, 0x07A5, W::ALetter),
+    (0x07A6, 0x07B0, W::Extend), (0x07B1, 0x07B1, W::ALetter), (0x07C0, 0x07C9, W::Numeric),
+    (0x07CA, 0x07EA, W::ALetter), (0x07EB, 0x07F3, W::Extend), (0x07F4, 0x07F5, W::ALetter),
+    (0x07F8, 0x07F8, W::MidNum), (0x07FA, 0x07FA, W::ALetter), (0x07FD, 0x07FD, W::Extend),
+    (0x0800, 0x0815, W::ALetter), (0x0816, 0x0819, W::Extend), (0x081A, 0x081A, W::ALetter),
+    (0x081B, 0x0823, W::Extend), (0x0824, 0x0824, W::ALetter), (0x0825, 0x0827, W::Extend),
+    (0x0828, 0x0828, W::ALetter), (0x0829, 0x082D, W::Extend), (0x0840, 0x0858, W::ALetter),
+    (0x0859, 0x085B, W::Extend), (0x0860, 0x086A, W::ALetter), (0x0870, 0x0887, W::ALetter),
+    (0x0889, 0x088E, W::ALetter), (0x0890, 0x0891, W::Format), (0x0898, 0x089F, W::Extend),
+    (0x08A0, 0x08C9, W::ALetter), (0x08CA, 0x08E1, W::Extend), (0x08E2, 0x08E2, W::Format),
+    (0x08E3, 0x0903, W::Extend), (0x0904, 0x0939, W::ALetter), (0x093A, 0x093C, W::Extend),
+    (0x093D, 0x093D, W::ALetter), (0x093E, 0x094F, W::Extend), (0x0950, 0x0950, W::ALetter),
+    (0x0951, 0x0957, W::Extend), (0x0958, 0x0961, W::ALetter), (0x0962, 0x0963, W::Extend),
+    (0x0966, 0x096F, W::Numeric), (0x0971, 0x0980, W::ALetter), (0x0981, 0x0983, W::Extend),
+    (0x0985, 0x098C, W::ALetter), (0x098F, 0x0990, W::ALetter), (0x0993, 0x09A8, W::ALetter),
+    (0x09AA, 0x09B0, W::ALetter), (0x09B2, 0x09B2, W::ALetter), (0x09B6, 0x09B9, W::ALetter),
+    (0x09BC, 0x09BC, W::Extend), (0x09BD, 0x09BD, W::ALetter), (0x09BE, 0x09C4, W::Extend),
+    (0x09C7, 0x09C8, W::Extend), (0x09CB, 0x09CD, W::Extend), (0x09CE, 0x09CE, W::ALetter),
+    (0x09D7, 0x09D7, W::Extend), (0x09DC, 0x09DD, W::ALetter), (0x09DF, 0x09E1, W::ALetter),
+    (0x09E2, 0x09E3, W::Extend), (0x09E6, 0x09EF, W::Numeric), (0x09F0, 0x09F1, W::ALetter),
+    (0x09FC, 0x09FC, W::ALetter), (0x09FE, 0x09FE, W::Extend), (0x0A01, 0x0A03, W::Extend),
+    (0x0A05, 0x0A0A, W::ALetter), (0x0A0F, 0x0A10, W::ALetter), (0x0A13, 0x0A28, W::ALetter),
+    (0x0A2A, 0x0A30, W::ALetter), (0x0A32, 0x0A33, W::ALetter), (0x0A35, 0x0A36, W::ALetter),
+    (0x0A38, 0x0A39, W::ALetter), (0x0A3C, 0x0A3C, W::Extend), (0x0A3E, 0x0A42, W::Extend),
+    (0x0A47, 0x0A48, W::Extend), (0x0A4B, 0x0A4D, W::Extend), (0x0A51, 0x0A51, W::Extend),
+    (0x0A59, 0x0A5C, W::ALetter), (0x0A5E, 0x0A5E, W::ALetter), (0x0A66, 0x0A6F, W::Numeric),
+    (0x0A70, 0x0A71, W::Extend), (0x0A72, 0x0A74, W::ALetter), (0x0A75, 0x0A75, W::Extend),
+    (0x0A81, 0x0A83, W::Extend), (0x0A85, 0x0A8D, W::ALetter), (0x0A8F, 0x0A91, W::ALetter),
+    (0x0A93, 0x0AA8, W::ALetter), (0x0AAA, 0x0AB0, W::ALetter), (0x0AB2, 0x0AB3, W::ALetter),
+    (0x0AB5, 0x0AB9, W::ALetter), (0x0ABC, 0x0ABC, W::Extend), (0x0ABD, 0x0ABD, W::ALetter),
+    (0x0ABE, 0x0AC5, W::Extend), (0x0AC7, 0x0AC9, W::Extend), (0x0ACB, 0x0ACD, W::Extend),
+    (0x0AD0, 0x0AD0, W::ALetter), (0x0AE0, 0x0AE1, W::ALetter), (0x0AE2, 0x0AE3, W::Extend),
+    (0x0AE6, 0x0AEF, W::Numeric), (0x0AF9, 0x0AF9, W::ALetter), (0x0AFA, 0x0AFF, W::Extend),
+    (0x0B01, 0x0B03, W::Extend), (0x0B05, 0x0B0C, W::ALetter), (0x0B0F, 0x0B10, W::ALetter),
+    (0x0B13, 0x0B28, W::ALetter), (0x0B2A, 0x0B30, W::ALetter), (0x0B32, 0x0B33, W::ALetter),
+    (0x0B35, 0x0B39, W::ALetter), (0x0B3C, 0x0B3C, W::Extend), (0x0B3D, 0x0B3D, W::ALetter),
+    (0x0B3E, 0x0B44, W::Extend), (0x0B47, 0x0B48, W::Extend), (0x0B4B, 0x0B4D, W::Extend),
+    (0x0B55, 0x0B57, W::Extend), (0x0B5C, 0x0B5D, W::ALetter), (0x0B5F, 0x0B61, W::ALetter),
+    (0x0B62, 0x0B63, W::Extend), (0x0B66, 0x0B6F, W::Numeric), (0x0B71, 0x0B71, W::ALetter),
+    (0x0B82, 0x0B82, W::Extend), (0x0B83, 0x0B83, W::ALetter), (0x0B85, 0x0B8A, W::ALetter),
+    (0x0B8E, 0x0B90, W::ALetter), (0x0B92, 0x0B95, W::ALetter), (0x0B99, 0x0B9A, W::ALetter),
+    (0x0B9C, 0x0B9C, W::ALetter), (0x0B9E, 0x0B9F, W::ALetter), (0x0BA3, 0x0BA4, W::ALetter),
+    (0x0BA8, 0x0BAA, W::ALetter), (0x0BAE, 0x0BB9, W::ALetter), (0x0BBE, 0x0BC2, W::Extend),
+    (0x0BC6, 0x0BC8, W::Extend), (0x0BCA, 0x0BCD, W::Extend), (0x0BD0, 0x0BD0, W::ALetter),
+    (0x0BD7, 0x0BD7, W::Extend), (0x0BE6, 0x0BEF, W::Numeric), (0x0C00, 0x0C04, W::Extend),
+    (0x0C05, 0x0C0C, W::ALetter), (0x0C0E, 0x0C10, W::ALetter), (0x0C12, 0x0C28, W::ALetter),
+    (0x0C2A, 0x0C39, W::ALetter), (0x0C3C, 0x0C3C, W::Extend), (0x0C3D, 0x0C3D, W::ALetter),
+    (0x0C3E, 0x0C44, W::Extend), (0x0C46, 0x0C48, W::Extend), (0x0C4A, 0x0C4D, W::Extend),
+    (0x0C55, 0x0C56, W::Extend), (0x0C58, 0x0C5A, W::ALetter), (0x0C5D, 0x0C5D, W::ALetter),
+    (0x0C60, 0x0C61, W::ALetter), (0x0C62, 0x0C63, W::Extend), (0x0C66, 0x0C6F, W::Numeric),
+    (0x0C80, 0x0C80, W::ALetter), (0x0C81, 0x0C83, W::Extend), (0x0C85, 0x0C8C, W::ALetter),
+    (0x0C8E, 0x0C90, W::ALetter), (0x0C92, 0x0CA8, W::ALetter), (0x0CAA, 0x0CB3, W::ALetter),
+    (0x0CB5, 0x0CB9, W::ALetter), (0x0CBC, 0x0CBC, W::Extend), (0x0CBD, 0x0CBD, W::ALetter),
+    (0x0CBE, 0x0CC4, W::Extend), (0x0CC6, 0x0CC8, W::Extend), (0x0CCA, 0x0CCD, W::Extend),
+    (0x0CD5, 0x0CD6, W::Extend), (0x0CDD, 0x0CDE, W::ALetter), (0x0CE0, 0x0CE1, W::ALetter),
+    (0x0CE2, 0x0CE3, W::Extend), (0x0CE6, 0x0CEF, W::Numeric), (0x0CF1, 0x0CF2, W::ALetter),
+    (0x0D00, 0x0D03, W::Extend), (0x0D04, 0x0D0C, W::ALetter), (0x0D0E, 0x0D10, W::ALetter),
+    (0x0D12, 0x0D3A, W::ALetter), (0x0D3B, 0x0D3C, W::Extend), (0x0D3D, 0x0D3D, W::ALetter),
+    (0x0D3E, 0x0D44, W::Extend), (0x0D46, 0x0D48, W::Extend), (0x0D4A, 0x0D4D, W::Extend),
+    (0x0D4E, 0x0D4E, W::ALetter), (0x0D54, 0x0D56, W::ALetter), (0x0D57, 0x0D57, W::Extend),
+    (0x0D5F, 0x0D61, W::ALetter), (0x0D62, 0x0D63, W::Extend), (0x0D66, 0x0D6F, W::Numeric),
+    (0x0D7A, 0x0D7F, W::ALetter), (0x0D81, 0x0D83, W::Extend), (0x0D85, 0x0D96, W::ALetter),
+    (0x0D9A, 0x0DB1, W::ALetter), (0x0DB3, 0x0DBB, W::ALetter), (0x0DBD, 0x0DBD, W::ALetter),
+    (0x0DC0, 0x0DC6, W::ALetter), (0x0DCA, 0x0DCA, W::Extend), (0x0DCF, 0x0DD4, W::Extend),
+    (0x0DD6, 0x0DD6, W::Extend), (0x0DD8, 0x0DDF, W::Extend), (0x0DE6, 0x0DEF, W::Numeric),
+    (0x0DF2, 0x0DF3, W::Extend), (0x0E31, 0x0E31, W::Extend), (0x0E34, 0x0E3A, W::Extend),
+    (0x0E47, 0x0E4E, W::Extend), (0x0E50, 0x0E59, W::Numeric), (0x0EB1, 0x0EB1, W::Extend),
+    (0x0EB4, 0x0EBC, W::Extend), (0x0EC8, 0x0ECD, W::Extend), (0x0ED0, 0x0ED9, W::Numeric),
+    (0x0F00, 0x0F00, W::ALetter), (0x0F18, 0x0F19, W::Extend), (0x0F20, 0x0F29, W::Numeric),
+    (0x0F35, 0x0F35, W::Extend), (0x0F37, 0x0F37, W::Extend), (0x0F39, 0x0F39, W::Extend),
+    (0x0F3E, 0x0F3F, W::Extend), (0x0F40, 0x0F47, W::ALetter), (0x0F49, 0x0F6C, W::ALetter),
+    (0x0F71, 0x0F84, W::Extend), (0x0F86, 0x0F87, W::Extend), (0x0F88, 0x0F8C, W::ALetter),
+    (0x0F8D, 0x0F97, W::Extend), (0x0F99, 0x0FBC, W::Extend), (0x0FC6, 0x0FC6, W::Extend),
+    (0x102B, 0x103E, W::Extend), (0x1040, 0x1049, W::Numeric), (0x1056, 0x1059, W::Extend),
+    (0x105E, 0x1060, W::Extend), (0x1062, 0x1064, W::Extend), (0x1067, 0x106D, W::Extend),
+    (0x1071, 0x1074, W::Extend), (0x1082, 0x108D, W::Extend), (0x108F, 0x108F, W::Extend),
+    (0x1090, 0x1099, W::Numeric), (0x109A, 0x109D, W::Extend), (0x10A0, 0x10C5, W::ALetter),
+    (0x10C7, 0x10C7, W::ALetter), (0x10CD, 0x10CD, W::ALetter), (0x10D0, 0x10FA, W::ALetter),
+    (0x10FC, 0x1248, W::ALetter), (0x124A, 0x124D, W::ALetter), (0x1250, 0x1256, W::ALetter),
+    (0x1258, 0x1258, W::ALetter), (0x125A, 0x125D, W::ALetter), (0x1260, 0x1288, W::ALetter),
+    (0x128A, 0x128D, W::ALetter), (0x1290, 0x12B0, W::ALetter), (0x12B2, 0x12B5, W::ALetter),
+    (0x12B8, 0x12BE, W::ALetter), (0x12C0, 0x12C0, W::ALetter), (0x12C2, 0x12C5, W::ALetter),
+    (0x12C8, 0x12D6, W::ALetter), (0x12D8, 0x1310, W::ALetter), (0x1312, 0x1315, W::ALetter),
+    (0x1318, 0x135A, W::ALetter), (0x135D, 0x135F, W::Extend), (0x1380, 0x138F, W::ALetter),
+    (0x13A0, 0x13F5, W::ALetter), (0x13F8, 0x13FD, W::ALetter), (0x1401, 0x166C, W::ALetter),
+    (0x166F, 0x167F, W::ALetter), (0x1680, 0x1680, W::WSegSpace), (0x1681, 0x169A, W::ALetter),
+    (0x16A0, 0x16EA, W::ALetter), (0x16EE, 0x16F8, W::ALetter), (0x1700, 0x1711, W::ALetter),
+    (0x1712, 0x1715, W::Extend), (0x171F, 0x1731, W::ALetter), (0x1732, 0x1734, W::Extend),
+    (0x1740, 0x1751, W::ALetter), (0x1752, 0x1753, W::Extend), (0x1760, 0x176C, W::ALetter),
+    (0x176E, 0x1770, W::ALetter), (0x1772, 0x1773, W::Extend), (0x17B4, 0x17D3, W::Extend),
+    (0x17DD, 0x17DD, W::Extend), (0x17E0, 0x17E9, W::Numeric), (0x180B, 0x180D, W::Extend),
+    (0x180E, 0x180E, W::Format), (0x180F, 0x180F, W::Extend), (0x1810, 0x1819, W::Numeric),
+    (0x1820, 0x1878, W::ALetter), (0x1880, 0x1884, W::ALetter), (0x1885, 0x1886, W::Extend),
+    (0x1887, 0x18A8, W::ALetter), (0x18A9, 0x18A9, W::Extend), (0x18AA, 0x18AA, W::ALetter),
+    (0x18B0, 0x18F5, W::ALetter), (0x1900, 0x191E, W::ALetter), (0x1920, 0x192B, W::Extend),
+    (0x1930, 0x193B, W::Extend), (0x1946, 0x194F, W::Numeric), (0x19D0, 0x19D9, W::Numeric),
+    (0x1A00, 0x1A16, W::ALetter), (0x1A17, 0x1A1B, W::Extend), (0x1A55, 0x1A5E, W::Extend),
+    (0x1A60, 0x1A7C, W::Extend), (0x1A7F, 0x1A7F, W::Extend), (0x1A80, 0x1A89, W::Numeric),
+    (0x1A90, 0x1A99, W::Numeric), (0x1AB0, 0x1ACE, W::Extend), (0x1B00, 0x1B04, W::Extend),
+    (0x1B05, 0x1B33, W::ALetter), (0x1B34, 0x1B44, W::Extend), (0x1B45, 0x1B4C, W::ALetter),
+    (0x1B50, 0x1B59, W::Numeric), (0x1B6B, 0x1B73, W::Extend), (0x1B80, 0x1B82, W::Extend),
+    (0x1B83, 0x1BA0, W::ALetter), (0x1BA1, 0x1BAD, W::Extend), (0x1BAE, 0x1BAF, W::ALetter),
+    (0x1BB0, 0x1BB9, W::Numeric), (0x1BBA, 0x1BE5, W::ALetter), (0x1BE6, 0x1BF3, W::Extend),
+    (0x1C00, 0x1C23, W::ALetter), (0x1C24, 0x1C37, W::Extend), (0x1C40, 0x1C49, W::Numeric),
+    (0x1C4D, 0x1C4F, W::ALetter), (0x1C50, 0x1C59, W::Numeric), (0x1C5A, 0x1C7D, W::ALetter),
+    (0x1C80, 0x1C88, W::ALetter), (0x1C90, 0x1CBA, W::ALetter), (0x1CBD, 0x1CBF, W::ALetter),
+    (0x1CD0, 0x1CD2, W::Extend), (0x1CD4, 0x1CE8, W::Extend), (0x1CE9, 0x1CEC, W::ALetter),
+    (0x1CED, 0x1CED, W::Extend), (0x1CEE, 0x1CF3, W::ALetter), (0x1CF4, 0x1CF4, W::Extend),
+    (0x1CF5, 0x1CF6, W::ALetter), (0x1CF7, 0x1CF9, W::Extend), (0x1CFA, 0x1CFA, W::ALetter),
+    (0x1D00, 0x1DBF, W::ALetter), (0x1DC0, 0x1DFF, W::Extend), (0x1E00, 0x1F15, W::ALetter),
+    (0x1F18, 0x1F1D, W::ALetter), (0x1F20, 0x1F45, W::ALetter), (0x1F48, 0x1F4D, W::ALetter),
+    (0x1F50, 0x1F57, W::ALetter), (0x1F59, 0x1F59, W::ALetter), (0x1F5B, 0x1F5B, W::ALetter),
+    (0x1F5D, 0x1F5D, W::ALetter), (0x1F5F, 0x1F7D, W::ALetter), (0x1F80, 0x1FB4, W::ALetter),
+    (0x1FB6, 0x1FBC, W::ALetter), (0x1FBE, 0x1FBE, W::ALetter), (0x1FC2, 0x1FC4, W::ALetter),
+    (0x1FC6, 0x1FCC, W::ALetter), (0x1FD0, 0x1FD3, W::ALetter), (0x1FD6, 0x1FDB, W::ALetter),
+    (0x1FE0, 0x1FEC, W::ALetter), (0x1FF2, 0x1FF4, W::ALetter), (0x1FF6, 0x1FFC, W::ALetter),
+    (0x2000, 0x2006, W::WSegSpace), (0x2008, 0x200A, W::WSegSpace), (0x200C, 0x200C, W::Extend),
+    (0x200D, 0x200D, W::Zwj), (0x200E, 0x200F, W::Format), (0x2018, 0x2019, W::MidNumLet),
+    (0x2024, 0x2024, W::MidNumLet), (0x2027, 0x2027, W::MidLetter),
+    (0x2028, 0x2029, W::Newline), (0x202A, 0x202E, W::Format),
+    (0x202F, 0x202F, W::ExtendNumLet), (0x203F, 0x2040, W::ExtendNumLet),
+    (0x2044, 0x2044, W::MidNum), (0x2054, 0x2054, W::ExtendNumLet),
+    (0x205F, 0x205F, W::WSegSpace), (0x2060, 0x2064, W::Format), (0x2066, 0x206F, W::Format),
+    (0x2071, 0x2071, W::ALetter), (0x207F, 0x207F, W::ALetter), (0x2090, 0x209C, W::ALetter),
+    (0x20D0, 0x20F0, W::Extend), (0x2102, 0x2102, W::ALetter), (0x2107, 0x2107, W::ALetter),
+    (0x210A, 0x2113, W::ALetter), (0x2115, 0x2115, W::ALetter), (0x2119, 0x211D, W::ALetter),
+    (0x2124, 0x2124, W::ALetter), (0x2126, 0x2126, W::ALetter), (0x2128, 0x2128, W::ALetter),
+    (0x212A, 0x212D, W::ALetter), (0x212F, 0x2139, W::ALetter), (0x213C, 0x213F, W::ALetter),
+    (0x2145, 0x2149, W::ALetter), (0x214E, 0x214E, W::ALetter), (0x2160, 0x2188, W::ALetter),
+    (0x24B6, 0x24E9, W::ALetter), (0x2C00, 0x2CE4, W::ALetter), (0x2CEB, 0x2CEE, W::ALetter),
+    (0x2CEF, 0x2CF1, W::Extend), (0x2CF2, 0x2CF3, W::ALetter), (0x2D00, 0x2D25, W::ALetter),
+    (0x2D27, 0x2D27, W::ALetter), (0x2D2D, 0x2D2D, W::ALetter), (0x2D30, 0x2D67, W::ALetter),
+    (0x2D6F, 0x2D6F, W::ALetter), (0x2D7F, 0x2D7F, W::Extend), (0x2D80, 0x2D96, W::ALetter),
+    (0x2DA0, 0x2DA6, W::ALetter), (0x2DA8, 0x2DAE, W::ALetter), (0x2DB0, 0x2DB6, W::ALetter),
+    (0x2DB8, 0x2DBE, W::ALetter), (0x2DC0, 0x2DC6, W::ALetter), (0x2DC8, 0x2DCE, W::ALetter),
+    (0x2DD0, 0x2DD6, W::ALetter), (0x2DD8, 0x2DDE, W::ALetter), (0x2DE0, 0x2DFF, W::Extend),
+    (0x2E2F, 0x2E2F, W::ALetter), (0x3000, 0x3000, W::WSegSpace), (0x3005, 0x3005, W::ALetter),
+    (0x302A, 0x302F, W::Extend), (0x3031, 0x3035, W::Katakana), (0x303B, 0x303C, W::ALetter),
+    (0x3099, 0x309A, W::Extend), (0x309B, 0x309C, W::Katakana), (0x30A0, 0x30FA, W::Katakana),
+    (0x30FC, 0x30FF, W::Katakana), (0x3105, 0x312F, W::ALetter), (0x3131, 0x318E, W::ALetter),
+    (0x31A0, 0x31BF, W::ALetter), (0x31F0, 0x31FF, W::Katakana), (0x32D0, 0x32FE, W::Katakana),
+    (0x3300, 0x3357, W::Katakana), (0xA000, 0xA48C, W::ALetter), (0xA4D0, 0xA4FD, W::ALetter),
+    (0xA500, 0xA60C, W::ALetter), (0xA610, 0xA61F, W::ALetter), (0xA620, 0xA629, W::Numeric),
+    (0xA62A, 0xA62B, W::ALetter), (0xA640, 0xA66E, W::ALetter), (0xA66F, 0xA672, W::Extend),
+    (0xA674, 0xA67D, W::Extend), (0xA67F, 0xA69D, W::ALetter), (0xA69E, 0xA69F, W::Extend),
+    (0xA6A0, 0xA6EF, W::ALetter), (0xA6F0, 0xA6F1, W::Extend), (0xA708, 0xA7CA, W::ALetter),
+    (0xA7D0, 0xA7D1, W::ALetter), (0xA7D3, 0xA7D3, W::ALetter), (0xA7D5, 0xA7D9, W::ALetter),
+    (0xA7F2, 0xA801, W::ALetter), (0xA802, 0xA802, W::Extend), (0xA803, 0xA805, W::ALetter),
+    (0xA806, 0xA806, W::Extend), (0xA807, 0xA80A, W::ALetter), (0xA80B, 0xA80B, W::Extend),
+    (0xA80C, 0xA822, W::ALetter), (0xA823, 0xA827, W::Extend), (0xA82C, 0xA82C, W::Extend),
+    (0xA840, 0xA873, W::ALetter), (0xA880, 0xA881, W::Extend), (0xA882, 0xA8B3, W::ALetter),
+    (0xA8B4, 0xA8C5, W::Extend), (0xA8D0, 0xA8D9, W::Numeric), (0xA8E0, 0xA8F1, W::Extend),
+    (0xA8F2, 0xA8F7, W::ALetter), (0xA8FB, 0xA8FB, W::ALetter), (0xA8FD, 0xA8FE, W::ALetter),
+    (0xA8FF, 0xA8FF, W::Extend), (0xA900, 0xA909, W::Numeric), (0xA90A, 0xA925, W::ALetter),
+    (0xA926, 0xA92D, W::Extend), (0xA930, 0xA946, W::ALetter), (0xA947, 0xA953, W::Extend),
+    (0xA960, 0xA97C, W::ALetter), (0xA980, 0xA983, W::Extend), (0xA984, 0xA9B2, W::ALetter),
+    (0xA9B3, 0xA9C0, W::Extend), (0xA9CF, 0xA9CF, W::ALetter), (0xA9D0, 0xA9D9, W::Numeric),
+    (0xA9E5, 0xA9E5, W::Extend), (0xA9F0, 0xA9F9, W::Numeric), (0xAA00, 0xAA28, W::ALetter),
+    (0xAA29, 0xAA36, W::Extend), (0xAA40, 0xAA42, W::ALetter), (0xAA43, 0xAA43, W::Extend),
+    (0xAA44, 0xAA4B, W::ALetter), (0xAA4C, 0xAA4D, W::Extend), (0xAA50, 0xAA59, W::Numeric),
+    (0xAA7B, 0xAA7D, W::Extend), (0xAAB0, 0xAAB0, W::Extend), (0xAAB2, 0xAAB4, W::Extend),
+    (0xAAB7, 0xAAB8, W::Extend), (0xAABE, 0xAABF, W::Extend), (0xAAC1, 0xAAC1, W::Extend),
+    (0xAAE0, 0xAAEA, W::ALetter), (0xAAEB, 0xAAEF, W::Extend), (0xAAF2, 0xAAF4, W::ALetter),
+    (0xAAF5, 0xAAF6, W::Extend), (0xAB01, 0xAB06, W::ALetter), (0xAB09, 0xAB0E, W::ALetter),
+    (0xAB11, 0xAB16, W::ALetter), (0xAB20, 0xAB26, W::ALetter), (0xAB28, 0xAB2E, W::ALetter),
+    (0xAB30, 0xAB69, W::ALetter), (0xAB70, 0xABE2, W::ALetter), (0xABE3, 0xABEA, W::Extend),
+    (0xABEC, 0xABED, W::Extend), (0xABF0, 0xABF9, W::Numeric), (0xAC00, 0xD7A3, W::ALetter),
+    (0xD7B0, 0xD7C6, W::ALetter), (0xD7CB, 0xD7FB, W::ALetter), (0xFB00, 0xFB06, W::ALetter),
+    (0xFB13, 0xFB17, W::ALetter), (0xFB1D, 0xFB1D, W::HebrewLetter),
+    (0xFB1E, 0xFB1E, W::Extend), (0xFB1F, 0xFB28, W::HebrewLetter),
+    (0xFB2A, 0xFB36, W::HebrewLetter), (0xFB38, 0xFB3C, W::HebrewLetter),
+    (0xFB3E, 0xFB3E, W::HebrewLetter), (0xFB40, 0xFB41, W::HebrewLetter),
+    (0xFB43, 0xFB44, W::HebrewLetter), (0xFB46, 0xFB4F, W::HebrewLetter),
+    (0xFB50, 0xFBB1, W::ALetter), (0xFBD3, 0xFD3D, W::ALetter), (0xFD50, 0xFD8F, W::ALetter),
+    (0xFD92, 0xFDC7, W::ALetter), (0xFDF0, 0xFDFB, W::ALetter), (0xFE00, 0xFE0F, W::Extend),
+    (0xFE10, 0xFE10, W::MidNum), (0xFE13, 0xFE13, W::MidLetter), (0xFE14, 0xFE14, W::MidNum),
+    (0xFE20, 0xFE2F, W::Extend), (0xFE33, 0xFE34, W::ExtendNumLet),
+    (0xFE4D, 0xFE4F, W::ExtendNumLet), (0xFE50, 0xFE50, W::MidNum),
+    (0xFE52, 0xFE52, W::MidNumLet), (0xFE54, 0xFE54, W::MidNum), (0xFE55, 0xFE55, W::MidLetter),
+    (0xFE70, 0xFE74, W::ALetter), (0xFE76, 0xFEFC, W::ALetter), (0xFEFF, 0xFEFF, W::Format),
+    (0xFF07, 0xFF07, W::MidNumLet), (0xFF0C, 0xFF0C, W::MidNum), (0xFF0E, 0xFF0E, W::MidNumLet),
+    (0xFF10, 0xFF19, W::Numeric), (0xFF1A, 0xFF1A, W::MidLetter), (0xFF1B, 0xFF1B, W::MidNum),
+    (0xFF21, 0xFF3A, W::ALetter), (0xFF3F, 0xFF3F, W::ExtendNumLet),
+    (0xFF41, 0xFF5A, W::ALetter), (0xFF66, 0xFF9D, W::Katakana), (0xFF9E, 0xFF9F, W::Extend),
+    (0xFFA0, 0xFFBE, W::ALetter), (0xFFC2, 0xFFC7, W::ALetter), (0xFFCA, 0xFFCF, W::ALetter),
+    (0xFFD2, 0xFFD7, W::ALetter), (0xFFDA, 0xFFDC, W::ALetter), (0xFFF9, 0xFFFB, W::Format),
+    (0x10000, 0x1000B, W::ALetter), (0x1000D, 0x10026, W::ALetter),
+    (0x10028, 0x1003A, W::ALetter), (0x1003C, 0x1003D, W::ALetter),
+    (0x1003F, 0x1004D, W::ALetter), (0x10050, 0x1005D, W::ALetter),
+    (0x10080, 0x100FA, W::ALetter), (0x10140, 0x10174, W::ALetter),
+    (0x101FD, 0x101FD, W::Extend), (0x10280, 0x1029C, W::ALetter),
+    (0x102A0, 0x102D0, W::ALetter), (0x102E0, 0x102E0, W::Extend),
+    (0x10300, 0x1031F, W::ALetter), (0x1032D, 0x1034A, W::ALetter),
+    (0x10350, 0x10375, W::ALetter), (0x10376, 0x1037A, W::Extend),
+    (0x10380, 0x1039D, W::ALetter), (0x103A0, 0x103C3, W::ALetter),
+    (0x103C8, 0x103CF, W::ALetter), (0x103D1, 0x103D5, W::ALetter),
+    (0x10400, 0x1049D, W::ALetter), (0x104A0, 0x104A9, W::Numeric),
+    (0x104B0, 0x104D3, W::ALetter), (0x104D8, 0x104FB, W::ALetter),
+    (0x10500, 0x10527, W::ALetter), (0x10530, 0x10563, W::ALetter),
+    (0x10570, 0x1057A, W::ALetter), (0x1057C, 0x1058A, W::ALetter),
+    (0x1058C, 0x10592, W::ALetter), (0x10594, 0x10595, W::ALetter),
+    (0x10597, 0x105A1, W::ALetter), (0x105A3, 0x105B1, W::ALetter),
+    (0x105B3, 0x105B9, W::ALetter), (0x105BB, 0x105BC, W::ALetter),
+    (0x10600, 0x10736, W::ALetter), (0x10740, 0x10755, W::ALetter),
+    (0x10760, 0x10767, W::ALetter), (0x10780, 0x10785, W::ALetter),
+    (0x10787, 0x107B0, W::ALetter), (0x107B2, 0x107BA, W::ALetter),
+    (0x10800, 0x10805, W::ALetter), (0x10808, 0x10808, W::ALetter),
+    (0x1080A, 0x10835, W::ALetter), (0x10837, 0x10838, W::ALetter),
+    (0x1083C, 0x1083C, W::ALetter), (0x1083F, 0x10855, W::ALetter),
+    (0x10860, 0x10876, W::ALetter), (0x10880, 0x1089E, W::ALetter),
+    (0x108E0, 0x108F2, W::ALetter), (0x108F4, 0x108F5, W::ALetter),
+    (0x10900, 0x10915, W::ALetter), (0x10920, 0x10939, W::ALetter),
+    (0x10980, 0x109B7, W::ALetter), (0x109BE, 0x109BF, W::ALetter),
+    (0x10A00, 0x10A00, W::ALetter), (0x10A01, 0x10A03, W::Extend),
+    (0x10A05, 0x10A06, W::Extend), (0x10A0C, 0x10A0F, W::Extend),
+    (0x10A10, 0x10A13, W::ALetter), (0x10A15, 0x10A17, W::ALetter),
+    (0x10A19, 0x10A35, W::ALetter), (0x10A38, 0x10A3A, W::Extend),
+    (0x10A3F, 0x10A3F, W::Extend), (0x10A60, 0x10A7C, W::ALetter),
+    (0x10A80, 0x10A9C, W::ALetter), (0x10AC0, 0x10AC7, W::ALetter),
+    (0x10AC9, 0x10AE4, W::ALetter), (0x10AE5, 0x10AE6, W::Extend),
+    (0x10B00, 0x10B35, W::ALetter), (0x10B40, 0x10B55, W::ALetter),
+    (0x10B60, 0x10B72, W::ALetter), (0x10B80, 0x10B91, W::ALetter),
+    (0x10C00, 0x10C48, W::ALetter), (0x10C80, 0x10CB2, W::ALetter),
+    (0x10CC0, 0x10CF2, W::ALetter), (0x10D00, 0x10D23, W::ALetter),
+    (0x10D24, 0x10D27, W::Extend), (0x10D30, 0x10D39, W::Numeric),
+    (0x10E80, 0x10EA9, W::ALetter), (0x10EAB, 0x10EAC, W::Extend),
+    (0x10EB0, 0x10EB1, W::ALetter), (0x10F00, 0x10F1C, W::ALetter),
+    (0x10F27, 0x10F27, W::ALetter), (0x10F30, 0x10F45, W::ALetter),
+    (0x10F46, 0x10F50, W::Extend), (0x10F70, 0x10F81, W::ALetter),
+    (0x10F82, 0x10F85, W::Extend), (0x10FB0, 0x10FC4, W::ALetter),
+    (0x10FE0, 0x10FF6, W::ALetter), (0x11000, 0x11002, W::Extend),
+    (0x11003, 0x11037, W::ALetter), (0x11038, 0x11046, W::Extend),
+    (0x11066, 0x1106F, W::Numeric), (0x11070, 0x11070, W::Extend),
+    (0x11071, 0x11072, W::ALetter), (0x11073, 0x11074, W::Extend),
+    (0x11075, 0x11075, W::ALetter), (0x1107F, 0x11082, W::Extend),
+    (0x11083, 0x110AF, W::ALetter), (0x110B0, 0x110BA, W::Extend),
+    (0x110BD, 0x110BD, W::Format), (0x110C2, 0x110C2, W::Extend), (0x110CD, 0x110CD, W::Format),
+    (0x110D0, 0x110E8, W::ALetter), (0x110F0, 0x110F9, W::Numeric),
+    (0x11100, 0x11102, W::Extend), (0x11103, 0x11126, W::ALetter),
+    (0x11127, 0x11134, W::Extend), (0x11136, 0x1113F, W::Numeric),
+    (0x11144, 0x11144, W::ALetter), (0x11145, 0x11146, W::Extend),
+    (0x11147, 0x11147, W::ALetter), (0x11150, 0x11172, W::ALetter),
+    (0x11173, 0x11173, W::Extend), (0x11176, 0x11176, W::ALetter),
+    (0x11180, 0x11182, W::Extend), (0x11183, 0x111B2, W::ALetter),
+    (0x111B3, 0x111C0, W::Extend), (0x111C1, 0x111C4, W::ALetter),
+    (0x111C9, 0x111CC, W::Extend), (0x111CE, 0x111CF, W::Extend),
+    (0x111D0, 0x111D9, W::Numeric), (0x111DA, 0x111DA, W::ALetter),
+    (0x111DC, 0x111DC, W::ALetter), (0x11200, 0x11211, W::ALetter),
+    (0x11213, 0x1122B, W::ALetter), (0x1122C, 0x11237, W::Extend),
+    (0x1123E, 0x1123E, W::Extend), (0x11280, 0x11286, W::ALetter),
+    (0x11288, 0x11288, W::ALetter), (0x1128A, 0x1128D, W::ALetter),
+    (0x1128F, 0x1129D, W::ALetter), (0x1129F, 0x112A8, W::ALetter),
+    (0x112B0, 0x112DE, W::ALetter), (0x112DF, 0x112EA, W::Extend),
+    (0x112F0, 0x112F9, W::Numeric), (0x11300, 0x11303, W::Extend),
+    (0x11305, 0x1130C, W::ALetter), (0x1130F, 0x11310, W::ALetter),
+    (0x11313, 0x11328, W::ALetter), (0x1132A, 0x11330, W::ALetter),
+    (0x11332, 0x11333, W::ALetter), (0x11335, 0x11339, W::ALetter),
+    (0x1133B, 0x1133C, W::Extend), (0x1133D, 0x1133D, W::ALetter),
+    (0x1133E, 0x11344, W::Extend), (0x11347, 0x11348, W::Extend), (0x1134B, 0x1134D, W::Extend),
+    (0x11350, 0x11350, W::ALetter), (0x11357, 0x11357, W::Extend),
+    (0x1135D, 0x11361, W::ALetter), (0x11362, 0x11363, W::Extend),
+    (0x11366, 0x1136C, W::Extend), (0x11370, 0x11374, W::Extend),
+    (0x11400, 0x11434, W::ALetter), (0x11435, 0x11446, W::Extend),
+    (0x11447, 0x1144A, W::ALetter), (0x11450, 0x11459, W::Numeric),
+    (0x1145E, 0x1145E, W::Extend), (0x1145F, 0x11461, W::ALetter),
+    (0x11480, 0x114AF, W::ALetter), (0x114B0, 0x114C3, W::Extend),
+    (0x114C4, 0x114C5, W::ALetter), (0x114C7, 0x114C7, W::ALetter),
+    (0x114D0, 0x114D9, W::Numeric), (0x11580, 0x115AE, W::ALetter),
+    (0x115AF, 0x115B5, W::Extend), (0x115B8, 0x115C0, W::Extend),
+    (0x115D8, 0x115DB, W::ALetter), (0x115DC, 0x115DD, W::Extend),
+    (0x11600, 0x1162F, W::ALetter), (0x11630, 0x11640, W::Extend),
+    (0x11644, 0x11644, W::ALetter), (0x11650, 0x11659, W::Numeric),
+    (0x11680, 0x116AA, W::ALetter), (0x116AB, 0x116B7, W::Extend),
+    (0x116B8, 0x116B8, W::ALetter), (0x116C0, 0x116C9, W::Numeric),
+    (0x1171D, 0x1172B, W::Extend), (0x11730, 0x11739, W::Numeric),
+    (0x11800, 0x1182B, W::ALetter), (0x1182C, 0x1183A, W::Extend),
+    (0x118A0, 0x118DF, W::ALetter), (0x118E0, 0x118E9, W::Numeric),
+    (0x118FF, 0x11906, W::ALetter), (0x11909, 0x11909, W::ALetter),
+    (0x1190C, 0x11913, W::ALetter), (0x11915, 0x11916, W::ALetter),
+    (0x11918, 0x1192F, W::ALetter), (0x11930, 0x11935, W::Extend),
+    (0x11937, 0x11938, W::Extend), (0x1193B, 0x1193E, W::Extend),
+    (0x1193F, 0x1193F, W::ALetter), (0x11940, 0x11940, W::Extend),
+    (0x11941, 0x11941, W::ALetter), (0x11942, 0x11943, W::Extend),
+    (0x11950, 0x11959, W::Numeric), (0x119A0, 0x119A7, W::ALetter),
+    (0x119AA, 0x119D0, W::ALetter), (0x119D1, 0x119D7, W::Extend),
+    (0x119DA, 0x119E0, W::Extend), (0x119E1, 0x119E1, W::ALetter),
+    (0x119E3, 0x119E3, W::ALetter), (0x119E4, 0x119E4, W::Extend),
+    (0x11A00, 0x11A00, W::ALetter), (0x11A01, 0x11A0A, W::Extend),
+    (0x11A0B, 0x11A32, W::ALetter), (0x11A33, 0x11A39, W::Extend),
+    (0x11A3A, 0x11A3A, W::ALetter), (0x11A3B, 0x11A3E, W::Extend),
+    (0x11A47, 0x11A47, W::Extend), (0x11A50, 0x11A50, W::ALetter),
+    (0x11A51, 0x11A5B, W::Extend), (0x11A5C, 0x11A89, W::ALetter),
+    (0x11A8A, 0x11A99, W::Extend), (0x11A9D, 0x11A9D, W::ALetter),
+    (0x11AB0, 0x11AF8, W::ALetter), (0x11C00, 0x11C08, W::ALetter),
+    (0x11C0A, 0x11C2E, W::ALetter), (0x11C2F, 0x11C36, W::Extend),
+    (0x11C38, 0x11C3F, W::Extend), (0x11C40, 0x11C40, W::ALetter),
+    (0x11C50, 0x11C59, W::Numeric), (0x11C72, 0x11C8F, W::ALetter),
+    (0x11C92, 0x11CA7, W::Extend), (0x11CA9, 0x11CB6, W::Extend),
+    (0x11D00, 0x11D06, W::ALetter), (0x11D08, 0x11D09, W::ALetter),
+    (0x11D0B, 0x11D30, W::ALetter), (0x11D31, 0x11D36, W::Extend),
+    (0x11D3A, 0x11D3A, W::Extend), (0x11D3C, 0x11D3D, W::Extend), (0x11D3F, 0x11D45, W::Extend),
+    (0x11D46, 0x11D46, W::ALetter), (0x11D47, 0x11D47, W::Extend),
+    (0x11D50, 0x11D59, W::Numeric), (0x11D60, 0x11D65, W::ALetter),
+    (0x11D67, 0x11D68, W::ALetter), (0x11D6A, 0x11D89, W::ALetter),
+    (0x11D8A, 0x11D8E, W::Extend), (0x11D90, 0x11D91, W::Extend), (0x11D93, 0x11D97, W::Extend),
+    (0x11D98, 0x11D98, W::ALetter), (0x11DA0, 0x11DA9, W::Numeric),
+    (0x11EE0, 0x11EF2, W::ALetter), (0x11EF3, 0x11EF6, W::Extend),
+    (0x11FB0, 0x11FB0, W::ALetter), (0x12000, 0x12399, W::ALetter),
+    (0x12400, 0x1246E, W::ALetter), (0x12480, 0x12543, W::ALetter),
+    (0x12F90, 0x12FF0, W::ALetter), (0x13000, 0x1342E, W::ALetter),
+    (0x13430, 0x13438, W::Format), (0x14400, 0x14646, W::ALetter),
+    (0x16800, 0x16A38, W::ALetter), (0x16A40, 0x16A5E, W::ALetter),
+    (0x16A60, 0x16A69, W::Numeric), (0x16A70, 0x16ABE, W::ALetter),
+    (0x16AC0, 0x16AC9, W::Numeric), (0x16AD0, 0x16AED, W::ALetter),
+    (0x16AF0, 0x16AF4, W::Extend), (0x16B00, 0x16B2F, W::ALetter),
+    (0x16B30, 0x16B36, W::Extend), (0x16B40, 0x16B43, W::ALetter),
+    (0x16B50, 0x16B59, W::Numeric), (0x16B63, 0x16B77, W::ALetter),
+    (0x16B7D, 0x16B8F, W::ALetter), (0x16E40, 0x16E7F, W::ALetter),
+    (0x16F00, 0x16F4A, W::ALetter), (0x16F4F, 0x16F4F, W::Extend),
+    (0x16F50, 0x16F50, W::ALetter), (0x16F51, 0x16F87, W::Extend),
+    (0x16F8F, 0x16F92, W::Extend), (0x16F93, 0x16F9F, W::ALetter),
+    (0x16FE0, 0x16FE1, W::ALetter), (0x16FE3, 0x16FE3, W::ALetter),
+    (0x16FE4, 0x16FE4, W::Extend), (0x16FF0, 0x16FF1, W::Extend),
+    (0x1AFF0, 0x1AFF3, W::Katakana), (0x1AFF5, 0x1AFFB, W::Katakana),
+    (0x1AFFD, 0x1AFFE, W::Katakana), (0x1B000, 0x1B000, W::Katakana),
+    (0x1B120, 0x1B122, W::Katakana), (0x1B164, 0x1B167, W::Katakana),
+    (0x1BC00, 0x1BC6A, W::ALetter), (0x1BC70, 0x1BC7C, W::ALetter),
+    (0x1BC80, 0x1BC88, W::ALetter), (0x1BC90, 0x1BC99, W::ALetter),
+    (0x1BC9D, 0x1BC9E, W::Extend), (0x1BCA0, 0x1BCA3, W::Format), (0x1CF00, 0x1CF2D, W::Extend),
+    (0x1CF30, 0x1CF46, W::Extend), (0x1D165, 0x1D169, W::Extend), (0x1D16D, 0x1D172, W::Extend),
+    (0x1D173, 0x1D17A, W::Format), (0x1D17B, 0x1D182, W::Extend), (0x1D185, 0x1D18B, W::Extend),
+    (0x1D1AA, 0x1D1AD, W::Extend), (0x1D242, 0x1D244, W::Extend),
+    (0x1D400, 0x1D454, W::ALetter), (0x1D456, 0x1D49C, W::ALetter),
+    (0x1D49E, 0x1D49F, W::ALetter), (0x1D4A2, 0x1D4A2, W::ALetter),
+    (0x1D4A5, 0x1D4A6, W::ALetter), (0x1D4A9, 0x1D4AC, W::ALetter),
+    (0x1D4AE, 0x1D4B9, W::ALetter), (0x1D4BB, 0x1D4BB, W::ALetter),
+    (0x1D4BD, 0x1D4C3, W::ALetter), (0x1D4C5, 0x1D505, W::ALetter),
+    (0x1D507, 0x1D50A, W::ALetter), (0x1D50D, 0x1D514, W::ALetter),
+    (0x1D516, 0x1D51C, W::ALetter), (0x1D51E, 0x1D539, W::ALetter),
+    (0x1D53B, 0x1D53E, W::ALetter), (0x1D540, 0x1D544, W::ALetter),
+    (0x1D546, 0x1D546, W::ALetter), (0x1D54A, 0x1D550, W::ALetter),
+    (0x1D552, 0x1D6A5, W::ALetter), (0x1D6A8, 0x1D6C0, W::ALetter),
+    (0x1D6C2, 0x1D6DA, W::ALetter), (0x1D6DC, 0x1D6FA, W::ALetter),
+    (0x1D6FC, 0x1D714, W::ALetter), (0x1D716, 0x1D734, W::ALetter),
+    (0x1D736, 0x1D74E, W::ALetter), (0x1D750, 0x1D76E, W::ALetter),
+    (0x1D770, 0x1D788, W::ALetter), (0x1D78A, 0x1D7A8, W::ALetter),
+    (0x1D7AA, 0x1D7C2, W::ALetter), (0x1D7C4, 0x1D7CB, W::ALetter),
+    (0x1D7CE, 0x1D7FF, W::Numeric), (0x1DA00, 0x1DA36, W::Extend),
+    (0x1DA3B, 0x1DA6C, W::Extend), (0x1DA75, 0x1DA75, W::Extend), (0x1DA84, 0x1DA84, W::Extend),
+    (0x1DA9B, 0x1DA9F, W::Extend), (0x1DAA1, 0x1DAAF, W::Extend),
+    (0x1DF00, 0x1DF1E, W::ALetter), (0x1E000, 0x1E006, W::Extend),
+    (0x1E008, 0x1E018, W::Extend), (0x1E01B, 0x1E021, W::Extend), (0x1E023, 0x1E024, W::Extend),
+    (0x1E026, 0x1E02A, W::Extend), (0x1E100, 0x1E12C, W::ALetter),
+    (0x1E130, 0x1E136, W::Extend), (0x1E137, 0x1E13D, W::ALetter),
+    (0x1E140, 0x1E149, W::Numeric), (0x1E14E, 0x1E14E, W::ALetter),
+    (0x1E290, 0x1E2AD, W::ALetter), (0x1E2AE, 0x1E2AE, W::Extend),
+    (0x1E2C0, 0x1E2EB, W::ALetter), (0x1E2EC, 0x1E2EF, W::Extend),
+    (0x1E2F0, 0x1E2F9, W::Numeric), (0x1E7E0, 0x1E7E6, W::ALetter),
+    (0x1E7E8, 0x1E7EB, W::ALetter), (0x1E7ED, 0x1E7EE, W::ALetter),
+    (0x1E7F0, 0x1E7FE, W::ALetter), (0x1E800, 0x1E8C4, W::ALetter),
+    (0x1E8D0, 0x1E8D6, W::Extend), (0x1E900, 0x1E943, W::ALetter),
+    (0x1E944, 0x1E94A, W::Extend), (0x1E94B, 0x1E94B, W::ALetter),
+    (0x1E950, 0x1E959, W::Numeric), (0x1EE00, 0x1EE03, W::ALetter),
+    (0x1EE05, 0x1EE1F, W::ALetter), (0x1EE21, 0x1EE22, W::ALetter),
+    (0x1EE24, 0x1EE24, W::ALetter), (0x1EE27, 0x1EE27, W::ALetter),
+    (0x1EE29, 0x1EE32, W::ALetter), (0x1EE34, 0x1EE37, W::ALetter),
+    (0x1EE39, 0x1EE39, W::ALetter), (0x1EE3B, 0x1EE3B, W::ALetter),
+    (0x1EE42, 0x1EE42, W::ALetter), (0x1EE47, 0x1EE47, W::ALetter),
+    (0x1EE49, 0x1EE49, W::ALetter), (0x1EE4B, 0x1EE4B, W::ALetter),
+    (0x1EE4D, 0x1EE4F, W::ALetter), (0x1EE51, 0x1EE52, W::ALetter),
+    (0x1EE54, 0x1EE54, W::ALetter), (0x1EE57, 0x1EE57, W::ALetter),
+    (0x1EE59, 0x1EE59, W::ALetter), (0x1EE5B, 0x1EE5B, W::ALetter),
+    (0x1EE5D, 0x1EE5D, W::ALetter), (0x1EE5F, 0x1EE5F, W::ALetter),
+    (0x1EE61, 0x1EE62, W::ALetter), (0x1EE64, 0x1EE64, W::ALetter),
+    (0x1EE67, 0x1EE6A, W::ALetter), (0x1EE6C, 0x1EE72, W::ALetter),
+    (0x1EE74, 0x1EE77, W::ALetter), (0x1EE79, 0x1EE7C, W::ALetter),
+    (0x1EE7E, 0x1EE7E, W::ALetter), (0x1EE80, 0x1EE89, W::ALetter),
+    (0x1EE8B, 0x1EE9B, W::ALetter), (0x1EEA1, 0x1EEA3, W::ALetter),
+    (0x1EEA5, 0x1EEA9, W::ALetter), (0x1EEAB, 0x1EEBB, W::ALetter),
+    (0x1F130, 0x1F149, W::ALetter), (0x1F150, 0x1F169, W::ALetter),
+    (0x1F170, 0x1F189, W::ALetter), (0x1F1E6, 0x1F1FF, W::RegionalIndicator),
+    (0x1F3FB, 0x1F3FF, W::Extend), (0x1FBF0, 0x1FBF9, W::Numeric),
+    (0xE0001, 0xE0001, W::Format), (0xE0020, 0xE007F, W::Extend), (0xE0100, 0xE01EF, W::Extend),
+];
+
+// Extended_Pictographic for all of Unicode, sorted
+#[rustfmt::skip]
+pub const EXTENDED_PICTOGRAPHIC: &[(u32, u32)] = &[
+    (0x00A9, 0x00A9), (0x00AE, 0x00AE), (0x203C, 0x203C), (0x2049, 0x2049), (0x2122, 0x2122),
+    (0x2139, 0x2139), (0x2194, 0x2199), (0x21A9, 0x21AA), (0x231A, 0x231B), (0x2328, 0x2328),
+    (0x2388, 0x2388), (0x23CF, 0x23CF), (0x23E9, 0x23F3), (0x23F8, 0x23FA), (0x24C2, 0x24C2),
+    (0x25AA, 0x25AB), (0x25B6, 0x25B6), (0x25C0, 0x25C0), (0x25FB, 0x25FE), (0x2600, 0x2605),
+    (0x2607, 0x2612), (0x2614, 0x2685), (0x2690, 0x2705), (0x2708, 0x2712), (0x2714, 0x2714),
+    (0x2716, 0x2716), (0x271D, 0x271D), (0x2721, 0x2721), (0x2728, 0x2728), (0x2733, 0x2734),
+    (0x2744, 0x2744), (0x2747, 0x2747), (0x274C, 0x274C), (0x274E, 0x274E), (0x2753, 0x2755),
+    (0x2757, 0x2757), (0x2763, 0x2767), (0x2795, 0x2797), (0x27A1, 0x27A1), (0x27B0, 0x27B0),
+    (0x27BF, 0x27BF), (0x2934, 0x2935), (0x2B05, 0x2B07), (0x2B1B, 0x2B1C), (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55), (0x3030, 0x3030), (0x303D, 0x303D), (0x3297, 0x3297), (0x3299, 0x3299),
+    (0x1F000, 0x1F0FF), (0x1F10D, 0x1F10F), (0x1F12F, 0x1F12F), (0x1F16C, 0x1F171),
+    (0x1F17E, 0x1F17F), (0x1F18E, 0x1F18E), (0x1F191, 0x1F19A), (0x1F1AD, 0x1F1E5),
+    (0x1F201, 0x1F20F), (0x1F21A, 0x1F21A), (0x1F22F, 0x1F22F), (0x1F232, 0x1F23A),
+    (0x1F23C, 0x1F23F), (0x1F249, 0x1F3FA), (0x1F400, 0x1F53D), (0x1F546, 0x1F64F),
+    (0x1F680, 0x1F6FF), (0x1F774, 0x1F77F), (0x1F7D5, 0x1F7FF), (0x1F80C, 0x1F80F),
+    (0x1F848, 0x1F84F), (0x1F85A, 0x1F85F), (0x1F888, 0x1F88F), (0x1F8AE, 0x1F8FF),
+    (0x1F90C, 0x1F93A), (0x1F93C, 0x1F945), (0x1F947, 0x1FAFF), (0x1FC00, 0x1FFFD),
+];
+
+// Grapheme_Cluster_Break property values, for extended grapheme clusters (UAX #29)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphemeBreak {
+    Other,
+    CR,
+    LF,
+    Control,
+    Extend,
+    Zwj,
+    RegionalIndicator,
+    Prepend,
+    SpacingMark,
+    L,
+    V,
+    T,
+    Lv,
+    Lvt,
+}
+
+use self::GraphemeBreak as G;
+
+// the Grapheme_Cluster_Break property for all of Unicode, sorted, characters not listed are
+// `Other`. Hangul syllables (`Lv` and `Lvt`) are left out, they follow from the code point.
+#[rustfmt::skip]
+pub const GRAPHEME_BREAK: &[(u32, u32, GraphemeBreak)] = &[
+    (0x0000, 0x0009, G::Control), (0x000A, 0x000A, G::LF), (0x000B, 0x000C, G::Control),
+    (0x000D, 0x000D, G::CR), (0x000E, 0x001F, G::Control), (0x007F, 0x009F, G::Control),
+    (0x00AD, 0x00AD, G::Control), (0x0300, 0x036F, G::Extend), (0x0483, 0x0489, G::Extend),
+    (0x0591, 0x05BD, G::Extend), (0x05BF, 0x05BF, G::Extend), (0x05C1, 0x05C2, G::Extend),
+    (0x05C4, 0x05C5, G::Extend), (0x05C7, 0x05C7, G::Extend), (0x0600, 0x0605, G::Prepend),
+    (0x0610, 0x061A, G::Extend), (0x061C, 0x061C, G::Control), (0x064B, 0x065F, G::Extend),
+    (0x0670, 0x0670, G::Extend), (0x06D6, 0x06DC, G::Extend), (0x06DD, 0x06DD, G::Prepend),
+    (0x06DF, 0x06E4, G::Extend), (0x06E7, 0x06E8, G::Extend), (0x06EA, 0x06ED, G::Extend),
+    (0x070F, 0x070F, G::Prepend), (0x0711, 0x0711, G::Extend), (0x0730, 0x074A, G::Extend),
+    (0x07A6, 0x07B0, G::Extend), (0x07EB, 0x07F3, G::Extend), (0x07FD, 0x07FD, G::Extend),
+    (0x0816, 0x0819, G::Extend), (0x081B, 0x0823, G::Extend), (0x0825, 0x0827, G::Extend),
+    (0x0829, 0x082D, G::Extend), (0x0859, 0x085B, G::Extend), (0x0890, 0x0891, G::Prepend),
+    (0x0898, 0x089F, G::Extend), (0x08CA, 0x08E1, G::Extend), (0x08E2, 0x08E2, G::Prepend),
+    (0x08E3, 0x0902, G::Extend), (0x0903, 0x0903, G::SpacingMark), (0x093A, 0x093A, G::Extend),
+    (0x093B, 0x093B, G::SpacingMark), (0x093C, 0x093C, G::Extend),
+    (0x093E, 0x0940, G::SpacingMark), (0x0941, 0x0948, G::Extend),
+    (0x0949, 0x094C, G::SpacingMark), (0x094D, 0x094D, G::Extend),
+    (0x094E, 0x094F, G::SpacingMark), (0x0951, 0x0957, G::Extend), (0x0962, 0x0963, G::Extend),
+    (0x0981, 0x0981, G::Extend), (0x0982, 0x0983, G::SpacingMark), (0x09BC, 0x09BC, G::Extend),
+    (0x09BE, 0x09BE, G::Extend), (0x09BF, 0x09C0, G::SpacingMark), (0x09C1, 0x09C4, G::Extend),
+    (0x09C7, 0x09C8, G::SpacingMark), (0x09CB, 0x09CC, G::SpacingMark),
+    (0x09CD, 0x09CD, G::Extend), (0x09D7, 0x09D7, G::Extend), (0x09E2, 0x09E3, G::Extend),
+    (0x09FE, 0x09FE, G::Extend), (0x0A01, 0x0A02, G::Extend), (0x0A03, 0x0A03, G::SpacingMark),
+    (0x0A3C, 0x0A3C, G::Extend), (0x0A3E, 0x0A40, G::SpacingMark), (0x0A41, 0x0A42, G::Extend),
+    (0x0A47, 0x0A48, G::Extend), (0x0A4B, 0x0A4D, G::Extend), (0x0A51, 0x0A51, G::Extend),
+    (0x0A70, 0x0A71, G::Extend), (0x0A75, 0x0A75, G::Extend), (0x0A81, 0x0A82, G::Extend),
+    (0x0A83, 0x0A83, G::SpacingMark), (0x0ABC, 0x0ABC, G::Extend),
+    (0x0ABE, 0x0AC0, G::SpacingMark), (0x0AC1, 0x0AC5, G::Extend), (0x0AC7, 0x0AC8, G::Extend),
+    (0x0AC9, 0x0AC9, G::SpacingMark), (0x0ACB, 0x0ACC, G::SpacingMark),
+    (0x0ACD, 0x0ACD, G::Extend), (0x0AE2, 0x0AE3, G::Extend), (0x0AFA, 0x0AFF, G::Extend),
+    (0x0B01, 0x0B01, G::Extend), (0x0B02, 0x0B03, G::SpacingMark), (0x0B3C, 0x0B3C, G::Extend),
+    (0x0B3E, 0x0B3F, G::Extend), (0x0B40, 0x0B40, G::SpacingMark), (0x0B41, 0x0B44, G::Extend),
+    (0x0B47, 0x0B48, G::SpacingMark), (0x0B4B, 0x0B4C, G::SpacingMark),
+    (0x0B4D, 0x0B4D, G::Extend), (0x0B55, 0x0B57, G::Extend), (0x0B62, 0x0B63, G::Extend),
+    (0x0B82, 0x0B82, G::Extend), (0x0BBE, 0x0BBE, G::Extend), (0x0BBF, 0x0BBF, G::SpacingMark),
+    (0x0BC0, 0x0BC0, G::Extend), (0x0BC1, 0x0BC2, G::SpacingMark),
+    (0x0BC6, 0x0BC8, G::SpacingMark), (0x0BCA, 0x0BCC, G::SpacingMark),
+    (0x0BCD, 0x0BCD, G::Extend), (0x0BD7, 0x0BD7, G::Extend), (0x0C00, 0x0C00, G::Extend),
+    (0x0C01, 0x0C03, G::SpacingMark), (0x0C04, 0x0C04, G::Extend), (0x0C3C, 0x0C3C, G::Extend),
+    (0x0C3E, 0x0C40, G::Extend), (0x0C41, 0x0C44, G::SpacingMark), (0x0C46, 0x0C48, G::Extend),
+    (0x0C4A, 0x0C4D, G::Extend), (0x0C55, 0x0C56, G::Extend), (0x0C62, 0x0C63, G::Extend),
+    (0x0C81, 0x0C81, G::Extend), (0x0C82, 0x0C83, G::SpacingMark), (0x0CBC, 0x0CBC, G::Extend),
+    (0x0CBE, 0x0CBE, G::SpacingMark), (0x0CBF, 0x0CBF, G::Extend),
+    (0x0CC0, 0x0CC1, G::SpacingMark), (0x0CC2, 0x0CC2, G::Extend),
+    (0x0CC3, 0x0CC4, G::SpacingMark), (0x0CC6, 0x0CC6, G::Extend),
+    (0x0CC7, 0x0CC8, G::SpacingMark), (0x0CCA, 0x0CCB, G::SpacingMark),
+    (0x0CCC, 0x0CCD, G::Extend), (0x0CD5, 0x0CD6, G::Extend), (0x0CE2, 0x0CE3, G::Extend),
+    (0x0D00, 0x0D01, G::Extend), (0x0D02, 0x0D03, G::SpacingMark), (0x0D3B, 0x0D3C, G::Extend),
+    (0x0D3E, 0x0D3E, G::Extend), (0x0D3F, 0x0D40, G::SpacingMark), (0x0D41, 0x0D44, G::Extend),
+    (0x0D46, 0x0D48, G::SpacingMark), (0x0D4A, 0x0D4C, G::SpacingMark),
+    (0x0D4D, 0x0D4D, G::Extend), (0x0D4E, 0x0D4E, G::Prepend), (0x0D57, 0x0D57, G::Extend),
+    (0x0D62, 0x0D63, G::Extend), (0x0D81, 0x0D81, G::Extend), (0x0D82, 0x0D83, G::SpacingMark),
+    (0x0DCA, 0x0DCA, G::Extend), (0x0DCF, 0x0DCF, G::Extend), (0x0DD0, 0x0DD1, G::SpacingMark),
+    (0x0DD2, 0x0DD4, G::Extend), (0x0DD6, 0x0DD6, G::Extend), (0x0DD8, 0x0DDE, G::SpacingMark),
+    (0x0DDF, 0x0DDF, G::Extend), (0x0DF2, 0x0DF3, G::SpacingMark), (0x0E31, 0x0E31, G::Extend),
+    (0x0E33, 0x0E33, G::SpacingMark), (0x0E34, 0x0E3A, G::Extend), (0x0E47, 0x0E4E, G::Extend),
+    (0x0EB1, 0x0EB1, G::Extend), (0x0EB3, 0x0EB3, G::SpacingMark), (0x0EB4, 0x0EBC, G::Extend),
+    (0x0EC8, 0x0ECD, G::Extend), (0x0F18, 0x0F19, G::Extend), (0x0F35, 0x0F35, G::Extend),
+    (0x0F37, 0x0F37, G::Extend), (0x0F39, 0x0F39, G::Extend), (0x0F3E, 0x0F3F, G::SpacingMark),
+    (0x0F71, 0x0F7E, G::Extend), (0x0F7F, 0x0F7F, G::SpacingMark), (0x0F80, 0x0F84, G::Extend),
+    (0x0F86, 0x0F87, G::Extend), (0x0F8D, 0x0F97, G::Extend), (0x0F99, 0x0FBC, G::Extend),
+    (0x0FC6, 0x0FC6, G::Extend), (0x102D, 0x1030, G::Extend), (0x1031, 0x1031, G::SpacingMark),
+    (0x1032, 0x1037, G::Extend), (0x1039, 0x103A, G::Extend), (0x103B, 0x103C, G::SpacingMark),
+    (0x103D, 0x103E, G::Extend), (0x1056, 0x1057, G::SpacingMark), (0x1058, 0x1059, G::Extend),
+    (0x105E, 0x1060, G::Extend), (0x1071, 0x1074, G::Extend), (0x1082, 0x1082, G::Extend),
+    (0x1084, 0x1084, G::SpacingMark), (0x1085, 0x1086, G::Extend), (0x108D, 0x108D, G::Extend),
+    (0x109D, 0x109D, G::Extend), (0x1100, 0x115F, G::L), (0x1160, 0x11A7, G::V),
+    (0x11A8, 0x11FF, G::T), (0x135D, 0x135F, G::Extend), (0x1712, 0x1714, G::Extend),
+    (0x1715, 0x1715, G::SpacingMark), (0x1732, 0x1733, G::Extend),
+    (0x1734, 0x1734, G::SpacingMark), (0x1752, 0x1753, G::Extend), (0x1772, 0x1773, G::Extend),
+    (0x17B4, 0x17B5, G::Extend), (0x17B6, 0x17B6, G::SpacingMark), (0x17B7, 0x17BD, G::Extend),
+    (0x17BE, 0x17C5, G::SpacingMark), (0x17C6, 0x17C6, G::Extend),
+    (0x17C7, 0x17C8, G::SpacingMark), (0x17C9, 0x17D3, G::Extend), (0x17DD, 0x17DD, G::Extend),
+    (0x180B, 0x180D, G::Extend), (0x180E, 0x180E, G::Control), (0x180F, 0x180F, G::Extend),
+    (0x1885, 0x1886, G::Extend), (0x18A9, 0x18A9, G::Extend), (0x1920, 0x1922, G::Extend),
+    (0x1923, 0x1926, G::SpacingMark), (0x1927, 0x1928, G::Extend),
+    (0x1929, 0x192B, G::SpacingMark), (0x1930, 0x1931, G::SpacingMark),
+    (0x1932, 0x1932, G::Extend), (0x1933, 0x1938, G::SpacingMark), (0x1939, 0x193B, G::Extend),
+    (0x1A17, 0x1A18, G::Extend), (0x1A19, 0x1A1A, G::SpacingMark), (0x1A1B, 0x1A1B, G::Extend),
+    (0x1A55, 0x1A55, G::SpacingMark), (0x1A56, 0x1A56, G::Extend),
+    (0x1A57, 0x1A57, G::SpacingMark), (0x1A58, 0x1A5E, G::Extend), (0x1A60, 0x1A60, G::Extend),
+    (0x1A62, 0x1A62, G::Extend), (0x1A65, 0x1A6C, G::Extend), (0x1A6D, 0x1A72, G::SpacingMark),
+    (0x1A73, 0x1A7C, G::Extend), (0x1A7F, 0x1A7F, G::Extend), (0x1AB0, 0x1ACE, G::Extend),
+    (0x1B00, 0x1B03, G::Extend), (0x1B04, 0x1B04, G::SpacingMark), (0x1B34, 0x1B3A, G::Extend),
+    (0x1B3B, 0x1B3B, G::SpacingMark), (0x1B3C, 0x1B3C, G::Extend),
+    (0x1B3D, 0x1B41, G::SpacingMark), (0x1B42, 0x1B42, G::Extend),
+    (0x1B43, 0x1B44, G::SpacingMark), (0x1B6B, 0x1B73, G::Extend), (0x1B80, 0x1B81, G::Extend),
+    (0x1B82, 0x1B82, G::SpacingMark), (0x1BA1, 0x1BA1, G::SpacingMark),
+    (0x1BA2, 0x1BA5, G::Extend), (0x1BA6, 0x1BA7, G::SpacingMark), (0x1BA8, 0x1BA9, G::Extend),
+    (0x1BAA, 0x1BAA, G::SpacingMark), (0x1BAB, 0x1BAD, G::Extend), (0x1BE6, 0x1BE6, G::Extend),
+    (0x1BE7, 0x1BE7, G::SpacingMark), (0x1BE8, 0x1BE9, G::Extend),
+    (0x1BEA, 0x1BEC, G::SpacingMark), (0x1BED, 0x1BED, G::Extend),
+    (0x1BEE, 0x1BEE, G::SpacingMark), (0x1BEF, 0x1BF1, G::Extend),
+    (0x1BF2, 0x1BF3, G::SpacingMark), (0x1C24, 0x1C2B, G::SpacingMark),
+    (0x1C2C, 0x1C33, G::Extend), (0x1C34, 0x1C35, G::SpacingMark), (0x1C36, 0x1C37, G::Extend),
+    (0x1CD0, 0x1CD2, G::Extend), (0x1CD4, 0x1CE0, G::Extend), (0x1CE1, 0x1CE1, G::SpacingMark),
+    (0x1CE2, 0x1CE8, G::Extend), (0x1CED, 0x1CED, G::Extend), (0x1CF4, 0x1CF4, G::Extend),
+    (0x1CF7, 0x1CF7, G::SpacingMark), (0x1CF8, 0x1CF9, G::Extend), (0x1DC0, 0x1DFF, G::Extend),
+    (0x200B, 0x200B, G::Control), (0x200C, 0x200C, G::Extend), (0x200D, 0x200D, G::Zwj),
+    (0x200E, 0x200F, G::Control), (0x2028, 0x202E, G::Control), (0x2060, 0x206F, G::Control),
+    (0x20D0, 0x20F0, G::Extend), (0x2CEF, 0x2CF1, G::Extend), (0x2D7F, 0x2D7F, G::Extend),
+    (0x2DE0, 0x2DFF, G::Extend), (0x302A, 0x302F, G::Extend), (0x3099, 0x309A, G::Extend),
+    (0xA66F, 0xA672, G::Extend), (0xA674, 0xA67D, G::Extend), (0xA69E, 0xA69F, G::Extend),
+    (0xA6F0, 0xA6F1, G::Extend), (0xA802, 0xA802, G::Extend), (0xA806, 0xA806, G::Extend),
+    (0xA80B, 0xA80B, G::Extend), (0xA823, 0xA824, G::SpacingMark), (0xA825, 0xA826, G::Extend),
+    (0xA827, 0xA827, G::SpacingMark), (0xA82C, 0xA82C, G::Extend),
+    (0xA880, 0xA881, G::SpacingMark), (0xA8B4, 0xA8C3, G::SpacingMark),
+    (0xA8C4, 0xA8C5, G::Extend), (0xA8E0, 0xA8F1, G::Extend), (0xA8FF, 0xA8FF, G::Extend),
+    (0xA926, 0xA92D, G::Extend), (0xA947, 0xA951, G::Extend), (0xA952, 0xA953, G::SpacingMark),
+    (0xA960, 0xA97C, G::L), (0xA980, 0xA982, G::Extend), (0xA983, 0xA983, G::SpacingMark),
+    (0xA9B3, 0xA9B3, G::Extend), (0xA9B4, 0xA9B5, G::SpacingMark), (0xA9B6, 0xA9B9, G::Extend),
+    (0xA9BA, 0xA9BB, G::SpacingMark), (0xA9BC, 0xA9BD, G::Extend),
+    (0xA9BE, 0xA9C0, G::SpacingMark), (0xA9E5, 0xA9E5, G::Extend), (0xAA29, 0xAA2E, G::Extend),
+    (0xAA2F, 0xAA30, G::SpacingMark), (0xAA31, 0xAA32, G::Extend),
+    (0xAA33, 0xAA34, G::SpacingMark), (0xAA35, 0xAA36, G::Extend), (0xAA43, 0xAA43, G::Extend),
+    (0xAA4C, 0xAA4C, G::Extend), (0xAA4D, 0xAA4D, G::SpacingMark), (0xAA7C, 0xAA7C, G::Extend),
+    (0xAAB0, 0xAAB0, G::Extend), (0xAAB2, 0xAAB4, G::Extend), (0xAAB7, 0xAAB8, G::Extend),
+    (0xAABE, 0xAABF, G::Extend), (0xAAC1, 0xAAC1, G::Extend), (0xAAEB, 0xAAEB, G::SpacingMark),
+    (0xAAEC, 0xAAED, G::Extend), (0xAAEE, 0xAAEF, G::SpacingMark),
+    (0xAAF5, 0xAAF5, G::SpacingMark), (0xAAF6, 0xAAF6, G::Extend),
+    (0xABE3, 0xABE4, G::SpacingMark), (0xABE5, 0xABE5, G::Extend),
+    (0xABE6, 0xABE7, G::SpacingMark), (0xABE8, 0xABE8, G::Extend),
+    (0xABE9, 0xABEA, G::SpacingMark), (0xABEC, 0xABEC, G::SpacingMark),
+    (0xABED, 0xABED, G::Extend), (0xD7B0, 0xD7C6, G::V), (0xD7CB, 0xD7FB, G::T),
+    (0xFB1E, 0xFB1E, G::Extend), (0xFE00, 0xFE0F, G::Extend), (0xFE20, 0xFE2F, G::Extend),
+    (0xFEFF, 0xFEFF, G::Control), (0xFF9E, 0xFF9F, G::Extend), (0xFFF0, 0xFFFB, G::Control),
+    (0x101FD, 0x101FD, G::Extend), (0x102E0, 0x102E0, G::Extend), (0x10376, 0x1037A, G::Extend),
+    (0x10A01, 0x10A03, G::Extend), (0x10A05, 0x10A06, G::Extend), (0x10A0C, 0x10A0F, G::Extend),
+    (0x10A38, 0x10A3A, G::Extend), (0x10A3F, 0x10A3F, G::Extend), (0x10AE5, 0x10AE6, G::Extend),
+    (0x10D24, 0x10D27, G::Extend), (0x10EAB, 0x10EAC, G::Extend), (0x10F46, 0x10F50, G::Extend),
+    (0x10F82, 0x10F85, G::Extend), (0x11000, 0x11000, G::SpacingMark),
+    (0x11001, 0x11001, G::Extend), (0x11002, 0x11002, G::SpacingMark),
+    (0x11038, 0x11046, G::Extend), (0x11070, 0x11070, G::Extend), (0x11073, 0x11074, G::Extend),
+    (0x1107F, 0x11081, G::Extend), (0x11082, 0x11082, G::SpacingMark),
+    (0x110B0, 0x110B2, G::SpacingMark), (0x110B3, 0x110B6, G::Extend),
+    (0x110B7, 0x110B8, G::SpacingMark), (0x110B9, 0x110BA, G::Extend),
+    (0x110BD, 0x110BD, G::Prepend), (0x110C2, 0x110C2, G::Extend),
+    (0x110CD, 0x110CD, G::Prepend), (0x11100, 0x11102, G::Extend),
+    (0x11127, 0x1112B, G::Extend), (0x1112C, 0x1112C, G::SpacingMark),
+    (0x1112D, 0x11134, G::Extend), (0x11145, 0x11146, G::SpacingMark),
+    (0x11173, 0x11173, G::Extend), (0x11180, 0x11181, G::Extend),
+    (0x11182, 0x11182, G::SpacingMark), (0x111B3, 0x111B5, G::SpacingMark),
+    (0x111B6, 0x111BE, G::Extend), (0x111BF, 0x111C0, G::SpacingMark),
+    (0x111C2, 0x111C3, G::Prepend), (0x111C9, 0x111CC, G::Extend),
+    (0x111CE, 0x111CE, G::SpacingMark), (0x111CF, 0x111CF, G::Extend),
+    (0x1122C, 0x1122E, G::SpacingMark), (0x1122F, 0x11231, G::Extend),
+    (0x11232, 0x11233, G::SpacingMark), (0x11234, 0x11234, G::Extend),
+    (0x11235, 0x11235, G::SpacingMark), (0x11236, 0x11237, G::Extend),
+    (0x1123E, 0x1123E, G::Extend), (0x112DF, 0x112DF, G::Extend),
+    (0x112E0, 0x112E2, G::SpacingMark), (0x112E3, 0x112EA, G::Extend),
+    (0x11300, 0x11301, G::Extend), (0x11302, 0x11303, G::SpacingMark),
+    (0x1133B, 0x1133C, G::Extend), (0x1133E, 0x1133E, G::Extend),
+    (0x1133F, 0x1133F, G::SpacingMark), (0x11340, 0x11340, G::Extend),
+    (0x11341, 0x11344, G::SpacingMark), (0x11347, 0x11348, G::SpacingMark),
+    (0x1134B, 0x1134D, G::SpacingMark), (0x11357, 0x11357, G::Extend),
+    (0x11362, 0x11363, G::SpacingMark), (0x11366, 0x1136C, G::Extend),
+    (0x11370, 0x11374, G::Extend), (0x11435, 0x11437, G::SpacingMark),
+    (0x11438, 0x1143F, G::Extend), (0x11440, 0x11441, G::SpacingMark),
+    (0x11442, 0x11444, G::Extend), (0x11445, 0x11445, G::SpacingMark),
+    (0x11446, 0x11446, G::Extend), (0x1145E, 0x1145E, G::Extend), (0x114B0, 0x114B0, G::Extend),
+    (0x114B1, 0x114B2, G::SpacingMark), (0x114B3, 0x114B8, G::Extend),
+    (0x114B9, 0x114B9, G::SpacingMark), (0x114BA, 0x114BA, G::Extend),
+    (0x114BB, 0x114BC, G::SpacingMark), (0x114BD, 0x114BD, G::Extend),
+    (0x114BE, 0x114BE, G::SpacingMark), (0x114BF, 0x114C0, G::Extend),
+    (0x114C1, 0x114C1, G::SpacingMark), (0x114C2, 0x114C3, G::Extend),
+    (0x115AF, 0x115AF, G::Extend), (0x115B0, 0x115B1, G::SpacingMark),
+    (0x115B2, 0x115B5, G::Extend), (0x115B8, 0x115BB, G::SpacingMark),
+    (0x115BC, 0x115BD, G::Extend), (0x115BE, 0x115BE, G::SpacingMark),
+    (0x115BF, 0x115C0, G::Extend), (0x115DC, 0x115DD, G::Extend),
+    (0x11630, 0x11632, G::SpacingMark), (0x11633, 0x1163A, G::Extend),
+    (0x1163B, 0x1163C, G::SpacingMark), (0x1163D, 0x1163D, G::Extend),
+    (0x1163E, 0x1163E, G::SpacingMark), (0x1163F, 0x11640, G::Extend),
+    (0x116AB, 0x116AB, G::Extend), (0x116AC, 0x116AC, G::SpacingMark),
+    (0x116AD, 0x116AD, G::Extend), (0x116AE, 0x116AF, G::SpacingMark),
+    (0x116B0, 0x116B5, G::Extend), (0x116B6, 0x116B6, G::SpacingMark),
+    (0x116B7, 0x116B7, G::Extend), (0x1171D, 0x1171F, G::Extend), (0x11722, 0x11725, G::Extend),
+    (0x11726, 0x11726, G::SpacingMark), (0x11727, 0x1172B, G::Extend),
+    (0x1182C, 0x1182E, G::SpacingMark), (0x1182F, 0x11837, G::Extend),
+    (0x11838, 0x11838, G::SpacingMark), (0x11839, 0x1183A, G::Extend),
+    (0x11930, 0x11930, G::Extend), (0x11931, 0x11935, G::SpacingMark),
+    (0x11937, 0x11938, G::SpacingMark), (0x1193B, 0x1193C, G::Extend),
+    (0x1193D, 0x1193D, G::SpacingMark), (0x1193E, 0x1193E, G::Extend),
+    (0x1193F, 0x1193F, G::Prepend), (0x11940, 0x11940, G::SpacingMark),
+    (0x11941, 0x11941, G::Prepend), (0x11942, 0x11942, G::SpacingMark),
+    (0x11943, 0x11943, G::Extend), (0x119D1, 0x119D3, G::SpacingMark),
+    (0x119D4, 0x119D7, G::Extend), (0x119DA, 0x119DB, G::Extend),
+    (0x119DC, 0x119DF, G::SpacingMark), (0x119E0, 0x119E0, G::Extend),
+    (0x119E4, 0x119E4, G::SpacingMark), (0x11A01, 0x11A0A, G::Extend),
+    (0x11A33, 0x11A38, G::Extend), (0x11A39, 0x11A39, G::SpacingMark),
+    (0x11A3A, 0x11A3A, G::Prepend), (0x11A3B, 0x11A3E, G::Extend),
+    (0x11A47, 0x11A47, G::Extend), (0x11A51, 0x11A56, G::Extend),
+    (0x11A57, 0x11A58, G::SpacingMark), (0x11A59, 0x11A5B, G::Extend),
+    (0x11A84, 0x11A89, G::Prepend), (0x11A8A, 0x11A96, G::Extend),
+    (0x11A97, 0x11A97, G::SpacingMark), (0x11A98, 0x11A99, G::Extend),
+    (0x11C2F, 0x11C2F, G::SpacingMark), (0x11C30, 0x11C36, G::Extend),
+    (0x11C38, 0x11C3D, G::Extend), (0x11C3E, 0x11C3E, G::SpacingMark),
+    (0x11C3F, 0x11C3F, G::Extend), (0x11C92, 0x11CA7, G::Extend),
+    (0x11CA9, 0x11CA9, G::SpacingMark), (0x11CAA, 0x11CB0, G::Extend),
+    (0x11CB1, 0x11CB1, G::SpacingMark), (0x11CB2, 0x11CB3, G::Extend),
+    (0x11CB4, 0x11CB4, G::SpacingMark), (0x11CB5, 0x11CB6, G::Extend),
+    (0x11D31, 0x11D36, G::Extend), (0x11D3A, 0x11D3A, G::Extend), (0x11D3C, 0x11D3D, G::Extend),
+    (0x11D3F, 0x11D45, G::Extend), (0x11D46, 0x11D46, G::Prepend),
+    (0x11D47, 0x11D47, G::Extend), (0x11D8A, 0x11D8E, G::SpacingMark),
+    (0x11D90, 0x11D91, G::Extend), (0x11D93, 0x11D94, G::SpacingMark),
+    (0x11D95, 0x11D95, G::Extend), (0x11D96, 0x11D96, G::SpacingMark),
+    (0x11D97, 0x11D97, G::Extend), (0x11EF3, 0x11EF4, G::Extend),
+    (0x11EF5, 0x11EF6, G::SpacingMark), (0x13430, 0x13438, G::Control),
+    (0x16AF0, 0x16AF4, G::Extend), (0x16B30, 0x16B36, G::Extend), (0x16F4F, 0x16F4F, G::Extend),
+    (0x16F51, 0x16F87, G::SpacingMark), (0x16F8F, 0x16F92, G::Extend),
+    (0x16FE4, 0x16FE4, G::Extend), (0x16FF0, 0x16FF1, G::SpacingMark),
+    (0x1BC9D, 0x1BC9E, G::Extend), (0x1BCA0, 0x1BCA3, G::Control),
+    (0x1CF00, 0x1CF2D, G::Extend), (0x1CF30, 0x1CF46, G::Extend), (0x1D165, 0x1D165, G::Extend),
+    (0x1D166, 0x1D166, G::SpacingMark), (0x1D167, 0x1D169, G::Extend),
+    (0x1D16D, 0x1D16D, G::SpacingMark), (0x1D16E, 0x1D172, G::Extend),
+    (0x1D173, 0x1D17A, G::Control), (0x1D17B, 0x1D182, G::Extend),
+    (0x1D185, 0x1D18B, G::Extend), (0x1D1AA, 0x1D1AD, G::Extend), (0x1D242, 0x1D244, G::Extend),
+    (0x1DA00, 0x1DA36, G::Extend), (0x1DA3B, 0x1DA6C, G::Extend), (0x1DA75, 0x1DA75, G::Extend),
+    (0x1DA84, 0x1DA84, G::Extend), (0x1DA9B, 0x1DA9F, G::Extend), (0x1DAA1, 0x1DAAF, G::Extend),
+    (0x1E000, 0x1E006, G::Extend), (0x1E008, 0x1E018, G::Extend), (0x1E01B, 0x1E021, G::Extend),
+    (0x1E023, 0x1E024, G::Extend), (0x1E026, 0x1E02A, G::Extend), (0x1E130, 0x1E136, G::Extend),
+    (0x1E2AE, 0x1E2AE, G::Extend), (0x1E2EC, 0x1E2EF, G::Extend), (0x1E8D0, 0x1E8D6, G::Extend),
+    (0x1E944, 0x1E94A, G::Extend), (0x1F1E6, 0x1F1FF, G::RegionalIndicator),
+    (0x1F3FB, 0x1F3FF, G::Extend), (0xE0000, 0xE001F, G::Control),
+    (0xE0020, 0xE007F, G::Extend), (0xE0080, 0xE00FF, G::Control),
+    (0xE0100, 0xE01EF, G::Extend), (0xE01F0, 0xE0FFF, G::Control),
+];
+
+// East_Asian_Width Wide and Fullwidth for all of Unicode, including the unassigned code points
+// that default to Wide, sorted
+#[rustfmt::skip]
+pub const EAST_ASIAN_WIDE: &[(u32, u32)] = &[
+    (0x1100, 0x115F), (0x231A, 0x231B), (0x2329, 0x232A), (0x23E9, 0x23EC), (0x23F0, 0x23F0),
+    (0x23F3, 0x23F3), (0x25FD, 0x25FE), (0x2614, 0x2615), (0x2648, 0x2653), (0x267F, 0x267F),
+    (0x2693, 0x2693), (0x26A1, 0x26A1), (0x26AA, 0x26AB), (0x26BD, 0x26BE), (0x26C4, 0x26C5),
+    (0x26CE, 0x26CE), (0x26D4, 0x26D4), (0x26EA, 0x26EA), (0x26F2, 0x26F3), (0x26F5, 0x26F5),
+    (0x26FA, 0x26FA), (0x26FD, 0x26FD), (0x2705, 0x2705), (0x270A, 0x270B), (0x2728, 0x2728),
+    (0x274C, 0x274C), (0x274E, 0x274E), (0x2753, 0x2755), (0x2757, 0x2757), (0x2795, 0x2797),
+    (0x27B0, 0x27B0), (0x27BF, 0x27BF), (0x2B1B, 0x2B1C), (0x2B50, 0x2B50), (0x2B55, 0x2B55),
+    (0x2E80, 0x2E99), (0x2E9B, 0x2EF3), (0x2F00, 0x2FD5), (0x2FF0, 0x2FFB), (0x3000, 0x303E),
+    (0x3041, 0x3096), (0x3099, 0x30FF), (0x3105, 0x312F), (0x3131, 0x318E), (0x3190, 0x31E3),
+    (0x31F0, 0x321E), (0x3220, 0x3247), (0x3250, 0x4DBF), (0x4E00, 0xA48C), (0xA490, 0xA4C6),
+    (0xA960, 0xA97C), (0xAC00, 0xD7A3), (0xF900, 0xFAFF), (0xFE10, 0xFE19), (0xFE30, 0xFE52),
+    (0xFE54, 0xFE66), (0xFE68, 0xFE6B), (0xFF01, 0xFF60), (0xFFE0, 0xFFE6), (0x16FE0, 0x16FE4),
+    (0x16FF0, 0x16FF1), (0x17000, 0x187F7), (0x18800, 0x18CD5), (0x18D00, 0x18D08),
+    (0x1AFF0, 0x1AFF3), (0x1AFF5, 0x1AFFB), (0x1AFFD, 0x1AFFE), (0x1B000, 0x1B122),
+    (0x1B150, 0x1B152), (0x1B164, 0x1B167), (0x1B170, 0x1B2FB), (0x1F004, 0x1F004),
+    (0x1F0CF, 0x1F0CF), (0x1F18E, 0x1F18E), (0x1F191, 0x1F19A), (0x1F200, 0x1F202),
+    (0x1F210, 0x1F23B), (0x1F240, 0x1F248), (0x1F250, 0x1F251), (0x1F260, 0x1F265),
+    (0x1F300, 0x1F320), (0x1F32D, 0x1F335), (0x1F337, 0x1F37C), (0x1F37E, 0x1F393),
+    (0x1F3A0, 0x1F3CA), (0x1F3CF, 0x1F3D3), (0x1F3E0, 0x1F3F0), (0x1F3F4, 0x1F3F4),
+    (0x1F3F8, 0x1F43E), (0x1F440, 0x1F440), (0x1F442, 0x1F4FC), (0x1F4FF, 0x1F53D),
+    (0x1F54B, 0x1F54E), (0x1F550, 0x1F567), (0x1F57A, 0x1F57A), (0x1F595, 0x1F596),
+    (0x1F5A4, 0x1F5A4), (0x1F5FB, 0x1F64F), (0x1F680, 0x1F6C5), (0x1F6CC, 0x1F6CC),
+    (0x1F6D0, 0x1F6D2), (0x1F6D5, 0x1F6D7), (0x1F6DD, 0x1F6DF), (0x1F6EB, 0x1F6EC),
+    (0x1F6F4, 0x1F6FC), (0x1F7E0, 0x1F7EB), (0x1F7F0, 0x1F7F0), (0x1F90C, 0x1F93A),
+    (0x1F93C, 0x1F945), (0x1F947, 0x1F9FF), (0x1FA70, 0x1FA74), (0x1FA78, 0x1FA7C),
+    (0x1FA80, 0x1FA86), (0x1FA90, 0x1FAAC), (0x1FAB0, 0x1FABA), (0x1FAC0, 0x1FAC5),
+    (0x1FAD0, 0x1FAD9), (0x1FAE0, 0x1FAE7), (0x1FAF0, 0x1FAF6), (0x20000, 0x2FFFD),
+    (0x30000, 0x3FFFD),
+];
@@ -0,0 +1,438 @@
+// Building a `User` step by step.
+//
+//   let ray = User::builder()
+//       .username("ray")
+//       .email("ray@example.com")
+//       .display_name("Ray Young")
+//       .locale("en-GB")
+//       .build()?;
+//
+// The username and email are tracked in the builder's type, so `build` only exists once both
+// are given and neither can be given twice; forgetting one is a compile error rather than a
+// runtime one. Everything is validated in `build`. Whatever isn't set starts as in
+// `build_user`: pending verification, one sign-in, no password, no roles, an empty profile.
+use crate::lifecycle::Lifecycle;
+use crate::structs::{Profile, User};
+use crate::user_validation::{validate_email, validate_username, UserError};
+use std::fmt;
+
+pub const DISPLAY_NAME_MAX_LEN: usize = 64;
+pub const AVATAR_URL_MAX_LEN: usize = 2048;
+
+// the first part of every IANA zone name, besides the lone `UTC`
+const TIMEZONE_AREAS: [&str; 11] = [
+    "Africa",
+    "America",
+    "Antarctica",
+    "Arctic",
+    "Asia",
+    "Atlantic",
+    "Australia",
+    "Etc",
+    "Europe",
+    "Indian",
+    "Pacific",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    InvalidUser(UserError),
+    EmptyDisplayName,
+    DisplayNameTooLong(usize),
+    InvalidDisplayNameCharacter(char),
+    InvalidLocale(String),
+    InvalidTimezone(String),
+    InvalidAvatarUrl(String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::InvalidUser(e) => write!(f, "{}", e),
+            BuildError::EmptyDisplayName => write!(f, "display name is empty"),
+            BuildError::DisplayNameTooLong(len) => write!(
+                f,
+                "display name has {} characters, at most {} are allowed",
+                len, DISPLAY_NAME_MAX_LEN
+            ),
+            BuildError::InvalidDisplayNameCharacter(c) => {
+                write!(f, "display name may not contain {:?}", c)
+            }
+            BuildError::InvalidLocale(locale) => {
+                write!(f, "{:?} is not a language tag like \"en-GB\"", locale)
+            }
+            BuildError::InvalidTimezone(zone) => {
+                write!(f, "{:?} is not a time zone like \"Europe/London\"", zone)
+            }
+            BuildError::InvalidAvatarUrl(url) => {
+                write!(f, "avatar URL {:?} is not an https URL", url)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl From<UserError> for BuildError {
+    fn from(e: UserError) -> BuildError {
+        BuildError::InvalidUser(e)
+    }
+}
+
+// surrounding spaces are trimmed by the builder before this is called
+pub fn validate_display_name(name: &str) -> Result<(), BuildError> {
+    if name.is_empty() {
+        return Err(BuildError::EmptyDisplayName);
+    }
+    let len = name.chars().count();
+    if len > DISPLAY_NAME_MAX_LEN {
+        return Err(BuildError::DisplayNameTooLong(len));
+    }
+    match name.chars().find(|c| c.is_control()) {
+        Some(c) => Err(BuildError::InvalidDisplayNameCharacter(c)),
+        None => Ok(()),
+    }
+}
+
+// A language, then optional script, region and variant subtags: `en`, `en-GB`, `zh-Hant-TW`.
+// The shape is checked, not whether the subtags are registered.
+pub fn validate_locale(locale: &str) -> Result<(), BuildError> {
+    let invalid = || BuildError::InvalidLocale(locale.to_string());
+    let mut subtags = locale.split('-');
+    let language = subtags.next().unwrap_or("");
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(invalid());
+    }
+    for subtag in subtags {
+        if !(2..=8).contains(&subtag.len()) || !subtag.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(invalid());
+        }
+    }
+    Ok(())
+}
+
+// `UTC`, or an area and one or two places such as `America/Argentina/Buenos_Aires`
+pub fn validate_timezone(zone: &str) -> Result<(), BuildError> {
+    if zone == "UTC" {
+        return Ok(());
+    }
+    let parts: Vec<&str> = zone.split('/').collect();
+    let well_formed = (2..=3).contains(&parts.len())
+        && TIMEZONE_AREAS.contains(&parts[0])
+        && parts[1..].iter().all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '+')
+        });
+    if well_formed {
+        Ok(())
+    } else {
+        Err(BuildError::InvalidTimezone(zone.to_string()))
+    }
+}
+
+// only https, so a profile never pulls images over plain text
+pub fn validate_avatar_url(url: &str) -> Result<(), BuildError> {
+    let invalid = || BuildError::InvalidAvatarUrl(url.to_string());
+    if url.len() > AVATAR_URL_MAX_LEN || url.contains(|c: char| c.is_whitespace() || c.is_control())
+    {
+        return Err(invalid());
+    }
+    let rest = url.strip_prefix("https://").ok_or_else(invalid)?;
+    let host = rest.split(&['/', '?', '#'][..]).next().unwrap_or("");
+    // no user info, and a host that at least looks like one
+    if host.is_empty() || host.contains('@') || host.starts_with('.') || host.starts_with(':') {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+// the type of a required field not given yet
+#[derive(Debug)]
+pub struct Missing;
+
+// the type of a required field that has been given
+#[derive(Debug)]
+pub struct Given(String);
+
+#[derive(Debug)]
+pub struct UserBuilder<U, E> {
+    username: U,
+    email: E,
+    sign_in_count: u64,
+    lifecycle: Lifecycle,
+    password_hash: Option<String>,
+    roles: Vec<String>,
+    profile: Profile,
+}
+
+impl User {
+    pub fn builder() -> UserBuilder<Missing, Missing> {
+        UserBuilder {
+            username: Missing,
+            email: Missing,
+            sign_in_count: 1,
+            lifecycle: Lifecycle::pending(),
+            password_hash: None,
+            roles: Vec::new(),
+            profile: Profile::default(),
+        }
+    }
+}
+
+impl<E> UserBuilder<Missing, E> {
+    pub fn username<S: Into<String>>(self, username: S) -> UserBuilder<Given, E> {
+        UserBuilder {
+            username: Given(username.into()),
+            email: self.email,
+            sign_in_count: self.sign_in_count,
+            lifecycle: self.lifecycle,
+            password_hash: self.password_hash,
+            roles: self.roles,
+            profile: self.profile,
+        }
+    }
+}
+
+impl<U> UserBuilder<U, Missing> {
+    pub fn email<S: Into<String>>(self, email: S) -> UserBuilder<U, Given> {
+        UserBuilder {
+            username: self.username,
+            email: Given(email.into()),
+            sign_in_count: self.sign_in_count,
+            lifecycle: self.lifecycle,
+            password_hash: self.password_hash,
+            roles: self.roles,
+            profile: self.profile,
+        }
+    }
+}
+
+impl<U, E> UserBuilder<U, E> {
+    pub fn display_name<S: Into<String>>(mut self, name: S) -> UserBuilder<U, E> {
+        self.profile.display_name = Some(name.into());
+        self
+    }
+
+    pub fn locale<S: Into<String>>(mut self, locale: S) -> UserBuilder<U, E> {
+        self.profile.locale = Some(locale.into());
+        self
+    }
+
+    pub fn timezone<S: Into<String>>(mut self, zone: S) -> UserBuilder<U, E> {
+        self.profile.timezone = Some(zone.into());
+        self
+    }
+
+    pub fn avatar_url<S: Into<String>>(mut self, url: S) -> UserBuilder<U, E> {
+        self.profile.avatar_url = Some(url.into());
+        self
+    }
+
+    pub fn role<S: Into<String>>(mut self, role: S) -> UserBuilder<U, E> {
+        self.roles.push(role.into());
+        self
+    }
+
+    pub fn sign_in_count(mut self, count: u64) -> UserBuilder<U, E> {
+        self.sign_in_count = count;
+        self
+    }
+
+    pub fn lifecycle(mut self, lifecycle: Lifecycle) -> UserBuilder<U, E> {
+        self.lifecycle = lifecycle;
+        self
+    }
+
+    // an already hashed password, a PHC string as from password.rs
+    pub fn password_hash<S: Into<String>>(mut self, hash: S) -> UserBuilder<U, E> {
+        self.password_hash = Some(hash.into());
+        self
+    }
+}
+
+impl UserBuilder<Given, Given> {
+    pub fn build(self) -> Result<User, BuildError> {
+        let Given(username) = self.username;
+        let Given(email) = self.email;
+        validate_username(&username)?;
+        validate_email(&email)?;
+        let mut profile = self.profile;
+        if let Some(name) = &mut profile.display_name {
+            *name = name.trim().to_string();
+            validate_display_name(name)?;
+        }
+        if let Some(locale) = &profile.locale {
+            validate_locale(locale)?;
+        }
+        if let Some(zone) = &profile.timezone {
+            validate_timezone(zone)?;
+        }
+        if let Some(url) = &profile.avatar_url {
+            validate_avatar_url(url)?;
+        }
+        Ok(User {
+            username,
+            email,
+            sign_in_count: self.sign_in_count,
+            lifecycle: self.lifecycle,
+            password_hash: self.password_hash,
+            roles: self.roles,
+            profile,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lifecycle::{AccountState, Lifecycle};
+    use crate::structs::{build_user, User};
+    use crate::user_builder::{
+        validate_avatar_url, validate_locale, validate_timezone, BuildError,
+    };
+    use crate::user_validation::UserError;
+
+    #[test]
+    fn test_defaults_match_build_user() {
+        let built = User::builder()
+            .email("ray@example.com")
+            .username("Ray")
+            .build()
+            .unwrap();
+        assert_eq!(
+            built,
+            build_user("ray@example.com".to_string(), "Ray".to_string())
+        );
+    }
+
+    #[test]
+    fn test_all_fields() {
+        let ray = User::builder()
+            .username("Ray")
+            .display_name("  Ray Young ")
+            .email("ray@example.com")
+            .locale("zh-Hant-TW")
+            .timezone("Asia/Taipei")
+            .avatar_url("https://cdn.example.com/a/ray.png?size=64")
+            .role("editor")
+            .role("viewer")
+            .sign_in_count(0)
+            .lifecycle(Lifecycle::active())
+            .password_hash("$pbkdf2-sha256$i=1$c2FsdA$VawE")
+            .build()
+            .unwrap();
+        assert_eq!(ray.profile.display_name.as_deref(), Some("Ray Young"));
+        assert_eq!(ray.profile.locale.as_deref(), Some("zh-Hant-TW"));
+        assert_eq!(ray.roles, ["editor", "viewer"]);
+        assert_eq!(ray.sign_in_count, 0);
+        assert_eq!(ray.lifecycle.state(), AccountState::Active);
+        assert!(ray.password_hash.is_some());
+    }
+
+    #[test]
+    fn test_build_validates() {
+        let base = || User::builder().username("Ray").email("ray@example.com");
+        assert_eq!(
+            User::builder()
+                .username("x")
+                .email("ray@example.com")
+                .build(),
+            Err(BuildError::InvalidUser(UserError::UsernameTooShort(1)))
+        );
+        assert_eq!(
+            User::builder().username("Ray").email("ray").build(),
+            Err(BuildError::InvalidUser(UserError::MissingAtSign))
+        );
+        assert_eq!(
+            base().display_name("   ").build(),
+            Err(BuildError::EmptyDisplayName)
+        );
+        assert_eq!(
+            base().display_name("R".repeat(65)).build(),
+            Err(BuildError::DisplayNameTooLong(65))
+        );
+        assert_eq!(
+            base().display_name("Ray\u{7}").build(),
+            Err(BuildError::InvalidDisplayNameCharacter('\u{7}'))
+        );
+        assert_eq!(
+            base().locale("english").build(),
+            Err(BuildError::InvalidLocale("english".to_string()))
+        );
+        assert_eq!(
+            base().timezone("Mars/Olympus").build(),
+            Err(BuildError::InvalidTimezone("Mars/Olympus".to_string()))
+        );
+        assert_eq!(
+            base().avatar_url("http://example.com/ray.png").build(),
+            Err(BuildError::InvalidAvatarUrl(
+                "http://example.com/ray.png".to_string()
+            ))
+        );
+        // a username that fails still stops the build before the profile is looked at
+        assert!(matches!(
+            User::builder()
+                .username("")
+                .email("ray@example.com")
+                .locale("?")
+                .build(),
+            Err(BuildError::InvalidUser(UserError::EmptyUsername))
+        ));
+    }
+
+    #[test]
+    fn test_profile_rules() {
+        for locale in ["en", "EN", "en-GB", "zh-Hant-TW", "de-CH-1996", "haw"].iter() {
+            assert_eq!(validate_locale(locale), Ok(()), "{}", locale);
+        }
+        for locale in ["", "e", "en_GB", "en-", "en--GB", "english", "en-G"].iter() {
+            assert!(validate_locale(locale).is_err(), "{}", locale);
+        }
+        for zone in [
+            "UTC",
+            "Europe/London",
+            "America/Argentina/Buenos_Aires",
+            "Etc/GMT+8",
+            "America/Port-au-Prince",
+        ]
+        .iter()
+        {
+            assert_eq!(validate_timezone(zone), Ok(()), "{}", zone);
+        }
+        for zone in [
+            "",
+            "utc",
+            "London",
+            "Europe/",
+            "Europe/London/x/y",
+            "Europe/Lon don",
+        ]
+        .iter()
+        {
+            assert!(validate_timezone(zone).is_err(), "{}", zone);
+        }
+        for url in [
+            "https://example.com",
+            "https://example.com:8443/a.png",
+            "https://x.io#y",
+        ]
+        .iter()
+        {
+            assert_eq!(validate_avatar_url(url), Ok(()), "{}", url);
+        }
+        for url in [
+            "",
+            "https://",
+            "https:///path",
+            "https://user@example.com/a.png",
+            "https://exa mple.com",
+            "javascript:alert(1)",
+            "ftp://example.com/a.png",
+        ]
+        .iter()
+        {
+            assert!(validate_avatar_url(url).is_err(), "{}", url);
+        }
+    }
+}
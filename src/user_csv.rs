@@ -0,0 +1,437 @@
+// Users to and from CSV, for the spreadsheets operations work with.
+//
+// Columns are found by their header through a `ColumnMapping`. Username and email are
+// required, the other columns are used when present. Each row is validated like
+// `try_build_user` and created in the repository; a bad row is reported with its line and
+// skipped while the rest of the file is still imported, rows the CSV reader can't make sense
+// of included. Lifecycle history and passwords stay out of CSV.
+//
+// Spreadsheets run a cell starting with `=`, `+`, `-` or `@` as a formula, so the export puts
+// a `'` in front of those and the import takes it off again.
+use crate::csv::{self, CsvError};
+use crate::lifecycle::Lifecycle;
+use crate::structs::User;
+use crate::user_record::{decode_state, encode_state};
+use crate::user_repository::{UserId, UserRepository};
+use crate::user_validation::try_build_user;
+use std::fmt;
+
+// header names of the columns, matched ignoring case and surrounding spaces
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMapping {
+    pub username: String,
+    pub email: String,
+    pub sign_in_count: Option<String>,
+    pub state: Option<String>,
+    // several roles go in one cell, separated by `;`
+    pub roles: Option<String>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> ColumnMapping {
+        ColumnMapping {
+            username: "username".to_string(),
+            email: "email".to_string(),
+            sign_in_count: Some("sign_in_count".to_string()),
+            state: Some("state".to_string()),
+            roles: Some("roles".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ImportOptions {
+    pub mapping: ColumnMapping,
+    // check every row and report, but leave the repository alone
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    Csv(CsvError),
+    // not even a header row
+    Empty,
+    MissingColumn(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImportError::Csv(e) => write!(f, "{}", e),
+            ImportError::Empty => write!(f, "the file is empty"),
+            ImportError::MissingColumn(name) => write!(f, "there is no {:?} column", name),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    // line and new id of every row that went in; in a dry run, the id it would have had
+    pub imported: Vec<(usize, UserId)>,
+    pub errors: Vec<RowError>,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} of {} rows",
+            if self.dry_run {
+                "would import"
+            } else {
+                "imported"
+            },
+            self.imported.len(),
+            self.imported.len() + self.errors.len()
+        )?;
+        for error in &self.errors {
+            write!(f, "\n{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+const FORMULA_STARTS: &[char] = &['=', '+', '-', '@'];
+
+fn escape_formula(cell: String) -> String {
+    if cell.starts_with(FORMULA_STARTS) {
+        format!("'{}", cell)
+    } else {
+        cell
+    }
+}
+
+fn unescape_formula(cell: &str) -> &str {
+    match cell.strip_prefix('\'') {
+        Some(rest) if rest.starts_with(FORMULA_STARTS) => rest,
+        _ => cell,
+    }
+}
+
+// where each mapped column is in a row
+struct Columns {
+    count: usize,
+    username: usize,
+    email: usize,
+    sign_in_count: Option<usize>,
+    state: Option<usize>,
+    roles: Option<usize>,
+}
+
+impl Columns {
+    fn find(header: &[String], mapping: &ColumnMapping) -> Result<Columns, ImportError> {
+        let position = |name: &str| {
+            header
+                .iter()
+                .position(|column| column.trim().eq_ignore_ascii_case(name.trim()))
+        };
+        let required =
+            |name: &str| position(name).ok_or_else(|| ImportError::MissingColumn(name.to_string()));
+        Ok(Columns {
+            count: header.len(),
+            username: required(&mapping.username)?,
+            email: required(&mapping.email)?,
+            sign_in_count: mapping.sign_in_count.as_deref().and_then(position),
+            state: mapping.state.as_deref().and_then(position),
+            roles: mapping.roles.as_deref().and_then(position),
+        })
+    }
+
+    fn user(&self, fields: &[String]) -> Result<User, String> {
+        if fields.len() != self.count {
+            return Err(format!(
+                "expected {} fields, found {}",
+                self.count,
+                fields.len()
+            ));
+        }
+        let field = |i: usize| unescape_formula(fields[i].trim());
+        let cell = |column: Option<usize>| column.map(field).unwrap_or("");
+        let mut user = try_build_user(
+            field(self.email).to_string(),
+            field(self.username).to_string(),
+        )
+        .map_err(|e| e.to_string())?;
+        let sign_in_count = cell(self.sign_in_count);
+        if !sign_in_count.is_empty() {
+            user.sign_in_count = sign_in_count
+                .parse()
+                .map_err(|_| format!("invalid sign-in count {:?}", sign_in_count))?;
+        }
+        let state = cell(self.state);
+        if !state.is_empty() {
+            let state = decode_state(state).ok_or_else(|| format!("invalid state {:?}", state))?;
+            user.lifecycle = Lifecycle::new(state);
+        }
+        user.roles = cell(self.roles)
+            .split(';')
+            .map(|role| role.trim())
+            .filter(|role| !role.is_empty())
+            .map(|role| role.to_string())
+            .collect();
+        Ok(user)
+    }
+}
+
+pub fn import_users(
+    text: &str,
+    options: &ImportOptions,
+    repository: &mut UserRepository,
+) -> Result<ImportReport, ImportError> {
+    let mut rows = csv::records(text);
+    let header = rows
+        .next()
+        .ok_or(ImportError::Empty)?
+        .map_err(ImportError::Csv)?;
+    let columns = Columns::find(&header.fields, &options.mapping)?;
+    // a dry run works on a copy, so duplicates within the file are still caught
+    let mut scratch;
+    let target = if options.dry_run {
+        scratch = repository.clone();
+        &mut scratch
+    } else {
+        repository
+    };
+    let mut report = ImportReport {
+        dry_run: options.dry_run,
+        imported: Vec::new(),
+        errors: Vec::new(),
+    };
+    for row in rows {
+        let row = match row {
+            Ok(row) => row,
+            Err(e) => {
+                report.errors.push(RowError {
+                    line: e.line,
+                    message: e.message,
+                });
+                continue;
+            }
+        };
+        // trailing blank lines are not rows
+        if row.fields.len() == 1 && row.fields[0].trim().is_empty() {
+            continue;
+        }
+        let created = columns
+            .user(&row.fields)
+            .and_then(|user| target.create(user).map_err(|e| e.to_string()));
+        match created {
+            Ok(id) => report.imported.push((row.line, id)),
+            Err(message) => report.errors.push(RowError {
+                line: row.line,
+                message,
+            }),
+        }
+    }
+    Ok(report)
+}
+
+// every user in id order, in the columns of `mapping`
+pub fn export_users(repository: &UserRepository, mapping: &ColumnMapping) -> String {
+    let mut header = vec![mapping.username.clone(), mapping.email.clone()];
+    header.extend(mapping.sign_in_count.iter().cloned());
+    header.extend(mapping.state.iter().cloned());
+    header.extend(mapping.roles.iter().cloned());
+    let mut rows = vec![header];
+    for (_, user) in repository.iter() {
+        let mut row = vec![user.username.clone(), user.email.clone()];
+        if mapping.sign_in_count.is_some() {
+            row.push(user.sign_in_count.to_string());
+        }
+        if mapping.state.is_some() {
+            row.push(encode_state(&user.lifecycle.state()));
+        }
+        if mapping.roles.is_some() {
+            row.push(user.roles.join(";"));
+        }
+        rows.push(row.into_iter().map(escape_formula).collect());
+    }
+    csv::write(&rows, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lifecycle::AccountState;
+    use crate::user_csv::{
+        export_users, import_users, ColumnMapping, ImportError, ImportOptions, RowError,
+    };
+    use crate::user_repository::UserRepository;
+
+    const SHEET: &str = "\u{feff}Email,Username,State,Roles,Notes\r\n\
+        ray@example.com,ray,active,editor; viewer,\"first, and \"\"best\"\"\"\r\n\
+        sam@example.com,sam,,,\"two\r\nlines\"\r\n\
+        not-an-email,tom,,,\r\n\
+        RAY@example.com,ray2,,,\r\n\
+        kim@example.com,kim,asleep,,\r\n\
+        lee@example.com,lee\r\n\
+        \r\n";
+
+    #[test]
+    fn test_import_reports_every_bad_row() {
+        let mut repository = UserRepository::new();
+        let report = import_users(SHEET, &ImportOptions::default(), &mut repository).unwrap();
+        assert_eq!(report.imported, [(2, 1), (3, 2)]);
+        assert_eq!(
+            report.errors,
+            [
+                RowError {
+                    line: 5,
+                    message: "email has no @".to_string()
+                },
+                RowError {
+                    line: 6,
+                    message: "email \"RAY@example.com\" is already used by user 1".to_string()
+                },
+                RowError {
+                    line: 7,
+                    message: "invalid state \"asleep\"".to_string()
+                },
+                RowError {
+                    line: 8,
+                    message: "expected 5 fields, found 2".to_string()
+                },
+            ]
+        );
+        let (_, ray) = repository.find_by_username("ray").unwrap();
+        assert_eq!(ray.lifecycle.state(), AccountState::Active);
+        assert_eq!(ray.roles, ["editor", "viewer"]);
+        let (_, sam) = repository.find_by_username("sam").unwrap();
+        assert_eq!(sam.lifecycle.state(), AccountState::PendingVerification);
+        assert!(sam.roles.is_empty());
+        assert!(report
+            .to_string()
+            .starts_with("imported 2 of 6 rows\nline 5: "));
+    }
+
+    #[test]
+    fn test_dry_run_changes_nothing() {
+        let mut repository = UserRepository::new();
+        let options = ImportOptions {
+            dry_run: true,
+            ..ImportOptions::default()
+        };
+        let report = import_users(SHEET, &options, &mut repository).unwrap();
+        assert!(repository.is_empty());
+        assert_eq!(report.imported.len(), 2);
+        // the duplicate within the file is still found
+        assert_eq!(report.errors.len(), 4);
+        assert!(report.to_string().starts_with("would import 2 of 6 rows"));
+    }
+
+    #[test]
+    fn test_column_mapping() {
+        let mapping = ColumnMapping {
+            username: "Login".to_string(),
+            email: "E-mail address".to_string(),
+            sign_in_count: Some("Visits".to_string()),
+            state: None,
+            roles: None,
+        };
+        let options = ImportOptions {
+            mapping: mapping.clone(),
+            dry_run: false,
+        };
+        let text = "login , e-mail address,visits,state\nray,ray@example.com,12,deleted\n";
+        let mut repository = UserRepository::new();
+        let report = import_users(text, &options, &mut repository).unwrap();
+        assert!(report.errors.is_empty());
+        let ray = repository.get(1).unwrap();
+        assert_eq!(ray.sign_in_count, 12);
+        // the state column isn't mapped, so it's ignored
+        assert_eq!(ray.lifecycle.state(), AccountState::PendingVerification);
+        assert_eq!(
+            export_users(&repository, &mapping),
+            "\u{feff}Login,E-mail address,Visits\r\nray,ray@example.com,12\r\n"
+        );
+    }
+
+    #[test]
+    fn test_file_errors() {
+        let mut repository = UserRepository::new();
+        let options = ImportOptions::default();
+        assert_eq!(
+            import_users("", &options, &mut repository),
+            Err(ImportError::Empty)
+        );
+        assert_eq!(
+            import_users("username,mail\n", &options, &mut repository),
+            Err(ImportError::MissingColumn("email".to_string()))
+        );
+        assert!(matches!(
+            import_users("\"username,email\n", &options, &mut repository),
+            Err(ImportError::Csv(_))
+        ));
+    }
+
+    #[test]
+    fn test_malformed_rows_are_skipped() {
+        let text = "username,email\n\
+            \"ray\"x,ray@example.com\n\
+            \"sam,sam@example.com\n\
+            kim,kim@example.com\n";
+        let mut repository = UserRepository::new();
+        let report = import_users(text, &ImportOptions::default(), &mut repository).unwrap();
+        assert_eq!(report.imported, [(4, 1)]);
+        assert_eq!(
+            report.errors,
+            [
+                RowError {
+                    line: 2,
+                    message: "unexpected 'x' after a closing quote".to_string()
+                },
+                RowError {
+                    line: 3,
+                    message: "unterminated quoted field".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_export_escapes_formulas() {
+        let mut repository = UserRepository::new();
+        let text = "username,email,roles\nray,ray@example.com,@team;=1+1\n";
+        import_users(text, &ImportOptions::default(), &mut repository).unwrap();
+        let mapping = ColumnMapping {
+            sign_in_count: None,
+            state: None,
+            ..ColumnMapping::default()
+        };
+        let exported = export_users(&repository, &mapping);
+        assert_eq!(
+            exported,
+            "\u{feff}username,email,roles\r\nray,ray@example.com,'@team;=1+1\r\n"
+        );
+        let mut copy = UserRepository::new();
+        import_users(&exported, &ImportOptions::default(), &mut copy).unwrap();
+        assert_eq!(copy.get(1).unwrap().roles, ["@team", "=1+1"]);
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let mut repository = UserRepository::new();
+        import_users(SHEET, &ImportOptions::default(), &mut repository).unwrap();
+        let text = export_users(&repository, &ColumnMapping::default());
+        let mut copy = UserRepository::new();
+        let report = import_users(&text, &ImportOptions::default(), &mut copy).unwrap();
+        assert!(report.errors.is_empty());
+        let original: Vec<_> = repository.iter().collect();
+        let copied: Vec<_> = copy.iter().collect();
+        assert_eq!(original, copied);
+    }
+}
@@ -0,0 +1,468 @@
+// An event-sourced history of users.
+//
+// Instead of overwriting a `User` in place, every change is recorded as an event and the user
+// is whatever folding their events gives. Nothing is lost: a user can be rebuilt as of any
+// moment, and their history read back event by event. Events are checked against the state
+// they apply to before they are recorded, so the log only ever holds changes that happened.
+use crate::anonymise::{erase, pseudonyms, redact};
+use crate::clock::Clock;
+use crate::lifecycle::{AccountState, LifecycleError};
+use crate::structs::{build_user, User};
+use crate::user_repository::UserId;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UserEvent {
+    UserCreated {
+        username: String,
+        email: String,
+    },
+    UsernameChanged {
+        username: String,
+    },
+    EmailChanged {
+        email: String,
+    },
+    // a PHC string, see password.rs
+    PasswordChanged {
+        password_hash: String,
+    },
+    RolesChanged {
+        roles: Vec<String>,
+    },
+    SignedIn,
+    Activated {
+        reason: String,
+    },
+    Suspended {
+        until: Option<SystemTime>,
+        reason: String,
+    },
+    Deactivated {
+        reason: String,
+    },
+    Deleted {
+        reason: String,
+    },
+    // personal data replaced with pseudonyms, see anonymise.rs
+    Anonymised {
+        username: String,
+        email: String,
+        reason: String,
+    },
+}
+
+impl UserEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            UserEvent::UserCreated { .. } => "user_created",
+            UserEvent::UsernameChanged { .. } => "username_changed",
+            UserEvent::EmailChanged { .. } => "email_changed",
+            UserEvent::PasswordChanged { .. } => "password_changed",
+            UserEvent::RolesChanged { .. } => "roles_changed",
+            UserEvent::SignedIn => "signed_in",
+            UserEvent::Activated { .. } => "activated",
+            UserEvent::Suspended { .. } => "suspended",
+            UserEvent::Deactivated { .. } => "deactivated",
+            UserEvent::Deleted { .. } => "deleted",
+            UserEvent::Anonymised { .. } => "anonymised",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    // position in the log, from 1
+    pub sequence: u64,
+    pub user: UserId,
+    pub at: SystemTime,
+    pub event: UserEvent,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventError {
+    NoSuchUser(UserId),
+    AlreadyCreated(UserId),
+    NotActive(AccountState),
+    Lifecycle(LifecycleError),
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EventError::NoSuchUser(id) => write!(f, "no user with id {}", id),
+            EventError::AlreadyCreated(id) => write!(f, "user {} already exists", id),
+            EventError::NotActive(state) => write!(f, "account is {}", state),
+            EventError::Lifecycle(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for EventError {}
+
+// The user after `event`, given the user before it (`None` before they were created).
+pub fn apply(
+    id: UserId,
+    user: Option<&User>,
+    event: &UserEvent,
+    at: SystemTime,
+) -> Result<User, EventError> {
+    let mut user = match (user, event) {
+        (None, UserEvent::UserCreated { username, email }) => {
+            return Ok(build_user(email.clone(), username.clone()))
+        }
+        (Some(_), UserEvent::UserCreated { .. }) => return Err(EventError::AlreadyCreated(id)),
+        (None, _) => return Err(EventError::NoSuchUser(id)),
+        (Some(user), _) => user.clone(),
+    };
+    let lifecycle = &mut user.lifecycle;
+    match event {
+        UserEvent::UserCreated { .. } => unreachable!(),
+        UserEvent::UsernameChanged { username } => user.username = username.clone(),
+        UserEvent::EmailChanged { email } => user.email = email.clone(),
+        UserEvent::PasswordChanged { password_hash } => {
+            user.password_hash = Some(password_hash.clone())
+        }
+        UserEvent::RolesChanged { roles } => user.roles = roles.clone(),
+        UserEvent::SignedIn => {
+            if !lifecycle.is_active_at(at) {
+                return Err(EventError::NotActive(lifecycle.state()));
+            }
+            lifecycle.expire_suspension(at);
+            user.sign_in_count += 1;
+        }
+        UserEvent::Activated { reason } => lifecycle
+            .activate(reason, at)
+            .map_err(EventError::Lifecycle)?,
+        UserEvent::Suspended { until, reason } => lifecycle
+            .suspend(*until, reason, at)
+            .map_err(EventError::Lifecycle)?,
+        UserEvent::Deactivated { reason } => lifecycle
+            .deactivate(reason, at)
+            .map_err(EventError::Lifecycle)?,
+        UserEvent::Deleted { reason } => lifecycle
+            .delete(reason, at)
+            .map_err(EventError::Lifecycle)?,
+        UserEvent::Anonymised {
+            username,
+            email,
+            reason,
+        } => erase(&mut user, username, email, reason, at).map_err(EventError::Lifecycle)?,
+    }
+    Ok(user)
+}
+
+// every user the events describe, as of the last one
+pub fn fold(events: &[RecordedEvent]) -> Result<HashMap<UserId, User>, EventError> {
+    let mut users = HashMap::new();
+    for recorded in events {
+        let user = apply(
+            recorded.user,
+            users.get(&recorded.user),
+            &recorded.event,
+            recorded.at,
+        )?;
+        users.insert(recorded.user, user);
+    }
+    Ok(users)
+}
+
+pub struct EventStore<C: Clock> {
+    clock: C,
+    events: Vec<RecordedEvent>,
+    // positions in `events` for each user
+    by_user: HashMap<UserId, Vec<usize>>,
+    // the fold of `events`, kept up to date
+    current: HashMap<UserId, User>,
+    next_id: UserId,
+}
+
+impl<C: Clock> EventStore<C> {
+    pub fn new(clock: C) -> EventStore<C> {
+        EventStore {
+            clock,
+            events: Vec::new(),
+            by_user: HashMap::new(),
+            current: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    // picks up from a saved log
+    pub fn from_events(clock: C, events: Vec<RecordedEvent>) -> Result<EventStore<C>, EventError> {
+        let current = fold(&events)?;
+        let mut by_user: HashMap<UserId, Vec<usize>> = HashMap::new();
+        for (i, recorded) in events.iter().enumerate() {
+            by_user.entry(recorded.user).or_default().push(i);
+        }
+        Ok(EventStore {
+            clock,
+            next_id: current.keys().copied().max().unwrap_or(0),
+            events,
+            by_user,
+            current,
+        })
+    }
+
+    pub fn create_user(&mut self, username: &str, email: &str) -> Result<UserId, EventError> {
+        let id = self.next_id + 1;
+        self.record(
+            id,
+            UserEvent::UserCreated {
+                username: username.to_string(),
+                email: email.to_string(),
+            },
+        )?;
+        Ok(id)
+    }
+
+    // applies `event` to the user now and logs it; a change that can't happen is not logged
+    pub fn record(&mut self, user: UserId, event: UserEvent) -> Result<&User, EventError> {
+        let at = self.clock.now();
+        let updated = apply(user, self.current.get(&user), &event, at)?;
+        self.events.push(RecordedEvent {
+            sequence: self.events.len() as u64 + 1,
+            user,
+            at,
+            event,
+        });
+        self.by_user
+            .entry(user)
+            .or_default()
+            .push(self.events.len() - 1);
+        self.current.insert(user, updated);
+        self.next_id = self.next_id.max(user);
+        Ok(&self.current[&user])
+    }
+
+    // Records the user's erasure and redacts their earlier events to the same pseudonyms, so
+    // neither the user nor their history gives the old data away.
+    pub fn anonymise(
+        &mut self,
+        user: UserId,
+        key: &[u8],
+        reason: &str,
+    ) -> Result<&User, EventError> {
+        let (username, email) = pseudonyms(key, user);
+        let event = UserEvent::Anonymised {
+            username: username.clone(),
+            email: email.clone(),
+            reason: reason.to_string(),
+        };
+        // nothing is redacted unless the erasure itself can be recorded
+        apply(user, self.current.get(&user), &event, self.clock.now())?;
+        for &i in self.by_user.get(&user).into_iter().flatten() {
+            redact(&mut self.events[i].event, &username, &email);
+        }
+        self.record(user, event)
+    }
+
+    pub fn current(&self, user: UserId) -> Option<&User> {
+        self.current.get(&user)
+    }
+
+    // the user's events, oldest first
+    pub fn history(&self, user: UserId) -> Vec<&RecordedEvent> {
+        self.by_user
+            .get(&user)
+            .map(|positions| positions.iter().map(|&i| &self.events[i]).collect())
+            .unwrap_or_default()
+    }
+
+    // the user as they were at `at`, `None` if they didn't exist yet
+    pub fn as_of(&self, user: UserId, at: SystemTime) -> Option<User> {
+        let mut state: Option<User> = None;
+        for recorded in self.history(user) {
+            if recorded.at > at {
+                break;
+            }
+            // recorded events always applied, and still do in the same order
+            state = apply(user, state.as_ref(), &recorded.event, recorded.at).ok();
+        }
+        state
+    }
+
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::{Clock, ManualClock};
+    use crate::lifecycle::{AccountState, LifecycleError};
+    use crate::user_events::{fold, EventError, EventStore, UserEvent};
+    use std::time::Duration;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn reason(text: &str) -> String {
+        text.to_string()
+    }
+
+    #[test]
+    fn test_nothing_is_overwritten() {
+        let clock = ManualClock::at_unix(1_000_000);
+        let mut store = EventStore::new(&clock);
+        let ray = store.create_user("Ray", "ray@old.example.com").unwrap();
+        let created = clock.now();
+        clock.advance(DAY);
+        store
+            .record(
+                ray,
+                UserEvent::EmailChanged {
+                    email: "ray@new.example.com".to_string(),
+                },
+            )
+            .unwrap();
+        let changed = clock.now();
+        assert_eq!(store.current(ray).unwrap().email, "ray@new.example.com");
+        assert_eq!(
+            store.as_of(ray, created).unwrap().email,
+            "ray@old.example.com"
+        );
+        assert_eq!(
+            store
+                .as_of(ray, changed - Duration::from_secs(1))
+                .unwrap()
+                .email,
+            "ray@old.example.com"
+        );
+        assert_eq!(
+            store.as_of(ray, changed).unwrap().email,
+            "ray@new.example.com"
+        );
+        assert_eq!(store.as_of(ray, created - Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn test_status_history() {
+        let clock = ManualClock::at_unix(1_000_000);
+        let mut store = EventStore::new(&clock);
+        let ray = store.create_user("Ray", "ray@example.com").unwrap();
+        clock.advance(DAY);
+        store
+            .record(
+                ray,
+                UserEvent::Activated {
+                    reason: reason("email verified"),
+                },
+            )
+            .unwrap();
+        let active = clock.now();
+        clock.advance(DAY);
+        store.record(ray, UserEvent::SignedIn).unwrap();
+        clock.advance(DAY);
+        store
+            .record(
+                ray,
+                UserEvent::Deactivated {
+                    reason: reason("closed by user"),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            store.current(ray).unwrap().lifecycle.state(),
+            AccountState::Deactivated
+        );
+        let then = store.as_of(ray, active).unwrap();
+        assert!(then.is_active());
+        assert_eq!(then.sign_in_count, 1);
+        assert_eq!(store.current(ray).unwrap().sign_in_count, 2);
+        let names: Vec<&str> = store
+            .history(ray)
+            .iter()
+            .map(|recorded| recorded.event.name())
+            .collect();
+        assert_eq!(
+            names,
+            ["user_created", "activated", "signed_in", "deactivated"]
+        );
+        // the lifecycle keeps its own record, with the event times
+        let lifecycle = store.current(ray).unwrap().lifecycle.clone();
+        assert_eq!(lifecycle.history()[0].at, active);
+    }
+
+    #[test]
+    fn test_impossible_changes_are_not_recorded() {
+        let clock = ManualClock::at_unix(1_000_000);
+        let mut store = EventStore::new(&clock);
+        let ray = store.create_user("Ray", "ray@example.com").unwrap();
+        assert_eq!(
+            store.record(ray, UserEvent::SignedIn),
+            Err(EventError::NotActive(AccountState::PendingVerification))
+        );
+        assert_eq!(
+            store.record(
+                ray,
+                UserEvent::Deactivated {
+                    reason: reason("bye")
+                }
+            ),
+            Err(EventError::Lifecycle(LifecycleError::IllegalTransition {
+                from: AccountState::PendingVerification,
+                to: AccountState::Deactivated,
+            }))
+        );
+        assert_eq!(
+            store.record(99, UserEvent::SignedIn),
+            Err(EventError::NoSuchUser(99))
+        );
+        assert_eq!(
+            store.record(
+                ray,
+                UserEvent::UserCreated {
+                    username: "again".to_string(),
+                    email: "again@example.com".to_string()
+                }
+            ),
+            Err(EventError::AlreadyCreated(ray))
+        );
+        assert_eq!(store.history(ray).len(), 1);
+        assert_eq!(store.events().len(), 1);
+    }
+
+    #[test]
+    fn test_fold_rebuilds_every_user() {
+        let clock = ManualClock::at_unix(1_000_000);
+        let mut store = EventStore::new(&clock);
+        let ray = store.create_user("Ray", "ray@example.com").unwrap();
+        let sam = store.create_user("Sam", "sam@example.com").unwrap();
+        clock.advance(DAY);
+        store
+            .record(
+                sam,
+                UserEvent::RolesChanged {
+                    roles: vec!["editor".to_string()],
+                },
+            )
+            .unwrap();
+        store
+            .record(
+                ray,
+                UserEvent::UsernameChanged {
+                    username: "Raymond".to_string(),
+                },
+            )
+            .unwrap();
+        let users = fold(store.events()).unwrap();
+        assert_eq!(users.len(), 2);
+        assert_eq!(&users[&ray], store.current(ray).unwrap());
+        assert_eq!(users[&sam].roles, ["editor"]);
+        assert_eq!(
+            store
+                .events()
+                .iter()
+                .map(|e| e.sequence)
+                .collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+        // a store picked up from the log carries on where it left off
+        let mut reopened = EventStore::from_events(&clock, store.events().to_vec()).unwrap();
+        assert_eq!(reopened.current(ray).unwrap().username, "Raymond");
+        assert_eq!(reopened.create_user("Kim", "kim@example.com").unwrap(), 3);
+        assert_eq!(reopened.events().last().unwrap().sequence, 5);
+    }
+}
@@ -0,0 +1,871 @@
+// A small query language over users, e.g.
+//
+//   active = true and sign_in_count > 10 and email ends_with "@corp.com"
+//   order by sign_in_count desc, username limit 20 offset 40
+//
+// Conditions compare a field with a literal: `=`, `!=`, `<`, `<=`, `>`, `>=`, and for text
+// `contains`, `starts_with` and `ends_with`. They combine with `and`, `or`, `not` and
+// parentheses. Queries are checked against the fields of `User` before they run, so a typo or
+// a comparison that can never make sense is an error pointing at the column, not an empty
+// result. Text comparisons are case-sensitive.
+//
+// Every `and`, `or`, `not` and pair of parentheses nests a condition one level deeper, and
+// queries nesting more than `MAX_DEPTH` levels are refused.
+use crate::structs::User;
+use crate::user_repository::{UserId, UserRepository};
+use std::cmp::Ordering;
+use std::fmt;
+
+// deeper queries are refused rather than risking the stack
+pub const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    // counted in characters, from 1
+    pub column: usize,
+    pub message: String,
+}
+
+impl QueryError {
+    // the query with a caret under the problem, for showing to whoever typed it
+    pub fn pointer(&self, query: &str) -> String {
+        format!(
+            "{}\n{}^ {}",
+            query,
+            " ".repeat(self.column - 1),
+            self.message
+        )
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Number(u64),
+    Symbol(&'static str),
+    LeftParen,
+    RightParen,
+    Comma,
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "`{}`", word),
+            Token::Text(text) => write!(f, "{:?}", text),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Symbol(symbol) => write!(f, "`{}`", symbol),
+            Token::LeftParen => write!(f, "`(`"),
+            Token::RightParen => write!(f, "`)`"),
+            Token::Comma => write!(f, "`,`"),
+            Token::End => write!(f, "the end of the query"),
+        }
+    }
+}
+
+// a token and the byte offset it starts at
+type Spanned = (Token, usize);
+
+fn lex(text: &str) -> Result<Vec<Spanned>, (usize, String)> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let token = if c.is_ascii_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            Token::Word(word)
+        } else if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if !c.is_ascii_digit() {
+                    break;
+                }
+                digits.push(c);
+                chars.next();
+            }
+            let n = digits
+                .parse()
+                .map_err(|_| (start, format!("{} is too large", digits)))?;
+            Token::Number(n)
+        } else if c == '"' {
+            chars.next();
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((at, '\\')) => match chars.next() {
+                        Some((_, c @ '"')) | Some((_, c @ '\\')) => value.push(c),
+                        _ => return Err((at, "only \\\" and \\\\ can be escaped".to_string())),
+                    },
+                    Some((_, c)) => value.push(c),
+                    None => return Err((start, "unterminated string".to_string())),
+                }
+            }
+            Token::Text(value)
+        } else {
+            chars.next();
+            let next = chars.peek().map(|&(_, c)| c);
+            let symbol = match (c, next) {
+                ('(', _) => {
+                    tokens.push((Token::LeftParen, start));
+                    continue;
+                }
+                (')', _) => {
+                    tokens.push((Token::RightParen, start));
+                    continue;
+                }
+                (',', _) => {
+                    tokens.push((Token::Comma, start));
+                    continue;
+                }
+                ('=', Some('=')) => "==",
+                ('=', _) => "=",
+                ('!', Some('=')) => "!=",
+                ('<', Some('=')) => "<=",
+                ('<', _) => "<",
+                ('>', Some('=')) => ">=",
+                ('>', _) => ">",
+                _ => return Err((start, format!("unexpected {:?}", c))),
+            };
+            if symbol.len() == 2 {
+                chars.next();
+            }
+            Token::Symbol(symbol)
+        };
+        tokens.push((token, start));
+    }
+    tokens.push((Token::End, text.len()));
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Id,
+    Username,
+    Email,
+    SignInCount,
+    Active,
+    State,
+    Roles,
+    HasPassword,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Number,
+    Text,
+    Boolean,
+    // a list of text, only `contains` applies
+    List,
+}
+
+const FIELDS: &[(&str, Field, Type)] = &[
+    ("id", Field::Id, Type::Number),
+    ("username", Field::Username, Type::Text),
+    ("email", Field::Email, Type::Text),
+    ("sign_in_count", Field::SignInCount, Type::Number),
+    ("active", Field::Active, Type::Boolean),
+    ("state", Field::State, Type::Text),
+    ("roles", Field::Roles, Type::List),
+    ("has_password", Field::HasPassword, Type::Boolean),
+];
+
+const STATES: &[&str] = &[
+    "pending_verification",
+    "active",
+    "suspended",
+    "deactivated",
+    "deleted",
+];
+
+fn type_name(t: Type) -> &'static str {
+    match t {
+        Type::Number => "a number",
+        Type::Text => "text",
+        Type::Boolean => "true or false",
+        Type::List => "a list",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(u64),
+    Text(String),
+    Boolean(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare { field: Field, op: Op, value: Value },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    // `None` matches everyone
+    pub filter: Option<Expr>,
+    // ties, and queries without `order by`, go by id
+    pub order: Vec<(Field, Direction)>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+// how many single-character edits turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous + if ca == cb { 0 } else { 1 };
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<Spanned>,
+    next: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, offset: usize, message: String) -> QueryError {
+        QueryError {
+            column: self.text[..offset].chars().count() + 1,
+            message,
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next].0
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens[self.next].1
+    }
+
+    fn advance(&mut self) -> Spanned {
+        let token = self.tokens[self.next].clone();
+        if self.next + 1 < self.tokens.len() {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn at_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Token::Word(w) if w == word)
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let found = self.at_word(word);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), QueryError> {
+        if self.eat_word(word) {
+            return Ok(());
+        }
+        Err(self.error(
+            self.offset(),
+            format!("expected `{}`, found {}", word, self.peek()),
+        ))
+    }
+
+    // true where a filter can't continue: the clauses after it, or the end
+    fn at_clause(&self) -> bool {
+        matches!(self.peek(), Token::End)
+            || self.at_word("order")
+            || self.at_word("limit")
+            || self.at_word("offset")
+    }
+
+    fn field(&mut self) -> Result<(Field, Type), QueryError> {
+        let (token, offset) = self.advance();
+        let name = match token {
+            Token::Word(name) => name,
+            other => return Err(self.error(offset, format!("expected a field, found {}", other))),
+        };
+        if let Some(&(_, field, t)) = FIELDS.iter().find(|(n, _, _)| *n == name) {
+            return Ok((field, t));
+        }
+        let mut message = format!("unknown field `{}`", name);
+        let closest = FIELDS
+            .iter()
+            .map(|(n, _, _)| (edit_distance(&name, n), *n))
+            .min();
+        if let Some((distance, suggestion)) = closest {
+            if distance <= 2 {
+                message.push_str(&format!(", did you mean `{}`?", suggestion));
+            }
+        }
+        Err(self.error(offset, message))
+    }
+
+    // one level deeper for the `and`, `or`, `not` or `(` that is next
+    fn enter(&mut self) -> Result<(), QueryError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(
+                self.offset(),
+                format!("nested too deeply, the limit is {}", MAX_DEPTH),
+            ));
+        }
+        self.depth += 1;
+        self.advance();
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let depth = self.depth;
+        let mut left = self.and()?;
+        // each `or` wraps everything before it
+        while self.at_word("or") {
+            self.enter()?;
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let depth = self.depth;
+        let mut left = self.not()?;
+        while self.at_word("and") {
+            self.enter()?;
+            left = Expr::And(Box::new(left), Box::new(self.not()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr, QueryError> {
+        let depth = self.depth;
+        if self.at_word("not") {
+            self.enter()?;
+            let inner = self.not()?;
+            self.depth = depth;
+            return Ok(Expr::Not(Box::new(inner)));
+        }
+        if *self.peek() == Token::LeftParen {
+            let open = self.offset();
+            self.enter()?;
+            let inner = self.or()?;
+            self.depth = depth;
+            if *self.peek() != Token::RightParen {
+                return Err(self.error(
+                    self.offset(),
+                    format!(
+                        "expected `)` to close the `(` at column {}, found {}",
+                        self.text[..open].chars().count() + 1,
+                        self.peek()
+                    ),
+                ));
+            }
+            self.advance();
+            return Ok(inner);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, QueryError> {
+        let field_offset = self.offset();
+        let (field, field_type) = self.field()?;
+        let field_name = &self.text[field_offset..self.offset()]
+            .trim_end()
+            .to_string();
+        let (token, op_offset) = self.advance();
+        let op = match &token {
+            Token::Symbol("=") | Token::Symbol("==") => Op::Eq,
+            Token::Symbol("!=") => Op::Ne,
+            Token::Symbol("<") => Op::Lt,
+            Token::Symbol("<=") => Op::Le,
+            Token::Symbol(">") => Op::Gt,
+            Token::Symbol(">=") => Op::Ge,
+            Token::Word(w) if w == "contains" => Op::Contains,
+            Token::Word(w) if w == "starts_with" => Op::StartsWith,
+            Token::Word(w) if w == "ends_with" => Op::EndsWith,
+            other => {
+                return Err(self.error(
+                    op_offset,
+                    format!(
+                        "expected a comparison after `{}`, found {}",
+                        field_name, other
+                    ),
+                ))
+            }
+        };
+        let (token, value_offset) = self.advance();
+        let value = match token {
+            Token::Number(n) => Value::Number(n),
+            Token::Text(text) => Value::Text(text),
+            Token::Word(w) if w == "true" => Value::Boolean(true),
+            Token::Word(w) if w == "false" => Value::Boolean(false),
+            other => {
+                return Err(self.error(value_offset, format!("expected a value, found {}", other)))
+            }
+        };
+
+        // type checks
+        let allowed = match field_type {
+            Type::Number => !matches!(op, Op::Contains | Op::StartsWith | Op::EndsWith),
+            Type::Text => true,
+            Type::Boolean => matches!(op, Op::Eq | Op::Ne),
+            Type::List => op == Op::Contains,
+        };
+        if !allowed {
+            return Err(self.error(
+                op_offset,
+                format!(
+                    "`{}` is {}, it can't be compared with {}",
+                    field_name,
+                    type_name(field_type),
+                    token_text(&self.text[op_offset..value_offset])
+                ),
+            ));
+        }
+        let value_type = match value {
+            Value::Number(_) => Type::Number,
+            Value::Text(_) => Type::Text,
+            Value::Boolean(_) => Type::Boolean,
+        };
+        let expected = if field_type == Type::List {
+            Type::Text
+        } else {
+            field_type
+        };
+        if value_type != expected {
+            return Err(self.error(
+                value_offset,
+                format!(
+                    "`{}` needs {}, found {}",
+                    field_name,
+                    type_name(expected),
+                    type_name(value_type)
+                ),
+            ));
+        }
+        if let (Field::State, Op::Eq, Value::Text(state))
+        | (Field::State, Op::Ne, Value::Text(state)) = (field, op, &value)
+        {
+            if !STATES.contains(&state.as_str()) {
+                return Err(self.error(
+                    value_offset,
+                    format!(
+                        "unknown state {:?}, states are {}",
+                        state,
+                        STATES.join(", ")
+                    ),
+                ));
+            }
+        }
+        Ok(Expr::Compare { field, op, value })
+    }
+
+    fn number(&mut self, clause: &str) -> Result<u64, QueryError> {
+        match self.advance() {
+            (Token::Number(n), _) => Ok(n),
+            (other, offset) => Err(self.error(
+                offset,
+                format!("expected a number after `{}`, found {}", clause, other),
+            )),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query, QueryError> {
+        let filter = if self.at_clause() {
+            None
+        } else {
+            Some(self.or()?)
+        };
+        let mut order = Vec::new();
+        if self.eat_word("order") {
+            self.expect_word("by")?;
+            loop {
+                let offset = self.offset();
+                let (field, t) = self.field()?;
+                if t == Type::List {
+                    return Err(self.error(offset, "a list can't be sorted by".to_string()));
+                }
+                let direction = if self.eat_word("desc") {
+                    Direction::Descending
+                } else {
+                    self.eat_word("asc");
+                    Direction::Ascending
+                };
+                order.push((field, direction));
+                if *self.peek() != Token::Comma {
+                    break;
+                }
+                self.advance();
+            }
+        }
+        let limit = if self.eat_word("limit") {
+            Some(self.number("limit")? as usize)
+        } else {
+            None
+        };
+        let offset = if self.eat_word("offset") {
+            self.number("offset")? as usize
+        } else {
+            0
+        };
+        if *self.peek() != Token::End {
+            let expected = if filter.is_some() && order.is_empty() && limit.is_none() {
+                "`and`, `or`, `order by`, `limit` or the end"
+            } else {
+                "the end of the query"
+            };
+            return Err(self.error(
+                self.offset(),
+                format!("expected {}, found {}", expected, self.peek()),
+            ));
+        }
+        Ok(Query {
+            filter,
+            order,
+            limit,
+            offset,
+        })
+    }
+}
+
+// how an operator was written, for error messages
+fn token_text(text: &str) -> String {
+    format!("`{}`", text.trim())
+}
+
+fn text_field(field: Field, user: &User) -> Option<&str> {
+    match field {
+        Field::Username => Some(&user.username),
+        Field::Email => Some(&user.email),
+        Field::State => Some(user.lifecycle.state().name()),
+        _ => None,
+    }
+}
+
+fn number_field(field: Field, id: UserId, user: &User) -> Option<u64> {
+    match field {
+        Field::Id => Some(id),
+        Field::SignInCount => Some(user.sign_in_count),
+        _ => None,
+    }
+}
+
+fn boolean_field(field: Field, user: &User) -> Option<bool> {
+    match field {
+        Field::Active => Some(user.is_active()),
+        Field::HasPassword => Some(user.password_hash.is_some()),
+        _ => None,
+    }
+}
+
+fn compare<T: PartialOrd + ?Sized>(a: &T, op: Op, b: &T) -> bool {
+    match op {
+        Op::Eq => a == b,
+        Op::Ne => a != b,
+        Op::Lt => a < b,
+        Op::Le => a <= b,
+        Op::Gt => a > b,
+        Op::Ge => a >= b,
+        _ => false,
+    }
+}
+
+impl Expr {
+    pub fn matches(&self, id: UserId, user: &User) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(id, user) && b.matches(id, user),
+            Expr::Or(a, b) => a.matches(id, user) || b.matches(id, user),
+            Expr::Not(a) => !a.matches(id, user),
+            Expr::Compare { field, op, value } => match (value, *op) {
+                (Value::Text(text), Op::Contains) if *field == Field::Roles => {
+                    user.roles.iter().any(|role| role == text)
+                }
+                (Value::Text(text), op) => {
+                    let actual = text_field(*field, user).unwrap_or("");
+                    match op {
+                        Op::Contains => actual.contains(text.as_str()),
+                        Op::StartsWith => actual.starts_with(text.as_str()),
+                        Op::EndsWith => actual.ends_with(text.as_str()),
+                        _ => compare(actual, op, text.as_str()),
+                    }
+                }
+                (Value::Number(n), op) => number_field(*field, id, user)
+                    .map(|actual| compare(&actual, op, n))
+                    .unwrap_or(false),
+                (Value::Boolean(b), op) => boolean_field(*field, user)
+                    .map(|actual| compare(&actual, op, b))
+                    .unwrap_or(false),
+            },
+        }
+    }
+}
+
+fn order_by(field: Field, a: (UserId, &User), b: (UserId, &User)) -> Ordering {
+    if let (Some(x), Some(y)) = (text_field(field, a.1), text_field(field, b.1)) {
+        return x.cmp(y);
+    }
+    if let (Some(x), Some(y)) = (number_field(field, a.0, a.1), number_field(field, b.0, b.1)) {
+        return x.cmp(&y);
+    }
+    if let (Some(x), Some(y)) = (boolean_field(field, a.1), boolean_field(field, b.1)) {
+        return x.cmp(&y);
+    }
+    Ordering::Equal
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Query, QueryError> {
+        let tokens = lex(text).map_err(|(offset, message)| QueryError {
+            column: text[..offset].chars().count() + 1,
+            message,
+        })?;
+        Parser {
+            text,
+            tokens,
+            next: 0,
+            depth: 0,
+        }
+        .query()
+    }
+
+    pub fn matches(&self, id: UserId, user: &User) -> bool {
+        self.filter
+            .as_ref()
+            .map(|filter| filter.matches(id, user))
+            .unwrap_or(true)
+    }
+
+    pub fn run<'a>(&self, repository: &'a UserRepository) -> Vec<(UserId, &'a User)> {
+        // `iter` is in id order and the sort is stable, so ties stay in id order
+        let mut found: Vec<(UserId, &User)> = repository
+            .iter()
+            .filter(|&(id, user)| self.matches(id, user))
+            .collect();
+        found.sort_by(|&a, &b| {
+            for &(field, direction) in &self.order {
+                let ordering = match direction {
+                    Direction::Ascending => order_by(field, a, b),
+                    Direction::Descending => order_by(field, b, a),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        });
+        found
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::{build_user, User};
+    use crate::user_query::{Query, QueryError, MAX_DEPTH};
+    use crate::user_repository::UserRepository;
+    use std::time::UNIX_EPOCH;
+
+    fn repository() -> UserRepository {
+        let mut repository = UserRepository::new();
+        let people = [
+            ("ray", "ray@corp.com", 25, true, "admin"),
+            ("sam", "sam@corp.com", 3, true, ""),
+            ("kim", "kim@home.org", 40, true, "editor"),
+            ("lee", "lee@corp.com", 12, false, "editor"),
+            ("ann", "ann@corp.com", 12, true, "editor"),
+        ];
+        for &(name, email, count, active, role) in people.iter() {
+            let mut user = build_user(email.to_string(), name.to_string());
+            user.sign_in_count = count;
+            if active {
+                user.lifecycle.activate("verified", UNIX_EPOCH).unwrap();
+            }
+            if !role.is_empty() {
+                user.roles = vec![role.to_string()];
+            }
+            repository.create(user).unwrap();
+        }
+        repository
+    }
+
+    fn names(query: &str) -> Vec<String> {
+        let repository = repository();
+        Query::parse(query)
+            .unwrap()
+            .run(&repository)
+            .into_iter()
+            .map(|(_, user): (_, &User)| user.username.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(
+            names(r#"active = true and sign_in_count > 10 and email ends_with "@corp.com""#),
+            ["ray", "ann"]
+        );
+        assert_eq!(names(""), ["ray", "sam", "kim", "lee", "ann"]);
+        assert_eq!(names("not active = true"), ["lee"]);
+        assert_eq!(names("state = \"pending_verification\""), ["lee"]);
+        assert_eq!(
+            names("roles contains \"editor\" and not (sign_in_count >= 40 or id = 4)"),
+            ["ann"]
+        );
+        // `and` binds tighter than `or`
+        assert_eq!(
+            names("username = \"sam\" or username = \"kim\" and active = false"),
+            ["sam"]
+        );
+        assert_eq!(
+            names("username starts_with \"k\" or email contains \"@home\""),
+            ["kim"]
+        );
+        assert_eq!(names("username < \"kim\""), ["ann"]);
+        assert_eq!(names("has_password = true"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_order_and_pages() {
+        assert_eq!(
+            names("order by sign_in_count desc"),
+            ["kim", "ray", "lee", "ann", "sam"]
+        );
+        // ties go by the next key, then by id
+        assert_eq!(
+            names("order by sign_in_count, username desc"),
+            ["sam", "lee", "ann", "ray", "kim"]
+        );
+        assert_eq!(names("order by username limit 2"), ["ann", "kim"]);
+        assert_eq!(names("order by username limit 2 offset 2"), ["lee", "ray"]);
+        assert_eq!(names("active = true offset 3"), ["ann"]);
+        assert_eq!(names("limit 0"), Vec::<String>::new());
+    }
+
+    fn error(query: &str) -> QueryError {
+        Query::parse(query).unwrap_err()
+    }
+
+    #[test]
+    fn test_errors_have_positions() {
+        let cases = [
+            ("usernme = \"ray\"", 1, "unknown field `usernme`, did you mean `username`?"),
+            ("sign_in_count > \"ten\"", 17, "`sign_in_count` needs a number, found text"),
+            ("active contains \"x\"", 8, "`active` is true or false, it can't be compared with `contains`"),
+            ("roles = \"admin\"", 7, "`roles` is a list, it can't be compared with `=`"),
+            ("state = \"asleep\"", 9, "unknown state \"asleep\", states are pending_verification, active, suspended, deactivated, deleted"),
+            ("email ends_with \"@corp.com", 17, "unterminated string"),
+            ("email ~ \"x\"", 7, "unexpected '~'"),
+            ("email \"x\"", 7, "expected a comparison after `email`, found \"x\""),
+            ("(id = 1 or id = 2", 18, "expected `)` to close the `(` at column 1, found the end of the query"),
+            ("id = 1 id = 2", 8, "expected `and`, `or`, `order by`, `limit` or the end, found `id`"),
+            ("order username", 7, "expected `by`, found `username`"),
+            ("order by roles", 10, "a list can't be sorted by"),
+            ("limit ten", 7, "expected a number after `limit`, found `ten`"),
+            ("id = 99999999999999999999", 6, "99999999999999999999 is too large"),
+        ];
+        for &(query, column, message) in cases.iter() {
+            assert_eq!(
+                error(query),
+                QueryError {
+                    column,
+                    message: message.to_string()
+                },
+                "{}",
+                query
+            );
+        }
+        // columns count characters, not bytes
+        assert_eq!(error("username = \"René\" or x").column, 22);
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let deep = format!("{}id = 1", "not ".repeat(MAX_DEPTH + 1));
+        assert_eq!(
+            error(&deep),
+            QueryError {
+                column: MAX_DEPTH * 4 + 1,
+                message: format!("nested too deeply, the limit is {}", MAX_DEPTH)
+            }
+        );
+        let parens = format!(
+            "{}id = 1{}",
+            "(".repeat(MAX_DEPTH + 1),
+            ")".repeat(MAX_DEPTH + 1)
+        );
+        assert_eq!(error(&parens).column, MAX_DEPTH + 1);
+        let chain = vec!["id = 1"; MAX_DEPTH + 2].join(" or ");
+        // the column of the `or` one past the limit
+        assert_eq!(error(&chain).column, (MAX_DEPTH + 1) * 10 - 2);
+        // up to the limit is fine, and so are levels that close again
+        let limit = format!("{}id = 1", "not ".repeat(MAX_DEPTH));
+        assert!(Query::parse(&limit).is_ok());
+        let closed = format!(
+            "{}id = 1{} and not id = 2",
+            "(".repeat(MAX_DEPTH - 1),
+            ")".repeat(MAX_DEPTH - 1)
+        );
+        assert!(Query::parse(&closed).is_ok());
+    }
+
+    #[test]
+    fn test_pointer() {
+        let query = "sign_in_count > \"ten\"";
+        assert_eq!(
+            error(query).pointer(query),
+            "sign_in_count > \"ten\"\n                ^ `sign_in_count` needs a number, found text"
+        );
+    }
+}
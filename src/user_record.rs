@@ -0,0 +1,443 @@
+// A plain-text encoding of `User` for storage: one `key=value` per line.
+// Values escape `\`, newline and carriage return; unknown keys are skipped when reading.
+//
+// The format is versioned. Records are always written in `CURRENT_VERSION` and older ones are
+// migrated as they are read, one version at a time:
+//   v1  username, email, sign_in_count, `state`, `transition`, `password_hash` and `role`, with
+//       no version line, so a record without one is v1
+//   v2  starts with `version=2`, adds the optional profile fields
+use crate::lifecycle::{AccountState, Lifecycle, Transition};
+use crate::structs::{Profile, User};
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordError {
+    MissingField(&'static str),
+    InvalidValue { field: String, value: String },
+    // a line without `=`
+    MalformedLine(String),
+    // written by a newer version of this program
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::MissingField(field) => write!(f, "missing field {:?}", field),
+            RecordError::InvalidValue { field, value } => {
+                write!(f, "invalid value {:?} for {:?}", value, field)
+            }
+            RecordError::MalformedLine(line) => write!(f, "malformed line {:?}", line),
+            RecordError::UnsupportedVersion(version) => write!(
+                f,
+                "record version {} is newer than {}, the latest known",
+                version, CURRENT_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+pub const CURRENT_VERSION: u32 = 2;
+
+pub type Fields = Vec<(String, String)>;
+
+pub fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn unescape(value: &str) -> Option<String> {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => out.push('\\'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+// nanoseconds since the epoch
+pub fn encode_time(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+        .to_string()
+}
+
+pub fn decode_time(text: &str) -> Option<SystemTime> {
+    let nanos: u128 = text.parse().ok()?;
+    let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+    UNIX_EPOCH.checked_add(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+}
+
+// `suspended@<time>` for a suspension with an end, otherwise the state's name
+pub fn encode_state(state: &AccountState) -> String {
+    match state {
+        AccountState::Suspended { until: Some(until) } => {
+            format!("suspended@{}", encode_time(*until))
+        }
+        _ => state.name().to_string(),
+    }
+}
+
+pub fn decode_state(text: &str) -> Option<AccountState> {
+    if let Some(until) = text.strip_prefix("suspended@") {
+        return Some(AccountState::Suspended {
+            until: Some(decode_time(until)?),
+        });
+    }
+    match text {
+        "pending_verification" => Some(AccountState::PendingVerification),
+        "active" => Some(AccountState::Active),
+        "suspended" => Some(AccountState::Suspended { until: None }),
+        "deactivated" => Some(AccountState::Deactivated),
+        "deleted" => Some(AccountState::Deleted),
+        _ => None,
+    }
+}
+
+// `<time>|<from>|<to>|<reason>`, the reason goes last as it may contain `|`
+fn encode_transition(transition: &Transition) -> String {
+    format!(
+        "{}|{}|{}|{}",
+        encode_time(transition.at),
+        encode_state(&transition.from),
+        encode_state(&transition.to),
+        transition.reason
+    )
+}
+
+fn decode_transition(text: &str) -> Option<Transition> {
+    let mut parts = text.splitn(4, '|');
+    let at = decode_time(parts.next()?)?;
+    let from = decode_state(parts.next()?)?;
+    let to = decode_state(parts.next()?)?;
+    let reason = parts.next()?.to_string();
+    Some(Transition {
+        from,
+        to,
+        reason,
+        at,
+    })
+}
+
+// splits a record into (key, unescaped value) pairs, in order
+pub fn parse_fields(text: &str) -> Result<Fields, RecordError> {
+    let mut fields = Vec::new();
+    for line in text.lines().filter(|line| !line.is_empty()) {
+        let eq = line
+            .find('=')
+            .ok_or_else(|| RecordError::MalformedLine(line.to_string()))?;
+        let (key, raw) = (&line[..eq], &line[eq + 1..]);
+        let value = unescape(raw).ok_or_else(|| RecordError::InvalidValue {
+            field: key.to_string(),
+            value: raw.to_string(),
+        })?;
+        fields.push((key.to_string(), value));
+    }
+    Ok(fields)
+}
+
+pub fn push_field(out: &mut String, key: &str, value: &str) {
+    out.push_str(key);
+    out.push('=');
+    out.push_str(&escape(value));
+    out.push('\n');
+}
+
+pub fn encode_user(user: &User) -> String {
+    let mut out = String::new();
+    push_field(&mut out, "version", &CURRENT_VERSION.to_string());
+    push_field(&mut out, "username", &user.username);
+    push_field(&mut out, "email", &user.email);
+    push_field(&mut out, "sign_in_count", &user.sign_in_count.to_string());
+    push_field(&mut out, "state", &encode_state(&user.lifecycle.state()));
+    for transition in user.lifecycle.history() {
+        push_field(&mut out, "transition", &encode_transition(transition));
+    }
+    if let Some(hash) = &user.password_hash {
+        push_field(&mut out, "password_hash", hash);
+    }
+    for role in &user.roles {
+        push_field(&mut out, "role", role);
+    }
+    let profile = &user.profile;
+    let optional = [
+        ("display_name", &profile.display_name),
+        ("locale", &profile.locale),
+        ("timezone", &profile.timezone),
+        ("avatar_url", &profile.avatar_url),
+    ];
+    for (key, value) in optional.iter() {
+        if let Some(value) = value {
+            push_field(&mut out, key, value);
+        }
+    }
+    out
+}
+
+fn invalid(field: &str, value: &str) -> RecordError {
+    RecordError::InvalidValue {
+        field: field.to_string(),
+        value: value.to_string(),
+    }
+}
+
+// the version a record was written in
+pub fn record_version(fields: &[(String, String)]) -> Result<u32, RecordError> {
+    match fields.iter().find(|(key, _)| key == "version") {
+        Some((key, value)) => match value.parse() {
+            Ok(version) if version >= 1 => Ok(version),
+            _ => Err(invalid(key, value)),
+        },
+        None => Ok(1),
+    }
+}
+
+// the profile fields are optional, so a v1 record only needs its version
+fn v1_to_v2(mut fields: Fields) -> Result<Fields, RecordError> {
+    fields.insert(0, ("version".to_string(), "2".to_string()));
+    Ok(fields)
+}
+
+type Migration = fn(Fields) -> Result<Fields, RecordError>;
+
+// `MIGRATIONS[i]` takes a record from version `i + 1` to `i + 2`
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize - 1] = [v1_to_v2];
+
+// Brings a record up to `CURRENT_VERSION`. Returns it with the version it was written in.
+pub fn migrate(fields: Fields) -> Result<(Fields, u32), RecordError> {
+    let version = record_version(&fields)?;
+    if version > CURRENT_VERSION {
+        return Err(RecordError::UnsupportedVersion(version));
+    }
+    let mut fields = fields;
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        fields = migration(fields)?;
+    }
+    Ok((fields, version))
+}
+
+// the user in a record of any version, with the version it was written in
+pub fn decode_versioned(fields: &[(String, String)]) -> Result<(User, u32), RecordError> {
+    let (fields, version) = migrate(fields.to_vec())?;
+    Ok((decode_current(&fields)?, version))
+}
+
+pub fn decode_fields(fields: &[(String, String)]) -> Result<User, RecordError> {
+    decode_versioned(fields).map(|(user, _)| user)
+}
+
+fn decode_current(fields: &[(String, String)]) -> Result<User, RecordError> {
+    let mut username = None;
+    let mut email = None;
+    let mut sign_in_count = None;
+    let mut state = None;
+    let mut history = Vec::new();
+    let mut password_hash = None;
+    let mut roles = Vec::new();
+    let mut profile = Profile::default();
+    for (key, value) in fields {
+        match key.as_str() {
+            "username" => username = Some(value.clone()),
+            "email" => email = Some(value.clone()),
+            "sign_in_count" => {
+                sign_in_count = Some(value.parse().map_err(|_| invalid(key, value))?)
+            }
+            "state" => state = Some(decode_state(value).ok_or_else(|| invalid(key, value))?),
+            "transition" => {
+                history.push(decode_transition(value).ok_or_else(|| invalid(key, value))?)
+            }
+            "password_hash" => password_hash = Some(value.clone()),
+            "role" => roles.push(value.clone()),
+            "display_name" => profile.display_name = Some(value.clone()),
+            "locale" => profile.locale = Some(value.clone()),
+            "timezone" => profile.timezone = Some(value.clone()),
+            "avatar_url" => profile.avatar_url = Some(value.clone()),
+            _ => {}
+        }
+    }
+    Ok(User {
+        username: username.ok_or(RecordError::MissingField("username"))?,
+        email: email.ok_or(RecordError::MissingField("email"))?,
+        sign_in_count: sign_in_count.ok_or(RecordError::MissingField("sign_in_count"))?,
+        lifecycle: Lifecycle::restore(state.ok_or(RecordError::MissingField("state"))?, history),
+        password_hash,
+        roles,
+        profile,
+    })
+}
+
+pub fn decode_user(text: &str) -> Result<User, RecordError> {
+    decode_fields(&parse_fields(text)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lifecycle::AccountState;
+    use crate::structs::{build_user, User};
+    use crate::user_record::{
+        decode_state, decode_time, decode_user, decode_versioned, encode_state, encode_time,
+        encode_user, escape, parse_fields, unescape, RecordError, CURRENT_VERSION,
+    };
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_escape_round_trip() {
+        for value in ["", "plain", "a\\b", "two\nlines\r\n", "\\n literal"].iter() {
+            assert!(!escape(value).contains('\n'));
+            assert_eq!(unescape(&escape(value)).unwrap(), *value);
+        }
+        assert_eq!(unescape("dangling\\"), None);
+        assert_eq!(unescape("\\x"), None);
+    }
+
+    #[test]
+    fn test_time_and_state() {
+        let time = UNIX_EPOCH + Duration::new(1_600_000_000, 123_456_789);
+        assert_eq!(encode_time(time), "1600000000123456789");
+        assert_eq!(decode_time(&encode_time(time)), Some(time));
+        assert_eq!(decode_time("soon"), None);
+        let states = [
+            AccountState::PendingVerification,
+            AccountState::Active,
+            AccountState::Suspended { until: None },
+            AccountState::Suspended { until: Some(time) },
+            AccountState::Deactivated,
+            AccountState::Deleted,
+        ];
+        for state in states.iter() {
+            assert_eq!(decode_state(&encode_state(state)), Some(*state));
+        }
+        assert_eq!(decode_state("asleep"), None);
+    }
+
+    #[test]
+    fn test_user_round_trip() {
+        let mut ray = build_user("ray@example.com".to_string(), "Ray\nYoung".to_string());
+        ray.sign_in_count = 42;
+        ray.password_hash = Some("$pbkdf2-sha256$i=1$c2FsdA$VawE".to_string());
+        ray.roles = vec!["editor".to_string(), "viewer".to_string()];
+        ray.profile.display_name = Some("Ray\\Young".to_string());
+        ray.profile.timezone = Some("Asia/Shanghai".to_string());
+        let at = UNIX_EPOCH + Duration::from_secs(100);
+        ray.lifecycle.activate("email verified", at).unwrap();
+        ray.lifecycle
+            .suspend(Some(at + Duration::from_secs(60)), "a|b", at)
+            .unwrap();
+        let text = encode_user(&ray);
+        assert_eq!(decode_user(&text), Ok(ray));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(
+            decode_user("username=ray\n"),
+            Err(RecordError::MissingField("email"))
+        );
+        assert_eq!(
+            decode_user("username=ray\nemail=r@example.com\nsign_in_count=lots\nstate=active\n"),
+            Err(RecordError::InvalidValue {
+                field: "sign_in_count".to_string(),
+                value: "lots".to_string()
+            })
+        );
+        assert_eq!(
+            decode_user("no equals sign"),
+            Err(RecordError::MalformedLine("no equals sign".to_string()))
+        );
+        // unknown keys from newer writers are skipped
+        let user = decode_user(
+            "username=ray\nemail=r@example.com\nsign_in_count=1\nstate=active\nshoe_size=9\n",
+        )
+        .unwrap();
+        assert_eq!(user.username, "ray");
+    }
+
+    // One record of each version, as `encode_user` wrote it then and writes it now.
+    const FIXTURES: [(&str, u32); 2] = [
+        (include_str!("fixtures/user_v1.txt"), 1),
+        (include_str!("fixtures/user_v2.txt"), 2),
+    ];
+
+    fn decode_fixture(text: &str) -> (User, u32) {
+        decode_versioned(&parse_fields(text).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_old_records_still_load() {
+        for &(text, version) in FIXTURES.iter() {
+            let (user, found) = decode_fixture(text);
+            assert_eq!(found, version);
+            assert_eq!(user.username, "Ray");
+            assert_eq!(user.email, "ray@example.com");
+        }
+
+        let (v1, _) = decode_fixture(FIXTURES[0].0);
+        assert!(matches!(
+            v1.lifecycle.state(),
+            AccountState::Suspended { until: Some(_) }
+        ));
+        assert_eq!(v1.lifecycle.history().len(), 2);
+        assert_eq!(v1.roles, ["editor", "viewer"]);
+        assert_eq!(v1.profile, Default::default());
+        // v1 was the current layout without its version line, and had no profile to write
+        let written = encode_user(&v1);
+        assert_eq!(
+            written.strip_prefix(&format!("version={}\n", CURRENT_VERSION)),
+            Some(FIXTURES[0].0)
+        );
+
+        let (v2, _) = decode_fixture(FIXTURES[1].0);
+        assert_eq!(v2.profile.display_name.as_deref(), Some("Ray Young"));
+        assert_eq!(v2.profile.timezone.as_deref(), Some("Europe/London"));
+    }
+
+    #[test]
+    fn test_records_are_written_current() {
+        for &(text, _) in FIXTURES.iter() {
+            let (user, _) = decode_fixture(text);
+            let written = encode_user(&user);
+            assert!(written.starts_with(&format!("version={}\n", CURRENT_VERSION)));
+            assert_eq!(decode_fixture(&written), (user, CURRENT_VERSION));
+        }
+        // the current format is stable, a current record is written back byte for byte
+        let (v2, _) = decode_fixture(FIXTURES[1].0);
+        assert_eq!(encode_user(&v2), FIXTURES[1].0);
+    }
+
+    #[test]
+    fn test_unknown_versions() {
+        assert_eq!(
+            decode_user("version=3\nusername=ray\n"),
+            Err(RecordError::UnsupportedVersion(3))
+        );
+        assert_eq!(
+            decode_user("version=0\nusername=ray\n"),
+            Err(RecordError::InvalidValue {
+                field: "version".to_string(),
+                value: "0".to_string()
+            })
+        );
+    }
+}
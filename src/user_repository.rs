@@ -0,0 +1,497 @@
+// In-memory `User` storage with unique secondary indices on username and email.
+// Usernames are also kept apart by their confusable skeleton, see usernames.rs.
+//
+// The rules only apply to new writes. Records loaded with `restore` were written under
+// whatever rules were in force at the time, so two of them may clash under today's: both are
+// loaded, the first keeps the index entry and the clash is listed in `conflicts` until an
+// operator renames one of them.
+use crate::structs::User;
+use crate::usernames::{case_fold, normalize_username, skeleton};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+pub type UserId = u64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepositoryError {
+    // `existing` is the user that already holds the value
+    UsernameTaken { username: String, existing: UserId },
+    EmailTaken { email: String, existing: UserId },
+    // a different username, but one that looks like `existing`'s
+    ConfusableUsername { username: String, existing: UserId },
+    NotFound(UserId),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepositoryError::UsernameTaken { username, existing } => write!(
+                f,
+                "username {:?} is already used by user {}",
+                username, existing
+            ),
+            RepositoryError::EmailTaken { email, existing } => {
+                write!(f, "email {:?} is already used by user {}", email, existing)
+            }
+            RepositoryError::ConfusableUsername { username, existing } => write!(
+                f,
+                "username {:?} is too easily confused with the one used by user {}",
+                username, existing
+            ),
+            RepositoryError::NotFound(id) => write!(f, "no user with id {}", id),
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {}
+
+// index keys, usernames are compared normalized and emails case-folded
+pub fn username_key(username: &str) -> String {
+    normalize_username(username)
+}
+
+pub fn email_key(email: &str) -> String {
+    case_fold(email)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UserRepository {
+    users: HashMap<UserId, User>,
+    by_username: HashMap<String, UserId>,
+    by_skeleton: HashMap<String, UserId>,
+    by_email: HashMap<String, UserId>,
+    // loaded users whose keys clash with another user's, see `restore`
+    conflicts: BTreeMap<UserId, RepositoryError>,
+    next_id: UserId,
+}
+
+impl UserRepository {
+    pub fn new() -> UserRepository {
+        UserRepository::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    // Checks all indices, ignoring entries owned by `owner` so an update can keep its own
+    // values. A value `owner` already has is fine too, even if it clashes with a loaded one.
+    fn check_unique(&self, user: &User, owner: Option<UserId>) -> Result<(), RepositoryError> {
+        let current = owner.and_then(|id| self.users.get(&id));
+        let same_username = current.is_some_and(|c| c.username == user.username);
+        let same_email = current.is_some_and(|c| email_key(&c.email) == email_key(&user.email));
+        if let Some(&existing) = self.by_username.get(&username_key(&user.username)) {
+            if Some(existing) != owner && !same_username {
+                return Err(RepositoryError::UsernameTaken {
+                    username: user.username.clone(),
+                    existing,
+                });
+            }
+        }
+        if let Some(&existing) = self.by_skeleton.get(&skeleton(&user.username)) {
+            if Some(existing) != owner && !same_username {
+                return Err(RepositoryError::ConfusableUsername {
+                    username: user.username.clone(),
+                    existing,
+                });
+            }
+        }
+        if let Some(&existing) = self.by_email.get(&email_key(&user.email)) {
+            if Some(existing) != owner && !same_email {
+                return Err(RepositoryError::EmailTaken {
+                    email: user.email.clone(),
+                    existing,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // indexes the keys nobody holds yet, a conflicting user leaves the others' in place
+    fn index(&mut self, id: UserId, user: &User) {
+        self.by_username
+            .entry(username_key(&user.username))
+            .or_insert(id);
+        self.by_skeleton
+            .entry(skeleton(&user.username))
+            .or_insert(id);
+        self.by_email.entry(email_key(&user.email)).or_insert(id);
+    }
+
+    // removes the keys `id` holds, then lets conflicting users take any that came free
+    fn unindex(&mut self, id: UserId, user: &User) {
+        let keys = [
+            (&mut self.by_username, username_key(&user.username)),
+            (&mut self.by_skeleton, skeleton(&user.username)),
+            (&mut self.by_email, email_key(&user.email)),
+        ];
+        for (index, key) in keys {
+            if index.get(&key) == Some(&id) {
+                index.remove(&key);
+            }
+        }
+        let conflicting: Vec<UserId> = self.conflicts.keys().copied().collect();
+        for other in conflicting {
+            if other == id {
+                continue;
+            }
+            let user = self.users[&other].clone();
+            match self.check_clash(other, &user) {
+                Some(clash) => {
+                    self.conflicts.insert(other, clash);
+                }
+                None => {
+                    self.conflicts.remove(&other);
+                }
+            }
+            self.index(other, &user);
+        }
+    }
+
+    // what `user` clashes with under the current rules, ignoring the keys `id` holds itself
+    fn check_clash(&self, id: UserId, user: &User) -> Option<RepositoryError> {
+        let held = |index: &HashMap<String, UserId>, key: String| match index.get(&key) {
+            Some(&existing) if existing != id => Some(existing),
+            _ => None,
+        };
+        if let Some(existing) = held(&self.by_username, username_key(&user.username)) {
+            return Some(RepositoryError::UsernameTaken {
+                username: user.username.clone(),
+                existing,
+            });
+        }
+        if let Some(existing) = held(&self.by_skeleton, skeleton(&user.username)) {
+            return Some(RepositoryError::ConfusableUsername {
+                username: user.username.clone(),
+                existing,
+            });
+        }
+        held(&self.by_email, email_key(&user.email)).map(|existing| RepositoryError::EmailTaken {
+            email: user.email.clone(),
+            existing,
+        })
+    }
+
+    // ids start at 1 and are never reused, even after a delete
+    pub fn create(&mut self, user: User) -> Result<UserId, RepositoryError> {
+        self.check_unique(&user, None)?;
+        self.next_id += 1;
+        let id = self.next_id;
+        self.index(id, &user);
+        self.users.insert(id, user);
+        Ok(id)
+    }
+
+    pub fn get(&self, id: UserId) -> Option<&User> {
+        self.users.get(&id)
+    }
+
+    pub fn find_by_username(&self, username: &str) -> Option<(UserId, &User)> {
+        let id = *self.by_username.get(&username_key(username))?;
+        self.users.get(&id).map(|user| (id, user))
+    }
+
+    pub fn find_by_email(&self, email: &str) -> Option<(UserId, &User)> {
+        let id = *self.by_email.get(&email_key(email))?;
+        self.users.get(&id).map(|user| (id, user))
+    }
+
+    // replaces the whole record, on a conflict nothing changes
+    pub fn update(&mut self, id: UserId, user: User) -> Result<(), RepositoryError> {
+        if !self.users.contains_key(&id) {
+            return Err(RepositoryError::NotFound(id));
+        }
+        self.check_unique(&user, Some(id))?;
+        self.replace(id, user);
+        Ok(())
+    }
+
+    fn replace(&mut self, id: UserId, user: User) {
+        if let Some(old) = self.users.remove(&id) {
+            self.unindex(id, &old);
+        }
+        match self.check_clash(id, &user) {
+            Some(clash) => {
+                self.conflicts.insert(id, clash);
+            }
+            None => {
+                self.conflicts.remove(&id);
+            }
+        }
+        self.index(id, &user);
+        self.users.insert(id, user);
+    }
+
+    // Puts a user back under a known id, replacing whatever has that id, e.g. when replaying
+    // a log. Uniqueness isn't enforced, a clash ends up in `conflicts` instead.
+    pub fn restore(&mut self, id: UserId, user: User) {
+        self.next_id = self.next_id.max(id);
+        self.replace(id, user);
+    }
+
+    // users whose username or email clashes with another user's, with the first clash found
+    pub fn conflicts(&self) -> impl Iterator<Item = (UserId, &RepositoryError)> {
+        self.conflicts.iter().map(|(&id, clash)| (id, clash))
+    }
+
+    // the highest id handed out so far, deleted or not
+    pub fn last_id(&self) -> UserId {
+        self.next_id
+    }
+
+    // makes sure ids up to `last` are never handed out again
+    pub fn reserve_ids(&mut self, last: UserId) {
+        self.next_id = self.next_id.max(last);
+    }
+
+    pub fn delete(&mut self, id: UserId) -> Result<User, RepositoryError> {
+        let user = self
+            .users
+            .remove(&id)
+            .ok_or(RepositoryError::NotFound(id))?;
+        self.conflicts.remove(&id);
+        self.unindex(id, &user);
+        Ok(user)
+    }
+
+    // in ascending id order
+    pub fn iter(&self) -> impl Iterator<Item = (UserId, &User)> {
+        let mut ids: Vec<UserId> = self.users.keys().copied().collect();
+        ids.sort_unstable();
+        ids.into_iter().map(move |id| (id, &self.users[&id]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::{build_user, User};
+    use crate::user_repository::{RepositoryError, UserRepository};
+
+    fn user(username: &str, email: &str) -> User {
+        build_user(email.to_string(), username.to_string())
+    }
+
+    #[test]
+    fn test_create_and_get() {
+        let mut repo = UserRepository::new();
+        assert!(repo.is_empty());
+        let ray = repo.create(user("Ray", "ray@example.com")).unwrap();
+        let sam = repo.create(user("sam", "sam@example.com")).unwrap();
+        assert_ne!(ray, sam);
+        assert_eq!(repo.len(), 2);
+        assert_eq!(repo.get(ray).unwrap().username, "Ray");
+        assert_eq!(repo.find_by_username("sam").unwrap().0, sam);
+        assert!(repo.find_by_username("nobody").is_none());
+        assert!(repo.get(99).is_none());
+    }
+
+    #[test]
+    fn test_email_lookup_is_case_insensitive() {
+        let mut repo = UserRepository::new();
+        let id = repo.create(user("Ray", "Ray@Example.com")).unwrap();
+        assert_eq!(repo.find_by_email("ray@example.com").unwrap().0, id);
+        assert_eq!(repo.find_by_email("RAY@EXAMPLE.COM").unwrap().0, id);
+        // the stored value keeps its original spelling
+        assert_eq!(repo.get(id).unwrap().email, "Ray@Example.com");
+        // folded, not just lowercased
+        let strasse = repo.create(user("Sam", "straße@example.com")).unwrap();
+        assert_eq!(
+            repo.find_by_email("STRASSE@example.com").unwrap().0,
+            strasse
+        );
+        assert!(repo.create(user("Kim", "σίσυφος@example.com")).is_ok());
+        assert!(matches!(
+            repo.create(user("Lee", "ΣΊΣΥΦΟΣ@example.com")),
+            Err(RepositoryError::EmailTaken { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unique_username_and_email() {
+        let mut repo = UserRepository::new();
+        let ray = repo.create(user("Ray", "ray@example.com")).unwrap();
+        assert_eq!(
+            repo.create(user("Ray", "other@example.com")),
+            Err(RepositoryError::UsernameTaken {
+                username: "Ray".to_string(),
+                existing: ray
+            })
+        );
+        assert_eq!(
+            repo.create(user("other", "RAY@example.com")),
+            Err(RepositoryError::EmailTaken {
+                email: "RAY@example.com".to_string(),
+                existing: ray
+            })
+        );
+        assert_eq!(repo.len(), 1);
+    }
+
+    #[test]
+    fn test_usernames_are_normalized() {
+        let mut repo = UserRepository::new();
+        let ray = repo.create(user("Ray", "ray@example.com")).unwrap();
+        for name in ["ray", "RAY", "\u{ff32}\u{ff41}\u{ff59}"].iter() {
+            assert_eq!(
+                repo.create(user(name, "other@example.com")),
+                Err(RepositoryError::UsernameTaken {
+                    username: name.to_string(),
+                    existing: ray
+                })
+            );
+            assert_eq!(repo.find_by_username(name).unwrap().0, ray);
+        }
+        // the stored value keeps its original spelling
+        assert_eq!(repo.get(ray).unwrap().username, "Ray");
+    }
+
+    #[test]
+    fn test_confusable_usernames() {
+        let mut repo = UserRepository::new();
+        let ray = repo.create(user("Ray", "ray@example.com")).unwrap();
+        // with a Cyrillic a
+        assert_eq!(
+            repo.create(user("R\u{430}y", "other@example.com")),
+            Err(RepositoryError::ConfusableUsername {
+                username: "R\u{430}y".to_string(),
+                existing: ray
+            })
+        );
+        let modern = repo.create(user("modern", "m@example.com")).unwrap();
+        assert!(matches!(
+            repo.create(user("rnodern", "r@example.com")),
+            Err(RepositoryError::ConfusableUsername { existing, .. }) if existing == modern
+        ));
+        // a user may change to a look-alike of their own name
+        repo.update(ray, user("RAY", "ray@example.com")).unwrap();
+        repo.delete(modern).unwrap();
+        repo.create(user("rnodern", "r@example.com")).unwrap();
+    }
+
+    #[test]
+    fn test_update_reindexes() {
+        let mut repo = UserRepository::new();
+        let ray = repo.create(user("Ray", "ray@example.com")).unwrap();
+        let sam = repo.create(user("sam", "sam@example.com")).unwrap();
+        // keeping your own username and email is not a conflict
+        let mut changed = repo.get(ray).unwrap().clone();
+        changed.sign_in_count = 5;
+        repo.update(ray, changed).unwrap();
+        assert_eq!(repo.get(ray).unwrap().sign_in_count, 5);
+
+        repo.update(ray, user("Ray", "ray@new.example.com"))
+            .unwrap();
+        assert!(repo.find_by_email("ray@example.com").is_none());
+        assert_eq!(repo.find_by_email("ray@new.example.com").unwrap().0, ray);
+        // the old email is free again
+        repo.update(sam, user("sam", "ray@example.com")).unwrap();
+
+        assert_eq!(
+            repo.update(sam, user("Ray", "sam@example.com")),
+            Err(RepositoryError::UsernameTaken {
+                username: "Ray".to_string(),
+                existing: ray
+            })
+        );
+        // a failed update leaves the record alone
+        assert_eq!(repo.get(sam).unwrap().email, "ray@example.com");
+        assert_eq!(
+            repo.update(42, user("x", "x@example.com")),
+            Err(RepositoryError::NotFound(42))
+        );
+    }
+
+    #[test]
+    fn test_delete_frees_keys() {
+        let mut repo = UserRepository::new();
+        let ray = repo.create(user("Ray", "ray@example.com")).unwrap();
+        assert_eq!(repo.delete(ray).unwrap().username, "Ray");
+        assert_eq!(repo.delete(ray), Err(RepositoryError::NotFound(ray)));
+        assert!(repo.find_by_username("Ray").is_none());
+        // ids are not reused
+        let again = repo.create(user("Ray", "ray@example.com")).unwrap();
+        assert!(again > ray);
+    }
+
+    #[test]
+    fn test_restore_keeps_ids() {
+        let mut repo = UserRepository::new();
+        repo.restore(7, user("Ray", "ray@example.com"));
+        assert_eq!(repo.get(7).unwrap().username, "Ray");
+        assert_eq!(repo.last_id(), 7);
+        // restoring an existing id replaces it
+        repo.restore(7, user("Ray", "ray@new.example.com"));
+        assert_eq!(repo.len(), 1);
+        assert_eq!(repo.conflicts().count(), 0);
+        repo.reserve_ids(10);
+        assert_eq!(repo.create(user("sam", "sam@example.com")), Ok(11));
+    }
+
+    #[test]
+    fn test_restored_conflicts() {
+        let mut repo = UserRepository::new();
+        // written back when usernames were only compared as they were spelled
+        repo.restore(1, user("Ray", "ray@example.com"));
+        repo.restore(2, user("ray", "other@example.com"));
+        repo.restore(3, user("modern", "m@example.com"));
+        repo.restore(4, user("rnodern", "r@example.com"));
+        assert_eq!(repo.len(), 4);
+        let conflicts: Vec<_> = repo.conflicts().map(|(id, e)| (id, e.clone())).collect();
+        assert_eq!(
+            conflicts,
+            vec![
+                (
+                    2,
+                    RepositoryError::UsernameTaken {
+                        username: "ray".to_string(),
+                        existing: 1
+                    }
+                ),
+                (
+                    4,
+                    RepositoryError::ConfusableUsername {
+                        username: "rnodern".to_string(),
+                        existing: 3
+                    }
+                ),
+            ]
+        );
+        // the first one keeps the name
+        assert_eq!(repo.find_by_username("RAY").unwrap().0, 1);
+        // both can still change everything but the clashing name
+        let mut second = repo.get(2).unwrap().clone();
+        second.sign_in_count = 3;
+        repo.update(2, second).unwrap();
+        assert!(matches!(
+            repo.update(4, user("Ray", "r@example.com")),
+            Err(RepositoryError::UsernameTaken { existing: 1, .. })
+        ));
+        // an operator renames one of them, which settles it
+        repo.update(2, user("raymond", "other@example.com"))
+            .unwrap();
+        assert_eq!(repo.find_by_username("ray").unwrap().0, 1);
+        // deleting the holder hands the name to the other
+        repo.delete(3).unwrap();
+        assert_eq!(repo.conflicts().count(), 0);
+        assert_eq!(repo.find_by_username("rnodern").unwrap().0, 4);
+        assert!(matches!(
+            repo.create(user("modern", "x@example.com")),
+            Err(RepositoryError::ConfusableUsername { existing: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_iter_in_id_order() {
+        let mut repo = UserRepository::new();
+        for name in ["aaa", "bbb", "ccc"].iter() {
+            repo.create(user(name, &format!("{}@example.com", name)))
+                .unwrap();
+        }
+        let names: Vec<&str> = repo.iter().map(|(_, u)| u.username.as_str()).collect();
+        assert_eq!(names, vec!["aaa", "bbb", "ccc"]);
+    }
+}
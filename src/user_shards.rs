@@ -0,0 +1,447 @@
+// A `User` store that many threads can use at once.
+//
+// Records are spread over shards by id, each behind its own `RwLock`, so reads of different
+// users don't wait on each other. The unique keys of user_repository.rs (username, its
+// skeleton and email) live in shards of their own, picked by hash. A write locks every key
+// shard it touches, in ascending order so two writers can't deadlock, and only then the
+// record's shard; reads hold one lock at a time. Changing a user needs the key shards of its
+// current keys, so two writes to one user, or two claims on one key, always meet on a lock.
+use crate::structs::User;
+use crate::user_repository::{email_key, username_key, RepositoryError, UserId};
+use crate::usernames::skeleton;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{RwLock, RwLockWriteGuard};
+
+pub const DEFAULT_SHARDS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Index {
+    Username,
+    Skeleton,
+    Email,
+}
+
+type Key = (Index, String);
+type KeyShard = HashMap<Key, UserId>;
+
+// in the order `UserRepository` checks them, so both report the same conflict
+fn keys(user: &User) -> [Key; 3] {
+    [
+        (Index::Username, username_key(&user.username)),
+        (Index::Skeleton, skeleton(&user.username)),
+        (Index::Email, email_key(&user.email)),
+    ]
+}
+
+fn conflict(index: Index, user: &User, existing: UserId) -> RepositoryError {
+    match index {
+        Index::Username => RepositoryError::UsernameTaken {
+            username: user.username.clone(),
+            existing,
+        },
+        Index::Skeleton => RepositoryError::ConfusableUsername {
+            username: user.username.clone(),
+            existing,
+        },
+        Index::Email => RepositoryError::EmailTaken {
+            email: user.email.clone(),
+            existing,
+        },
+    }
+}
+
+// the key shards a write holds, by shard number
+struct KeyLocks<'a> {
+    guards: Vec<(usize, RwLockWriteGuard<'a, KeyShard>)>,
+}
+
+impl<'a> KeyLocks<'a> {
+    fn shard(&mut self, number: usize) -> &mut KeyShard {
+        let i = self
+            .guards
+            .binary_search_by_key(&number, |(n, _)| *n)
+            .expect("key shard not locked");
+        &mut self.guards[i].1
+    }
+}
+
+pub struct ShardedUserStore {
+    records: Vec<RwLock<HashMap<UserId, User>>>,
+    keys: Vec<RwLock<KeyShard>>,
+    last_id: AtomicU64,
+}
+
+impl Default for ShardedUserStore {
+    fn default() -> ShardedUserStore {
+        ShardedUserStore::with_shards(DEFAULT_SHARDS)
+    }
+}
+
+impl ShardedUserStore {
+    pub fn new() -> ShardedUserStore {
+        ShardedUserStore::default()
+    }
+
+    pub fn with_shards(shards: usize) -> ShardedUserStore {
+        assert!(shards > 0, "a store needs at least one shard");
+        ShardedUserStore {
+            records: (0..shards).map(|_| RwLock::new(HashMap::new())).collect(),
+            keys: (0..shards).map(|_| RwLock::new(HashMap::new())).collect(),
+            last_id: AtomicU64::new(0),
+        }
+    }
+
+    fn record_shard(&self, id: UserId) -> usize {
+        (id % self.records.len() as u64) as usize
+    }
+
+    fn key_shard(&self, key: &Key) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.keys.len() as u64) as usize
+    }
+
+    fn lock_keys<'k>(&self, keys: impl Iterator<Item = &'k Key>) -> KeyLocks<'_> {
+        let mut shards: Vec<usize> = keys.map(|key| self.key_shard(key)).collect();
+        shards.sort_unstable();
+        shards.dedup();
+        KeyLocks {
+            guards: shards
+                .into_iter()
+                .map(|n| (n, self.keys[n].write().unwrap()))
+                .collect(),
+        }
+    }
+
+    fn check_unique(
+        &self,
+        locks: &mut KeyLocks,
+        user: &User,
+        keys: &[Key; 3],
+        owner: Option<UserId>,
+    ) -> Result<(), RepositoryError> {
+        for key in keys {
+            if let Some(&existing) = locks.shard(self.key_shard(key)).get(key) {
+                if Some(existing) != owner {
+                    return Err(conflict(key.0, user, existing));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.records
+            .iter()
+            .map(|shard| shard.read().unwrap().len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // ids start at 1 and are never reused, as in `UserRepository`
+    pub fn create(&self, user: User) -> Result<UserId, RepositoryError> {
+        let keys = keys(&user);
+        let mut locks = self.lock_keys(keys.iter());
+        self.check_unique(&mut locks, &user, &keys, None)?;
+        let id = self.last_id.fetch_add(1, Ordering::Relaxed) + 1;
+        for key in keys.iter() {
+            locks.shard(self.key_shard(key)).insert(key.clone(), id);
+        }
+        self.records[self.record_shard(id)]
+            .write()
+            .unwrap()
+            .insert(id, user);
+        Ok(id)
+    }
+
+    // a copy, the stored record may change as soon as the lock is let go
+    pub fn get(&self, id: UserId) -> Option<User> {
+        self.records[self.record_shard(id)]
+            .read()
+            .unwrap()
+            .get(&id)
+            .cloned()
+    }
+
+    fn find(&self, key: Key) -> Option<(UserId, User)> {
+        let shard = &self.keys[self.key_shard(&key)];
+        loop {
+            let id = *shard.read().unwrap().get(&key)?;
+            // the user may have changed between the two locks, then look again
+            match self.get(id) {
+                Some(user) if keys(&user).contains(&key) => return Some((id, user)),
+                _ => continue,
+            }
+        }
+    }
+
+    pub fn find_by_username(&self, username: &str) -> Option<(UserId, User)> {
+        self.find((Index::Username, username_key(username)))
+    }
+
+    pub fn find_by_email(&self, email: &str) -> Option<(UserId, User)> {
+        self.find((Index::Email, email_key(email)))
+    }
+
+    // Changes a user in place, as one step: no other write to the user comes between reading
+    // it and storing the result. `change` may be called more than once if the user changes
+    // under it. On a conflict nothing changes.
+    pub fn modify<F: FnMut(&mut User)>(
+        &self,
+        id: UserId,
+        mut change: F,
+    ) -> Result<(), RepositoryError> {
+        loop {
+            let current = self.get(id).ok_or(RepositoryError::NotFound(id))?;
+            let mut next = current.clone();
+            change(&mut next);
+            let old_keys = keys(&current);
+            let new_keys = keys(&next);
+            let mut locks = self.lock_keys(old_keys.iter().chain(new_keys.iter()));
+            let mut records = self.records[self.record_shard(id)].write().unwrap();
+            let stored = records.get_mut(&id).ok_or(RepositoryError::NotFound(id))?;
+            if *stored != current {
+                continue;
+            }
+            self.check_unique(&mut locks, &next, &new_keys, Some(id))?;
+            for key in old_keys.iter() {
+                locks.shard(self.key_shard(key)).remove(key);
+            }
+            for key in new_keys.iter() {
+                locks.shard(self.key_shard(key)).insert(key.clone(), id);
+            }
+            *stored = next;
+            return Ok(());
+        }
+    }
+
+    // replaces the whole record
+    pub fn update(&self, id: UserId, user: User) -> Result<(), RepositoryError> {
+        self.modify(id, |stored| *stored = user.clone())
+    }
+
+    pub fn delete(&self, id: UserId) -> Result<User, RepositoryError> {
+        loop {
+            let current = self.get(id).ok_or(RepositoryError::NotFound(id))?;
+            let old_keys = keys(&current);
+            let mut locks = self.lock_keys(old_keys.iter());
+            let mut records = self.records[self.record_shard(id)].write().unwrap();
+            match records.get(&id) {
+                None => return Err(RepositoryError::NotFound(id)),
+                Some(stored) if *stored != current => continue,
+                Some(_) => {}
+            }
+            for key in old_keys.iter() {
+                locks.shard(self.key_shard(key)).remove(key);
+            }
+            return Ok(records.remove(&id).unwrap());
+        }
+    }
+
+    // Every user in id order, as of one moment: all record shards are read-locked together,
+    // which is safe as writers only ever hold one of them.
+    pub fn users(&self) -> Vec<(UserId, User)> {
+        let shards: Vec<_> = self
+            .records
+            .iter()
+            .map(|shard| shard.read().unwrap())
+            .collect();
+        let mut users: Vec<(UserId, User)> = shards
+            .iter()
+            .flat_map(|shard| shard.iter().map(|(&id, user)| (id, user.clone())))
+            .collect();
+        users.sort_unstable_by_key(|&(id, _)| id);
+        users
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::{build_user, User};
+    use crate::user_repository::RepositoryError;
+    use crate::user_shards::{keys, ShardedUserStore};
+    use std::collections::HashMap;
+    use std::thread;
+
+    fn user(username: &str, email: &str) -> User {
+        build_user(email.to_string(), username.to_string())
+    }
+
+    // the key shards hold exactly the keys of the stored users, none of them shared
+    fn check_invariants(store: &ShardedUserStore) {
+        let mut expected = HashMap::new();
+        for (id, user) in store.users() {
+            for key in keys(&user).iter() {
+                if let Some(other) = expected.insert(key.clone(), id) {
+                    panic!("users {} and {} share {:?}", other, id, key);
+                }
+            }
+        }
+        let mut indexed = HashMap::new();
+        for (n, shard) in store.keys.iter().enumerate() {
+            for (key, &id) in shard.read().unwrap().iter() {
+                assert_eq!(store.key_shard(key), n, "{:?} is in the wrong shard", key);
+                indexed.insert(key.clone(), id);
+            }
+        }
+        assert_eq!(indexed, expected);
+    }
+
+    #[test]
+    fn test_single_thread() {
+        let store = ShardedUserStore::with_shards(4);
+        assert!(store.is_empty());
+        let ray = store.create(user("Ray", "ray@example.com")).unwrap();
+        let sam = store.create(user("sam", "sam@example.com")).unwrap();
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.find_by_username("RAY").unwrap().0, ray);
+        assert_eq!(store.find_by_email("SAM@example.com").unwrap().0, sam);
+        assert_eq!(
+            store.create(user("R\u{430}y", "x@example.com")),
+            Err(RepositoryError::ConfusableUsername {
+                username: "R\u{430}y".to_string(),
+                existing: ray
+            })
+        );
+        assert!(matches!(
+            store.update(sam, user("sam", "Ray@example.com")),
+            Err(RepositoryError::EmailTaken { existing, .. }) if existing == ray
+        ));
+        store
+            .modify(ray, |u| u.email = "ray@new.example.com".to_string())
+            .unwrap();
+        assert!(store.find_by_email("ray@example.com").is_none());
+        store.update(sam, user("sam", "ray@example.com")).unwrap();
+        assert_eq!(store.delete(ray).unwrap().username, "Ray");
+        assert_eq!(store.delete(ray), Err(RepositoryError::NotFound(ray)));
+        assert_eq!(
+            store.modify(ray, |_| {}),
+            Err(RepositoryError::NotFound(ray))
+        );
+        assert!(store.create(user("ray", "r@example.com")).unwrap() > sam);
+        check_invariants(&store);
+    }
+
+    #[test]
+    fn test_racing_creates_keep_names_unique() {
+        let store = ShardedUserStore::with_shards(8);
+        // every thread tries every name, some of them look-alikes of others
+        let names: Vec<String> = (0..100)
+            .flat_map(|i| {
+                vec![
+                    format!("user{}", i),
+                    format!("USER{}", i),
+                    format!("u\u{455}er{}", i),
+                ]
+            })
+            .collect();
+        let won: Vec<Vec<String>> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..8)
+                .map(|t| {
+                    let store = &store;
+                    let names = &names;
+                    scope.spawn(move || {
+                        let mut won = Vec::new();
+                        for (i, name) in names.iter().enumerate() {
+                            let email = format!("{}-{}@example.com", t, i);
+                            match store.create(user(name, &email)) {
+                                Ok(_) => won.push(name.clone()),
+                                Err(RepositoryError::UsernameTaken { .. })
+                                | Err(RepositoryError::ConfusableUsername { .. }) => {}
+                                Err(e) => panic!("unexpected {}", e),
+                            }
+                        }
+                        won
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+        // one spelling of each name, for one thread
+        assert_eq!(won.iter().map(Vec::len).sum::<usize>(), 100);
+        assert_eq!(store.len(), 100);
+        check_invariants(&store);
+    }
+
+    #[test]
+    fn test_concurrent_modify_loses_no_updates() {
+        let store = ShardedUserStore::with_shards(4);
+        let ids: Vec<_> = (0..4)
+            .map(|i| {
+                store
+                    .create(user(&format!("user{}", i), &format!("{}@example.com", i)))
+                    .unwrap()
+            })
+            .collect();
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for round in 0..500 {
+                        store
+                            .modify(ids[round % ids.len()], |u| u.sign_in_count += 1)
+                            .unwrap();
+                    }
+                });
+            }
+        });
+        for &id in ids.iter() {
+            // build_user starts the count at 1
+            assert_eq!(store.get(id).unwrap().sign_in_count, 1 + 8 * 500 / 4);
+        }
+    }
+
+    #[test]
+    fn test_renames_under_readers() {
+        let store = ShardedUserStore::with_shards(8);
+        let ids: Vec<_> = (0..20)
+            .map(|i| {
+                store
+                    .create(user(&format!("name{}", i), &format!("{}@example.com", i)))
+                    .unwrap()
+            })
+            .collect();
+        thread::scope(|scope| {
+            // writers move users onto each other's names and emails, most attempts conflict
+            for t in 0..4 {
+                let store = &store;
+                let ids = &ids;
+                scope.spawn(move || {
+                    for round in 0..400 {
+                        let id = ids[(round * 7 + t) % ids.len()];
+                        let n = (round * 13 + t * 5) % 30;
+                        let _ = store.modify(id, |u| {
+                            u.username = format!("name{}", n);
+                            u.email = format!("{}@example.com", (n + t) % 30);
+                        });
+                        if round % 50 == 0 {
+                            let _ = store.delete(ids[(round + t) % ids.len()]);
+                        }
+                    }
+                });
+            }
+            // readers only ever see a user under a name it really has
+            for _ in 0..4 {
+                let store = &store;
+                scope.spawn(move || {
+                    for round in 0..2000 {
+                        let name = format!("NAME{}", round % 30);
+                        if let Some((_, found)) = store.find_by_username(&name) {
+                            assert_eq!(found.username.to_lowercase(), name.to_lowercase());
+                        }
+                        let email = format!("{}@EXAMPLE.com", round % 30);
+                        if let Some((_, found)) = store.find_by_email(&email) {
+                            assert_eq!(found.email.to_lowercase(), email.to_lowercase());
+                        }
+                    }
+                });
+            }
+        });
+        check_invariants(&store);
+    }
+}
@@ -0,0 +1,874 @@
+// Persists a `UserRepository` in a directory on local disk.
+//
+// Every change is appended to `users.log` as a framed record,
+//   [payload length: u32 LE][CRC-32 of payload: u32 LE][payload]
+// and fsynced before the call returns. Opening the store replays the log. A record cut short by
+// a crash can only be the last one, so a bad record at the very end is dropped and the file
+// truncated back to the last good record; a bad record with more data after it is corruption.
+// The length has no checksum of its own, so a record that claims to run past the end of the
+// file only counts as torn if no intact record can be found in what follows its header.
+// A write that fails part way is cut off again before the call returns, and if even that
+// fails the store refuses further writes until it is reopened.
+//
+// Anonymising a user (anonymise.rs) compacts straight away, so no record with their old
+// username or email is left in the log.
+//
+// Compaction writes the live records to `users.log.tmp`, fsyncs it and renames it over the log,
+// so at any moment either the old or the new log is complete on disk.
+use crate::anonymise::{erase, pseudonyms};
+use crate::lifecycle::LifecycleError;
+use crate::structs::User;
+use crate::user_record::{
+    decode_versioned, encode_user, parse_fields, push_field, RecordError, CURRENT_VERSION,
+};
+use crate::user_repository::{RepositoryError, UserId, UserRepository};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const LOG_FILE: &str = "users.log";
+const TEMP_FILE: &str = "users.log.tmp";
+const HEADER_LEN: usize = 8;
+
+#[derive(Debug)]
+pub enum StoreError {
+    Io(io::Error),
+    Repository(RepositoryError),
+    Lifecycle(LifecycleError),
+    // the log is damaged somewhere other than its tail
+    Corrupt { offset: u64, reason: String },
+    // a failed write left bytes in the log that couldn't be removed again
+    Failed,
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Io(e) => write!(f, "i/o error: {}", e),
+            StoreError::Repository(e) => write!(f, "{}", e),
+            StoreError::Lifecycle(e) => write!(f, "{}", e),
+            StoreError::Corrupt { offset, reason } => {
+                write!(f, "corrupt log at byte {}: {}", offset, reason)
+            }
+            StoreError::Failed => write!(f, "an earlier write failed, reopen the store"),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<io::Error> for StoreError {
+    fn from(e: io::Error) -> StoreError {
+        StoreError::Io(e)
+    }
+}
+
+impl From<RepositoryError> for StoreError {
+    fn from(e: RepositoryError) -> StoreError {
+        StoreError::Repository(e)
+    }
+}
+
+// CRC-32 (IEEE 802.3), bit by bit, the records are small
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Op {
+    Put(UserId, Box<User>),
+    Delete(UserId),
+    // keeps deleted ids from being reused once compaction has dropped their records
+    Reserve(UserId),
+}
+
+fn encode_op(op: &Op) -> String {
+    let mut out = String::new();
+    match op {
+        Op::Put(id, user) => {
+            push_field(&mut out, "op", "put");
+            push_field(&mut out, "id", &id.to_string());
+            out.push_str(&encode_user(user));
+        }
+        Op::Delete(id) => {
+            push_field(&mut out, "op", "delete");
+            push_field(&mut out, "id", &id.to_string());
+        }
+        Op::Reserve(id) => {
+            push_field(&mut out, "op", "reserve");
+            push_field(&mut out, "id", &id.to_string());
+        }
+    }
+    out
+}
+
+// the op, and for a put the version its user was written in
+fn decode_op(payload: &[u8]) -> Result<(Op, u32), RecordError> {
+    let text = std::str::from_utf8(payload)
+        .map_err(|_| RecordError::MalformedLine("not UTF-8".to_string()))?;
+    let fields = parse_fields(text)?;
+    // `op` and `id` always lead, the user's fields follow
+    let value = |index: usize, key: &'static str| match fields.get(index) {
+        Some((k, v)) if k == key => Ok(v.clone()),
+        _ => Err(RecordError::MissingField(key)),
+    };
+    let op = value(0, "op")?;
+    let raw_id = value(1, "id")?;
+    let id = raw_id.parse().map_err(|_| RecordError::InvalidValue {
+        field: "id".to_string(),
+        value: raw_id.clone(),
+    })?;
+    match op.as_str() {
+        "put" => {
+            let (user, version) = decode_versioned(&fields[2..])?;
+            Ok((Op::Put(id, Box::new(user)), version))
+        }
+        "delete" => Ok((Op::Delete(id), CURRENT_VERSION)),
+        "reserve" => Ok((Op::Reserve(id), CURRENT_VERSION)),
+        _ => Err(RecordError::InvalidValue {
+            field: "op".to_string(),
+            value: op,
+        }),
+    }
+}
+
+fn frame(op: &Op) -> Vec<u8> {
+    let payload = encode_op(op).into_bytes();
+    let mut out = Vec::with_capacity(HEADER_LEN + payload.len());
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(&crc32(&payload).to_le_bytes());
+    out.extend_from_slice(&payload);
+    out
+}
+
+fn apply(repo: &mut UserRepository, op: Op) -> Result<(), RepositoryError> {
+    match op {
+        Op::Put(id, user) => {
+            repo.restore(id, *user);
+            Ok(())
+        }
+        Op::Delete(id) => repo.delete(id).map(|_| ()),
+        Op::Reserve(id) => {
+            repo.reserve_ids(id);
+            Ok(())
+        }
+    }
+}
+
+// the length and checksum in the header at `offset`, if there is a whole header
+fn header(bytes: &[u8], offset: usize) -> Option<(usize, u32)> {
+    let header = bytes.get(offset..offset + HEADER_LEN)?;
+    let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let crc = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    Some((len, crc))
+}
+
+// Whether an intact record starts anywhere from `from` on. Only called for a record that
+// runs past the end of the file: after a torn write there is nothing, after a damaged length
+// there are the records it swallowed.
+fn record_follows(bytes: &[u8], from: usize) -> bool {
+    (from..bytes.len()).any(|start| match header(bytes, start) {
+        Some((len, crc)) => {
+            let end = start + HEADER_LEN + len;
+            end <= bytes.len()
+                && crc32(&bytes[start + HEADER_LEN..end]) == crc
+                && decode_op(&bytes[start + HEADER_LEN..end]).is_ok()
+        }
+        None => false,
+    })
+}
+
+// what replaying a log found
+struct Replayed {
+    repo: UserRepository,
+    records: usize,
+    // the length of the intact prefix of the log
+    intact: usize,
+    // live users whose record is in an older format
+    outdated: HashSet<UserId>,
+}
+
+fn replay(bytes: &[u8]) -> Result<Replayed, StoreError> {
+    let mut repo = UserRepository::new();
+    let mut outdated = HashSet::new();
+    let mut records = 0;
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let corrupt = |reason: String| StoreError::Corrupt {
+            offset: offset as u64,
+            reason,
+        };
+        // nothing can follow a header cut short
+        let (len, crc) = match header(bytes, offset) {
+            Some(header) => header,
+            None => break,
+        };
+        let end = offset + HEADER_LEN + len;
+        if end > bytes.len() {
+            if record_follows(bytes, offset + HEADER_LEN) {
+                return Err(corrupt(format!(
+                    "record length {} runs past the end of the log",
+                    len
+                )));
+            }
+            break;
+        }
+        let payload = &bytes[offset + HEADER_LEN..end];
+        if crc32(payload) != crc {
+            // a last record whose data never reached the disk
+            if end == bytes.len() {
+                break;
+            }
+            return Err(corrupt("checksum mismatch".to_string()));
+        }
+        let (op, version) = decode_op(payload).map_err(|e| corrupt(e.to_string()))?;
+        match op {
+            Op::Put(id, _) if version < CURRENT_VERSION => outdated.insert(id),
+            Op::Put(id, _) | Op::Delete(id) => outdated.remove(&id),
+            Op::Reserve(_) => false,
+        };
+        apply(&mut repo, op).map_err(|e| corrupt(e.to_string()))?;
+        records += 1;
+        offset = end;
+    }
+    Ok(Replayed {
+        repo,
+        records,
+        intact: offset,
+        outdated,
+    })
+}
+
+// makes a rename or a new file in `dir` durable
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    if cfg!(unix) {
+        File::open(dir)?.sync_all()
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoreOptions {
+    // compact once the log holds at least this many dead records, and more dead than live
+    pub compact_min_garbage: usize,
+}
+
+impl Default for StoreOptions {
+    fn default() -> StoreOptions {
+        StoreOptions {
+            compact_min_garbage: 1_000,
+        }
+    }
+}
+
+pub struct UserStore {
+    dir: PathBuf,
+    log: File,
+    repo: UserRepository,
+    // records in the log file, live or not
+    records: usize,
+    // users still stored in an older record format, see user_record.rs
+    outdated: HashSet<UserId>,
+    options: StoreOptions,
+    // set when a failed write couldn't be taken back out of the log, or a compaction replaced
+    // the log file but couldn't switch `log` over to it
+    failed: bool,
+    // makes the next compaction fail right after its rename
+    #[cfg(test)]
+    fail_reopen: bool,
+}
+
+impl UserStore {
+    pub fn open(dir: &Path) -> Result<UserStore, StoreError> {
+        UserStore::open_with(dir, StoreOptions::default())
+    }
+
+    pub fn open_with(dir: &Path, options: StoreOptions) -> Result<UserStore, StoreError> {
+        fs::create_dir_all(dir)?;
+        // left behind by a compaction that never got to its rename, the log is still whole
+        let temp = dir.join(TEMP_FILE);
+        if temp.exists() {
+            fs::remove_file(&temp)?;
+        }
+        let path = dir.join(LOG_FILE);
+        let mut bytes = Vec::new();
+        if path.exists() {
+            File::open(&path)?.read_to_end(&mut bytes)?;
+        }
+        let Replayed {
+            repo,
+            records,
+            intact,
+            outdated,
+        } = replay(&bytes)?;
+        let log = OpenOptions::new().create(true).append(true).open(&path)?;
+        if intact < bytes.len() {
+            log.set_len(intact as u64)?;
+            log.sync_all()?;
+        }
+        sync_dir(dir)?;
+        Ok(UserStore {
+            dir: dir.to_path_buf(),
+            log,
+            repo,
+            records,
+            outdated,
+            options,
+            failed: false,
+            #[cfg(test)]
+            fail_reopen: false,
+        })
+    }
+
+    pub fn repository(&self) -> &UserRepository {
+        &self.repo
+    }
+
+    pub fn get(&self, id: UserId) -> Option<&User> {
+        self.repo.get(id)
+    }
+
+    // Writes a record, or on failure cuts the log back to where it was, so the next record
+    // doesn't land after a partial one.
+    fn append(&mut self, op: &Op) -> Result<(), StoreError> {
+        if self.failed {
+            return Err(StoreError::Failed);
+        }
+        let len = self.log.metadata()?.len();
+        let written = self
+            .log
+            .write_all(&frame(op))
+            .and_then(|_| self.log.sync_data());
+        if let Err(e) = written {
+            if self
+                .log
+                .set_len(len)
+                .and_then(|_| self.log.sync_data())
+                .is_err()
+            {
+                self.failed = true;
+            }
+            return Err(e.into());
+        }
+        self.records += 1;
+        Ok(())
+    }
+
+    // dead records in the log: overwritten puts, deletes and the records they deleted
+    pub fn garbage(&self) -> usize {
+        self.records.saturating_sub(self.repo.len())
+    }
+
+    // users whose record on disk is in an older format; they read fine, as records are
+    // migrated when loaded, but stay old on disk until rewritten
+    pub fn outdated(&self) -> usize {
+        self.outdated.len()
+    }
+
+    // Rewrites every record in the current format at once, returning how many were old.
+    pub fn upgrade(&mut self) -> Result<usize, StoreError> {
+        let outdated = self.outdated();
+        if outdated > 0 {
+            self.compact()?;
+        }
+        Ok(outdated)
+    }
+
+    fn maybe_compact(&mut self) {
+        let garbage = self.garbage();
+        if garbage >= self.options.compact_min_garbage && garbage > self.repo.len() {
+            // the change is already durable in the old log, so a failed compaction only
+            // means it is tried again after the next change
+            let _ = self.compact();
+        }
+    }
+
+    pub fn create(&mut self, user: User) -> Result<UserId, StoreError> {
+        let id = self.repo.create(user.clone())?;
+        if let Err(e) = self.append(&Op::Put(id, Box::new(user))) {
+            let _ = self.repo.delete(id);
+            return Err(e);
+        }
+        self.maybe_compact();
+        Ok(id)
+    }
+
+    pub fn update(&mut self, id: UserId, user: User) -> Result<(), StoreError> {
+        let old = self
+            .repo
+            .get(id)
+            .cloned()
+            .ok_or(RepositoryError::NotFound(id))?;
+        self.repo.update(id, user.clone())?;
+        if let Err(e) = self.append(&Op::Put(id, Box::new(user))) {
+            self.repo.restore(id, old);
+            return Err(e);
+        }
+        self.outdated.remove(&id);
+        self.maybe_compact();
+        Ok(())
+    }
+
+    pub fn delete(&mut self, id: UserId) -> Result<User, StoreError> {
+        let user = self.repo.delete(id)?;
+        if let Err(e) = self.append(&Op::Delete(id)) {
+            self.repo.restore(id, user);
+            return Err(e);
+        }
+        self.outdated.remove(&id);
+        self.maybe_compact();
+        Ok(user)
+    }
+
+    // Replaces the user's personal data with pseudonyms and closes the account, then compacts
+    // so the earlier records holding the real values are gone from disk. If the compaction
+    // fails the erasure is still logged, call `compact` again until it succeeds.
+    pub fn anonymise(
+        &mut self,
+        id: UserId,
+        key: &[u8],
+        reason: &str,
+        at: SystemTime,
+    ) -> Result<(), StoreError> {
+        let mut user = self
+            .repo
+            .get(id)
+            .cloned()
+            .ok_or(RepositoryError::NotFound(id))?;
+        let (username, email) = pseudonyms(key, id);
+        erase(&mut user, &username, &email, reason, at).map_err(StoreError::Lifecycle)?;
+        self.update(id, user)?;
+        self.compact()
+    }
+
+    // rewrites the log with only the live records
+    fn reopen(&self, path: &Path) -> io::Result<File> {
+        #[cfg(test)]
+        {
+            if self.fail_reopen {
+                return Err(io::Error::other("reopen failed"));
+            }
+        }
+        OpenOptions::new().append(true).open(path)
+    }
+
+    pub fn compact(&mut self) -> Result<(), StoreError> {
+        let temp = self.dir.join(TEMP_FILE);
+        let mut out = File::create(&temp)?;
+        let mut records = 0;
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&frame(&Op::Reserve(self.repo.last_id())));
+        records += 1;
+        for (id, user) in self.repo.iter() {
+            buf.extend_from_slice(&frame(&Op::Put(id, Box::new(user.clone()))));
+            records += 1;
+        }
+        out.write_all(&buf)?;
+        out.sync_all()?;
+        drop(out);
+        let path = self.dir.join(LOG_FILE);
+        fs::rename(&temp, &path)?;
+        // `log` now points at the unlinked old file, so until it is reopened nothing may be
+        // written through it
+        self.failed = true;
+        sync_dir(&self.dir)?;
+        self.log = self.reopen(&path)?;
+        self.records = records;
+        self.outdated.clear();
+        // the new log holds exactly what is in memory, whatever a failed write left behind
+        self.failed = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lifecycle::AccountState;
+    use crate::structs::{build_user, User};
+    use crate::user_record::encode_user;
+    use crate::user_store::{crc32, StoreError, StoreOptions, UserStore, LOG_FILE, TEMP_FILE};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    static DIRS: AtomicUsize = AtomicUsize::new(0);
+
+    // a fresh directory per test, removed again when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> TempDir {
+            let path = std::env::temp_dir().join(format!(
+                "rust-practice-store-{}-{}",
+                std::process::id(),
+                DIRS.fetch_add(1, Ordering::SeqCst)
+            ));
+            let _ = fs::remove_dir_all(&path);
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn user(name: &str) -> User {
+        build_user(format!("{}@example.com", name), name.to_string())
+    }
+
+    fn names(store: &UserStore) -> Vec<(u64, String)> {
+        store
+            .repository()
+            .iter()
+            .map(|(id, u)| (id, u.username.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_changes_survive_reopen() {
+        let dir = TempDir::new();
+        {
+            let mut store = UserStore::open(&dir.0).unwrap();
+            let ray = store.create(user("ray")).unwrap();
+            let sam = store.create(user("sam")).unwrap();
+            store.create(user("kim")).unwrap();
+            let mut changed = user("ray");
+            changed.sign_in_count = 9;
+            store.update(ray, changed).unwrap();
+            store.delete(sam).unwrap();
+        }
+        let store = UserStore::open(&dir.0).unwrap();
+        assert_eq!(
+            names(&store),
+            vec![(1, "ray".to_string()), (3, "kim".to_string())]
+        );
+        assert_eq!(store.get(1).unwrap().sign_in_count, 9);
+        assert_eq!(store.garbage(), 3);
+    }
+
+    #[test]
+    fn test_constraint_violations_are_not_logged() {
+        let dir = TempDir::new();
+        let mut store = UserStore::open(&dir.0).unwrap();
+        store.create(user("ray")).unwrap();
+        assert!(matches!(
+            store.create(user("ray")),
+            Err(StoreError::Repository(_))
+        ));
+        assert!(matches!(store.delete(42), Err(StoreError::Repository(_))));
+        drop(store);
+        let store = UserStore::open(&dir.0).unwrap();
+        assert_eq!(store.repository().len(), 1);
+        assert_eq!(store.garbage(), 0);
+    }
+
+    #[test]
+    fn test_recovers_from_torn_last_record() {
+        let dir = TempDir::new();
+        {
+            let mut store = UserStore::open(&dir.0).unwrap();
+            store.create(user("ray")).unwrap();
+            store.create(user("sam")).unwrap();
+        }
+        let path = dir.0.join(LOG_FILE);
+        let full = fs::read(&path).unwrap();
+        let first_len = 8 + u32::from_le_bytes([full[0], full[1], full[2], full[3]]) as usize;
+        // simulate a crash at every byte of sam's record
+        for cut in first_len..full.len() {
+            fs::write(&path, &full[..cut]).unwrap();
+            let mut store = UserStore::open(&dir.0).unwrap();
+            assert_eq!(
+                names(&store),
+                vec![(1, "ray".to_string())],
+                "cut at {}",
+                cut
+            );
+            assert_eq!(fs::metadata(&path).unwrap().len() as usize, first_len);
+            // and the log keeps working afterwards
+            store.create(user("kim")).unwrap();
+            drop(store);
+            let store = UserStore::open(&dir.0).unwrap();
+            assert_eq!(store.repository().len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_recovers_from_unflushed_payload() {
+        let dir = TempDir::new();
+        {
+            let mut store = UserStore::open(&dir.0).unwrap();
+            store.create(user("ray")).unwrap();
+            store.create(user("sam")).unwrap();
+        }
+        // the header made it to disk but the payload pages are still zero
+        let path = dir.0.join(LOG_FILE);
+        let mut bytes = fs::read(&path).unwrap();
+        let first_len = 8 + u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        for b in bytes[first_len + 8..].iter_mut() {
+            *b = 0;
+        }
+        fs::write(&path, &bytes).unwrap();
+        let store = UserStore::open(&dir.0).unwrap();
+        assert_eq!(names(&store), vec![(1, "ray".to_string())]);
+    }
+
+    #[test]
+    fn test_damage_before_the_tail_is_an_error() {
+        let dir = TempDir::new();
+        {
+            let mut store = UserStore::open(&dir.0).unwrap();
+            store.create(user("ray")).unwrap();
+            store.create(user("sam")).unwrap();
+        }
+        let path = dir.0.join(LOG_FILE);
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[12] ^= 0xff;
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            UserStore::open(&dir.0),
+            Err(StoreError::Corrupt { offset: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_damaged_length_is_an_error() {
+        let dir = TempDir::new();
+        {
+            let mut store = UserStore::open(&dir.0).unwrap();
+            store.create(user("ray")).unwrap();
+            store.create(user("sam")).unwrap();
+            store.create(user("kim")).unwrap();
+        }
+        let path = dir.0.join(LOG_FILE);
+        let mut bytes = fs::read(&path).unwrap();
+        let first_len = 8 + u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        // one flipped bit makes sam's record claim to run past the end of the file
+        bytes[first_len + 3] ^= 0x01;
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            UserStore::open(&dir.0),
+            Err(StoreError::Corrupt { offset, .. }) if offset == first_len as u64
+        ));
+        // and kim's record is still there to be rescued
+        assert_eq!(fs::read(&path).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_failed_write_stops_further_writes() {
+        let dir = TempDir::new();
+        let mut store = UserStore::open(&dir.0).unwrap();
+        store.create(user("ray")).unwrap();
+        let path = dir.0.join(LOG_FILE);
+        let size = fs::metadata(&path).unwrap().len();
+        // a handle that can neither write nor truncate
+        store.log = fs::File::open(&path).unwrap();
+        assert!(matches!(store.create(user("sam")), Err(StoreError::Io(_))));
+        assert_eq!(names(&store), vec![(1, "ray".to_string())]);
+        assert!(matches!(store.create(user("kim")), Err(StoreError::Failed)));
+        assert!(matches!(store.delete(1), Err(StoreError::Failed)));
+        assert_eq!(store.repository().len(), 1);
+        assert_eq!(fs::metadata(&path).unwrap().len(), size);
+        drop(store);
+        let mut store = UserStore::open(&dir.0).unwrap();
+        assert_eq!(names(&store), vec![(1, "ray".to_string())]);
+        store.create(user("sam")).unwrap();
+    }
+
+    #[test]
+    fn test_failed_compaction_stops_further_writes() {
+        let dir = TempDir::new();
+        let mut store = UserStore::open(&dir.0).unwrap();
+        store.create(user("ray")).unwrap();
+        store.fail_reopen = true;
+        assert!(matches!(store.compact(), Err(StoreError::Io(_))));
+        // the old log is gone, writing to it would lose the user
+        assert!(matches!(store.create(user("sam")), Err(StoreError::Failed)));
+        assert_eq!(names(&store), vec![(1, "ray".to_string())]);
+        // a compaction that gets through puts things right
+        store.fail_reopen = false;
+        store.compact().unwrap();
+        store.create(user("sam")).unwrap();
+        drop(store);
+        // the refused create had taken id 2, ids aren't handed out twice
+        let store = UserStore::open(&dir.0).unwrap();
+        assert_eq!(
+            names(&store),
+            vec![(1, "ray".to_string()), (3, "sam".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_compaction() {
+        let dir = TempDir::new();
+        let options = StoreOptions {
+            compact_min_garbage: 10,
+        };
+        {
+            let mut store = UserStore::open_with(&dir.0, options.clone()).unwrap();
+            let ray = store.create(user("ray")).unwrap();
+            let doomed = store.create(user("zed")).unwrap();
+            store.delete(doomed).unwrap();
+            for count in 0..8 {
+                let mut changed = user("ray");
+                changed.sign_in_count = count;
+                store.update(ray, changed).unwrap();
+            }
+            // 11 records with 1 live, so it compacted on the way
+            assert!(store.garbage() < 10);
+            let size = fs::metadata(dir.0.join(LOG_FILE)).unwrap().len();
+            store.compact().unwrap();
+            assert!(fs::metadata(dir.0.join(LOG_FILE)).unwrap().len() <= size);
+            assert_eq!(store.garbage(), 1);
+        }
+        let mut store = UserStore::open_with(&dir.0, options).unwrap();
+        assert_eq!(names(&store), vec![(1, "ray".to_string())]);
+        assert_eq!(store.get(1).unwrap().sign_in_count, 7);
+        // zed's id stays retired even though its records are gone
+        assert_eq!(store.create(user("kim")).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_anonymise_leaves_nothing_on_disk() {
+        let dir = TempDir::new();
+        let mut store = UserStore::open(&dir.0).unwrap();
+        let ray = store.create(user("ray")).unwrap();
+        let kim = store.create(user("kim")).unwrap();
+        let mut changed = store.get(ray).unwrap().clone();
+        changed.profile.display_name = Some("Ray Hall".to_string());
+        changed.password_hash = Some("$pbkdf2-sha256$secret".to_string());
+        store.update(ray, changed).unwrap();
+        let at = UNIX_EPOCH + Duration::from_secs(1_000);
+        store.anonymise(ray, b"key", "erasure request", at).unwrap();
+
+        let log = fs::read(dir.0.join(LOG_FILE)).unwrap();
+        let log = String::from_utf8_lossy(&log);
+        for secret in &["ray", "Ray Hall", "secret"] {
+            assert!(!log.contains(secret), "{:?} is still in the log", secret);
+        }
+        assert!(log.contains("kim@example.com"));
+        drop(store);
+        let store = UserStore::open(&dir.0).unwrap();
+        let erased = store.get(ray).unwrap();
+        assert!(erased.username.starts_with("anon-"));
+        assert_eq!(erased.lifecycle.state(), AccountState::Deleted);
+        assert_eq!(store.get(kim).unwrap().username, "kim");
+        assert!(matches!(
+            UserStore::open(&dir.0)
+                .unwrap()
+                .anonymise(9, b"key", "", at),
+            Err(StoreError::Repository(_))
+        ));
+    }
+
+    #[test]
+    fn test_crash_during_compaction() {
+        let dir = TempDir::new();
+        {
+            let mut store = UserStore::open(&dir.0).unwrap();
+            store.create(user("ray")).unwrap();
+        }
+        // a half written temp file from a compaction that never reached its rename
+        fs::write(dir.0.join(TEMP_FILE), b"\x05\x00\x00").unwrap();
+        let store = UserStore::open(&dir.0).unwrap();
+        assert_eq!(names(&store), vec![(1, "ray".to_string())]);
+        assert!(!dir.0.join(TEMP_FILE).exists());
+    }
+
+    // a log as an older version wrote it, one put per record text
+    fn old_log(records: &[(u64, &str)]) -> Vec<u8> {
+        let mut log = Vec::new();
+        for &(id, record) in records {
+            let payload = format!("op=put\nid={}\n{}", id, record).into_bytes();
+            log.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            log.extend_from_slice(&crc32(&payload).to_le_bytes());
+            log.extend_from_slice(&payload);
+        }
+        log
+    }
+
+    #[test]
+    fn test_records_clashing_under_newer_rules_still_load() {
+        let dir = TempDir::new();
+        fs::create_dir_all(&dir.0).unwrap();
+        let record = |name: &str| encode_user(&user(name));
+        let log = old_log(&[
+            (1, &record("Ray")),
+            (2, &record("ray")),
+            (3, &record("modern")),
+            (4, &record("rnodern")),
+        ]);
+        fs::write(dir.0.join(LOG_FILE), log).unwrap();
+        let mut store = UserStore::open(&dir.0).unwrap();
+        assert_eq!(store.repository().len(), 4);
+        let conflicting: Vec<u64> = store.repository().conflicts().map(|(id, _)| id).collect();
+        assert_eq!(conflicting, vec![2, 4]);
+        // resolved by renaming, which is logged like any other change
+        store.update(2, user("raymond")).unwrap();
+        drop(store);
+        let store = UserStore::open(&dir.0).unwrap();
+        let conflicting: Vec<u64> = store.repository().conflicts().map(|(id, _)| id).collect();
+        assert_eq!(conflicting, vec![4]);
+    }
+
+    #[test]
+    fn test_old_records_upgrade() {
+        let dir = TempDir::new();
+        fs::create_dir_all(&dir.0).unwrap();
+        // the fixtures are all Ray, the others get their own names
+        let renamed = |text: &str, name: &str| {
+            text.replace("Ray", name)
+                .replace("ray@", &format!("{}@", name))
+        };
+        let sam = renamed(include_str!("fixtures/user_v1.txt"), "sam");
+        let kim = renamed(include_str!("fixtures/user_v2.txt"), "kim");
+        let log = old_log(&[
+            (1, include_str!("fixtures/user_v1.txt")),
+            (2, &sam),
+            (3, &kim),
+        ]);
+        fs::write(dir.0.join(LOG_FILE), log).unwrap();
+        {
+            // read lazily, left as they are on disk
+            let mut store = UserStore::open(&dir.0).unwrap();
+            assert_eq!(store.outdated(), 2);
+            assert_eq!(store.get(2).unwrap().roles, ["editor", "viewer"]);
+            // a write puts the record in the current format
+            let mut ray = store.get(1).unwrap().clone();
+            ray.sign_in_count += 1;
+            store.update(1, ray).unwrap();
+            assert_eq!(store.outdated(), 1);
+        }
+        let mut store = UserStore::open(&dir.0).unwrap();
+        assert_eq!(store.outdated(), 1);
+        let before: Vec<User> = store.repository().iter().map(|(_, u)| u.clone()).collect();
+        assert_eq!(store.upgrade().unwrap(), 1);
+        assert_eq!(store.upgrade().unwrap(), 0);
+        drop(store);
+        let store = UserStore::open(&dir.0).unwrap();
+        assert_eq!(store.outdated(), 0);
+        let after: Vec<User> = store.repository().iter().map(|(_, u)| u.clone()).collect();
+        assert_eq!(after, before);
+        assert_eq!(after[0].sign_in_count, 13);
+    }
+}
@@ -0,0 +1,503 @@
+// Fallible construction of `User` with username and email validation.
+// The email rules are a practical subset of RFC 5322: a dot-atom local part and a domain
+// of hostname labels, where labels may be internationalised (IDN) and are checked in their
+// punycode (RFC 3492) form.
+use crate::structs::{build_user, User};
+use std::fmt;
+
+pub const USERNAME_MIN_LEN: usize = 3;
+pub const USERNAME_MAX_LEN: usize = 32;
+pub const EMAIL_MAX_LEN: usize = 254;
+pub const LOCAL_PART_MAX_LEN: usize = 64;
+pub const DOMAIN_MAX_LEN: usize = 253;
+pub const LABEL_MAX_LEN: usize = 63;
+
+// compared case-insensitively
+const RESERVED_USERNAMES: [&str; 18] = [
+    "abuse",
+    "admin",
+    "administrator",
+    "api",
+    "help",
+    "hostmaster",
+    "info",
+    "mail",
+    "no-reply",
+    "noreply",
+    "null",
+    "postmaster",
+    "root",
+    "security",
+    "support",
+    "system",
+    "webmaster",
+    "www",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UserError {
+    EmptyUsername,
+    UsernameTooShort(usize),
+    UsernameTooLong(usize),
+    InvalidUsernameCharacter(char),
+    // `.`, `-` and `_` may not start or end a username, or follow each other
+    MisplacedUsernameSeparator,
+    ReservedUsername(String),
+    EmptyEmail,
+    EmailTooLong(usize),
+    MissingAtSign,
+    EmptyLocalPart,
+    LocalPartTooLong(usize),
+    InvalidLocalPartCharacter(char),
+    // a dot at the start or end of the local part, or two in a row
+    MisplacedLocalPartDot,
+    EmptyDomain,
+    DomainTooLong(usize),
+    // the domain needs at least a name and a top-level label, e.g. `example.com`
+    MissingTopLevelDomain,
+    EmptyDomainLabel,
+    DomainLabelTooLong(String),
+    InvalidDomainCharacter(char),
+    // labels may not start or end with `-`
+    MisplacedDomainHyphen(String),
+    NumericTopLevelDomain(String),
+}
+
+impl fmt::Display for UserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UserError::EmptyUsername => write!(f, "username is empty"),
+            UserError::UsernameTooShort(len) => write!(
+                f,
+                "username has {} characters, at least {} are required",
+                len, USERNAME_MIN_LEN
+            ),
+            UserError::UsernameTooLong(len) => write!(
+                f,
+                "username has {} characters, at most {} are allowed",
+                len, USERNAME_MAX_LEN
+            ),
+            UserError::InvalidUsernameCharacter(c) => {
+                write!(f, "username may not contain {:?}", c)
+            }
+            UserError::MisplacedUsernameSeparator => write!(
+                f,
+                "username may not start or end with a separator or repeat one"
+            ),
+            UserError::ReservedUsername(name) => write!(f, "username {:?} is reserved", name),
+            UserError::EmptyEmail => write!(f, "email is empty"),
+            UserError::EmailTooLong(len) => write!(
+                f,
+                "email is {} bytes long, at most {} are allowed",
+                len, EMAIL_MAX_LEN
+            ),
+            UserError::MissingAtSign => write!(f, "email has no @"),
+            UserError::EmptyLocalPart => write!(f, "email has nothing before the @"),
+            UserError::LocalPartTooLong(len) => write!(
+                f,
+                "email local part is {} bytes long, at most {} are allowed",
+                len, LOCAL_PART_MAX_LEN
+            ),
+            UserError::InvalidLocalPartCharacter(c) => {
+                write!(f, "email local part may not contain {:?}", c)
+            }
+            UserError::MisplacedLocalPartDot => write!(
+                f,
+                "email local part may not start or end with a dot or have two in a row"
+            ),
+            UserError::EmptyDomain => write!(f, "email has nothing after the @"),
+            UserError::DomainTooLong(len) => write!(
+                f,
+                "email domain is {} bytes long, at most {} are allowed",
+                len, DOMAIN_MAX_LEN
+            ),
+            UserError::MissingTopLevelDomain => write!(f, "email domain has no top-level domain"),
+            UserError::EmptyDomainLabel => write!(f, "email domain has an empty label"),
+            UserError::DomainLabelTooLong(label) => write!(
+                f,
+                "domain label {:?} is longer than {} bytes",
+                label, LABEL_MAX_LEN
+            ),
+            UserError::InvalidDomainCharacter(c) => {
+                write!(f, "email domain may not contain {:?}", c)
+            }
+            UserError::MisplacedDomainHyphen(label) => {
+                write!(f, "domain label {:?} starts or ends with a hyphen", label)
+            }
+            UserError::NumericTopLevelDomain(label) => {
+                write!(f, "top-level domain {:?} is all digits", label)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UserError {}
+
+fn is_separator(c: char) -> bool {
+    c == '.' || c == '-' || c == '_'
+}
+
+// ASCII letters, digits and single `.`, `-` or `_` between them
+pub fn validate_username(username: &str) -> Result<(), UserError> {
+    if username.is_empty() {
+        return Err(UserError::EmptyUsername);
+    }
+    if let Some(c) = username
+        .chars()
+        .find(|&c| !c.is_ascii_alphanumeric() && !is_separator(c))
+    {
+        return Err(UserError::InvalidUsernameCharacter(c));
+    }
+    // only ASCII is left, so bytes and characters agree
+    let len = username.len();
+    if len < USERNAME_MIN_LEN {
+        return Err(UserError::UsernameTooShort(len));
+    }
+    if len > USERNAME_MAX_LEN {
+        return Err(UserError::UsernameTooLong(len));
+    }
+    let bytes = username.as_bytes();
+    if is_separator(bytes[0] as char)
+        || is_separator(bytes[len - 1] as char)
+        || bytes
+            .windows(2)
+            .any(|pair| is_separator(pair[0] as char) && is_separator(pair[1] as char))
+    {
+        return Err(UserError::MisplacedUsernameSeparator);
+    }
+    if RESERVED_USERNAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(username))
+    {
+        return Err(UserError::ReservedUsername(username.to_string()));
+    }
+    Ok(())
+}
+
+// RFC 5322 atext
+fn is_atext(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~-".contains(c)
+}
+
+fn validate_local_part(local: &str) -> Result<(), UserError> {
+    if local.is_empty() {
+        return Err(UserError::EmptyLocalPart);
+    }
+    if local.len() > LOCAL_PART_MAX_LEN {
+        return Err(UserError::LocalPartTooLong(local.len()));
+    }
+    if let Some(c) = local.chars().find(|&c| c != '.' && !is_atext(c)) {
+        return Err(UserError::InvalidLocalPartCharacter(c));
+    }
+    if local.starts_with('.') || local.ends_with('.') || local.contains("..") {
+        return Err(UserError::MisplacedLocalPartDot);
+    }
+    Ok(())
+}
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+
+fn adapt(delta: u32, num_points: u32, first_time: bool) -> u32 {
+    let mut delta = if first_time { delta / DAMP } else { delta / 2 };
+    delta += delta / num_points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
+
+fn encode_digit(d: u32) -> char {
+    if d < 26 {
+        (b'a' + d as u8) as char
+    } else {
+        (b'0' + (d - 26) as u8) as char
+    }
+}
+
+// RFC 3492 punycode, without the `xn--` prefix; None if the label is absurdly long
+pub fn punycode_encode(label: &str) -> Option<String> {
+    let input: Vec<u32> = label.chars().map(|c| c as u32).collect();
+    let mut output: String = label.chars().filter(char::is_ascii).collect();
+    let basic = output.len() as u32;
+    let mut handled = basic;
+    if basic > 0 {
+        output.push('-');
+    }
+    let mut n = INITIAL_N;
+    let mut delta: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    while (handled as usize) < input.len() {
+        let m = *input.iter().filter(|&&c| c >= n).min()?;
+        delta = delta.checked_add((m - n).checked_mul(handled + 1)?)?;
+        n = m;
+        for &c in input.iter() {
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = if k <= bias {
+                        T_MIN
+                    } else if k >= bias + T_MAX {
+                        T_MAX
+                    } else {
+                        k - bias
+                    };
+                    if q < t {
+                        break;
+                    }
+                    output.push(encode_digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(encode_digit(q));
+                bias = adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    Some(output)
+}
+
+// The ASCII (A-label) form of a possibly internationalised domain,
+// e.g. "bücher.example" becomes "xn--bcher-kva.example".
+pub fn to_ascii_domain(domain: &str) -> Result<String, UserError> {
+    if domain.is_empty() {
+        return Err(UserError::EmptyDomain);
+    }
+    let mut labels = Vec::new();
+    for label in domain.split('.') {
+        if label.is_empty() {
+            return Err(UserError::EmptyDomainLabel);
+        }
+        if let Some(c) = label.chars().find(|&c| !(c.is_alphanumeric() || c == '-')) {
+            return Err(UserError::InvalidDomainCharacter(c));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(UserError::MisplacedDomainHyphen(label.to_string()));
+        }
+        let lower = label.to_lowercase();
+        let ascii = if lower.is_ascii() {
+            lower
+        } else {
+            match punycode_encode(&lower) {
+                Some(encoded) => format!("xn--{}", encoded),
+                None => return Err(UserError::DomainLabelTooLong(label.to_string())),
+            }
+        };
+        if ascii.len() > LABEL_MAX_LEN {
+            return Err(UserError::DomainLabelTooLong(label.to_string()));
+        }
+        labels.push(ascii);
+    }
+    let ascii = labels.join(".");
+    if ascii.len() > DOMAIN_MAX_LEN {
+        return Err(UserError::DomainTooLong(ascii.len()));
+    }
+    Ok(ascii)
+}
+
+pub fn validate_email(email: &str) -> Result<(), UserError> {
+    if email.is_empty() {
+        return Err(UserError::EmptyEmail);
+    }
+    // the local part can't contain @ unquoted, so the last one separates it from the domain
+    let at = email.rfind('@').ok_or(UserError::MissingAtSign)?;
+    let (local, domain) = (&email[..at], &email[at + 1..]);
+    validate_local_part(local)?;
+    let ascii_domain = to_ascii_domain(domain)?;
+    let top_level = ascii_domain.rsplit('.').next().unwrap_or("");
+    if !ascii_domain.contains('.') {
+        return Err(UserError::MissingTopLevelDomain);
+    }
+    if top_level.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(UserError::NumericTopLevelDomain(top_level.to_string()));
+    }
+    let len = local.len() + 1 + ascii_domain.len();
+    if len > EMAIL_MAX_LEN {
+        return Err(UserError::EmailTooLong(len));
+    }
+    Ok(())
+}
+
+// like `build_user`, but refuses usernames and emails that don't validate
+pub fn try_build_user(email: String, username: String) -> Result<User, UserError> {
+    validate_username(&username)?;
+    validate_email(&email)?;
+    Ok(build_user(email, username))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::user_validation::{
+        punycode_encode, to_ascii_domain, try_build_user, validate_email, validate_username,
+        UserError,
+    };
+
+    #[test]
+    fn test_try_build_user() {
+        let ray = try_build_user("rayyounghong@gmail.com".to_string(), "Ray".to_string()).unwrap();
+        assert_eq!(ray.username, "Ray");
+        assert_eq!(ray.email, "rayyounghong@gmail.com");
+        assert_eq!(
+            try_build_user("rayyounghong@gmail.com".to_string(), "".to_string()),
+            Err(UserError::EmptyUsername)
+        );
+        assert_eq!(
+            try_build_user("not-an-email".to_string(), "Ray".to_string()),
+            Err(UserError::MissingAtSign)
+        );
+    }
+
+    #[test]
+    fn test_valid_usernames() {
+        for name in [
+            "ray",
+            "Ray_Young",
+            "r.y-h",
+            "abc",
+            "a1b2c3",
+            &"x".repeat(32),
+        ]
+        .iter()
+        {
+            assert_eq!(validate_username(name), Ok(()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_invalid_usernames() {
+        let long = "x".repeat(33);
+        let cases = vec![
+            ("", UserError::EmptyUsername),
+            ("ab", UserError::UsernameTooShort(2)),
+            (&long as &str, UserError::UsernameTooLong(33)),
+            ("ray young", UserError::InvalidUsernameCharacter(' ')),
+            ("rаy", UserError::InvalidUsernameCharacter('а')),
+            ("ray!", UserError::InvalidUsernameCharacter('!')),
+            ("_ray", UserError::MisplacedUsernameSeparator),
+            ("ray.", UserError::MisplacedUsernameSeparator),
+            ("ray._young", UserError::MisplacedUsernameSeparator),
+            ("Admin", UserError::ReservedUsername("Admin".to_string())),
+            (
+                "no-reply",
+                UserError::ReservedUsername("no-reply".to_string()),
+            ),
+        ];
+        for (name, error) in cases {
+            assert_eq!(validate_username(name), Err(error), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_valid_emails() {
+        let cases = [
+            "rayyounghong@gmail.com",
+            "first.last@example.org",
+            "user+tag@sub.example.co.uk",
+            "o'brien@example.ie",
+            "x@a-b.io",
+            "!#$%&'*+/=?^_`{|}~-@example.com",
+            "ray@bücher.example",
+            "ray@例え.テスト",
+        ];
+        for email in cases.iter() {
+            assert_eq!(validate_email(email), Ok(()), "{}", email);
+        }
+    }
+
+    #[test]
+    fn test_invalid_emails() {
+        let long_label = "a".repeat(64);
+        let long_local = format!("{}@example.com", "a".repeat(65));
+        let long_domain = format!("ray@{}.com", vec!["a".repeat(60); 5].join("."));
+        let cases = vec![
+            ("", UserError::EmptyEmail),
+            ("ray.example.com", UserError::MissingAtSign),
+            ("@example.com", UserError::EmptyLocalPart),
+            (&long_local as &str, UserError::LocalPartTooLong(65)),
+            (
+                "ray young@example.com",
+                UserError::InvalidLocalPartCharacter(' '),
+            ),
+            (
+                "ray@you@example.com",
+                UserError::InvalidLocalPartCharacter('@'),
+            ),
+            (".ray@example.com", UserError::MisplacedLocalPartDot),
+            ("ray..young@example.com", UserError::MisplacedLocalPartDot),
+            ("ray@", UserError::EmptyDomain),
+            ("ray@localhost", UserError::MissingTopLevelDomain),
+            ("ray@example..com", UserError::EmptyDomainLabel),
+            ("ray@example.com.", UserError::EmptyDomainLabel),
+            ("ray@exa_mple.com", UserError::InvalidDomainCharacter('_')),
+            (
+                "ray@-example.com",
+                UserError::MisplacedDomainHyphen("-example".to_string()),
+            ),
+            (
+                "ray@127.0.0.1",
+                UserError::NumericTopLevelDomain("1".to_string()),
+            ),
+            (&long_domain as &str, UserError::DomainTooLong(308)),
+        ];
+        for (email, error) in cases {
+            assert_eq!(validate_email(email), Err(error), "{}", email);
+        }
+        let email = format!("ray@{}.com", long_label);
+        assert_eq!(
+            validate_email(&email),
+            Err(UserError::DomainLabelTooLong(long_label))
+        );
+    }
+
+    #[test]
+    fn test_punycode() {
+        // samples checked against other implementations
+        assert_eq!(punycode_encode("bücher"), Some("bcher-kva".to_string()));
+        assert_eq!(punycode_encode("münchen"), Some("mnchen-3ya".to_string()));
+        assert_eq!(punycode_encode("mañana"), Some("maana-pta".to_string()));
+        assert_eq!(punycode_encode("例え"), Some("r8jz45g".to_string()));
+        assert_eq!(punycode_encode("ü"), Some("tda".to_string()));
+        assert_eq!(punycode_encode("plain"), Some("plain-".to_string()));
+    }
+
+    #[test]
+    fn test_idn_label_length_uses_ascii_form() {
+        assert_eq!(
+            to_ascii_domain("Bücher.Example"),
+            Ok("xn--bcher-kva.example".to_string())
+        );
+        // 60 characters is fine as Unicode, but too long once encoded
+        let label = "ü".repeat(60);
+        assert_eq!(
+            to_ascii_domain(&format!("{}.com", label)),
+            Err(UserError::DomainLabelTooLong(label))
+        );
+    }
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            UserError::UsernameTooShort(2).to_string(),
+            "username has 2 characters, at least 3 are required"
+        );
+        assert_eq!(
+            UserError::ReservedUsername("root".to_string()).to_string(),
+            "username \"root\" is reserved"
+        );
+    }
+}
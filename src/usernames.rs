@@ -0,0 +1,199 @@
+// Comparing usernames the way people read them.
+//
+// `normalize_username` gives the form two usernames are compared in: NFKC, then case folded,
+// then NFKC again (Unicode's NFKC_Casefold), so "Ray", "ray" and "ＲＡＹ" are all "ray".
+// `skeleton` goes further and replaces characters that look alike with one prototype, after
+// UTS #39, so "rаy" with a Cyrillic а and "ray" get the same skeleton.
+//
+// This is a partial check. Neither is built from the full Unicode data: the NFKC mappings in
+// unicode_tables.rs and the look-alikes in `prototype` are hand-picked subsets, not
+// UnicodeData.txt or UTS #39's confusables.txt. They cover the Latin, Greek, Cyrillic and
+// Armenian letters and the compatibility forms usernames are realistically made of; anything
+// else passes through unchanged, so look-alikes from other scripts are not caught. Combining
+// marks are only composed when they directly follow their base letter, in the order NFC
+// would have them.
+use crate::unicode_tables::{COMPATIBILITY_RUNS, COMPATIBILITY_STRINGS, COMPOSITIONS};
+use std::convert::TryFrom;
+
+// appends `c`, composing it with the character before if they make one
+fn push_composed(out: &mut String, c: char) {
+    if let Some(last) = out.chars().next_back() {
+        if let Ok(i) =
+            COMPOSITIONS.binary_search_by(|&(base, mark, _)| (base, mark).cmp(&(last, c)))
+        {
+            out.pop();
+            out.push(COMPOSITIONS[i].2);
+            return;
+        }
+    }
+    out.push(c);
+}
+
+fn push_compatibility(out: &mut String, c: char) {
+    if let Ok(i) = COMPATIBILITY_STRINGS.binary_search_by(|&(from, _)| from.cmp(&c)) {
+        for mapped in COMPATIBILITY_STRINGS[i].1.chars() {
+            push_composed(out, mapped);
+        }
+        return;
+    }
+    let cp = c as u32;
+    let run = COMPATIBILITY_RUNS
+        .binary_search_by(|&(first, last, _)| {
+            if last < cp {
+                std::cmp::Ordering::Less
+            } else if first > cp {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .ok()
+        .map(|i| COMPATIBILITY_RUNS[i]);
+    let mapped = run
+        .and_then(|(first, _, target)| char::try_from(target + (cp - first)).ok())
+        .unwrap_or(c);
+    push_composed(out, mapped);
+}
+
+pub fn nfkc(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        push_compatibility(&mut out, c);
+    }
+    out
+}
+
+// full case folding; `to_lowercase` already does it for all but a few
+pub fn case_fold(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            'ß' | 'ẞ' => out.push_str("ss"),
+            'ς' => out.push('σ'),
+            _ => out.extend(c.to_lowercase()),
+        }
+    }
+    out
+}
+
+pub fn normalize_username(username: &str) -> String {
+    nfkc(&case_fold(&nfkc(username)))
+}
+
+// the character a look-alike stands for, `Some("")` for invisible ones
+fn prototype(c: char) -> Option<&'static str> {
+    let prototype = match c {
+        // invisible: soft hyphen, zero width spaces and joiners, word joiner, BOM
+        '\u{ad}' | '\u{34f}' | '\u{200b}'..='\u{200f}' | '\u{2060}'..='\u{2064}' | '\u{feff}' => "",
+        '0' | 'О' | 'о' | 'Ο' | 'ο' | 'օ' => "o",
+        '1' | 'I' | '|' | 'ǀ' | 'Ӏ' | 'ӏ' | 'Ι' | 'І' => "l",
+        'm' => "rn",
+        'w' | 'ԝ' => "vv",
+        'А' | 'а' | 'Α' | 'α' | 'ɑ' => "a",
+        'В' | 'Β' => "b",
+        'С' | 'с' | 'ϲ' => "c",
+        'ԁ' => "d",
+        'Е' | 'е' | 'Ε' => "e",
+        'ɡ' => "g",
+        'Н' | 'һ' | 'Η' | 'հ' => "h",
+        'і' | 'ı' | 'ɩ' => "i",
+        'Ј' | 'ј' | 'ϳ' => "j",
+        'К' | 'Κ' => "k",
+        'Ν' | 'ո' => "n",
+        'Р' | 'р' | 'Ρ' | 'ρ' => "p",
+        'ԛ' | 'զ' => "q",
+        'Ѕ' | 'ѕ' => "s",
+        'Т' | 'Τ' => "t",
+        'υ' | 'ս' => "u",
+        'ν' => "v",
+        'Х' | 'х' | 'Χ' | 'χ' => "x",
+        'У' | 'у' | 'Υ' => "y",
+        'Ζ' => "z",
+        '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' => "-",
+        _ => return None,
+    };
+    Some(prototype)
+}
+
+fn map_prototypes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match prototype(c) {
+            Some(p) => out.push_str(p),
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+// Two usernames with the same skeleton look alike. Look-alikes are mapped before case
+// folding as well as after, so a capital I still reads as l.
+pub fn skeleton(username: &str) -> String {
+    map_prototypes(&case_fold(&map_prototypes(&nfkc(username))))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::usernames::{case_fold, nfkc, normalize_username, skeleton};
+
+    #[test]
+    fn test_nfkc() {
+        let cases = [
+            ("ray", "ray"),
+            ("ＲＡＹ１２", "RAY12"),
+            ("ﬁle", "file"),
+            ("x²", "x2"),
+            ("①", "1"),
+            ("ℌ", "H"),
+            ("𝐑𝐚𝐲", "Ray"),
+            ("Ⅻ", "XII"),
+            ("a\u{3000}b", "a b"),
+            // decomposed accents are composed
+            ("Rene\u{301}", "René"),
+            ("e\u{302}\u{301}", "ế"),
+            ("René", "René"),
+            // a fullwidth letter followed by a combining mark composes too
+            ("Ｅ\u{301}", "É"),
+        ];
+        for (input, expected) in cases.iter() {
+            assert_eq!(nfkc(input), *expected, "nfkc({:?})", input);
+        }
+    }
+
+    #[test]
+    fn test_case_fold() {
+        assert_eq!(case_fold("Straße"), "strasse");
+        assert_eq!(case_fold("ΣΊΣΥΦΟΣ"), "σίσυφοσ");
+        assert_eq!(case_fold("σίσυφος"), "σίσυφοσ");
+        assert_eq!(case_fold("ÀÉÎ"), "àéî");
+    }
+
+    #[test]
+    fn test_normalize_username() {
+        for name in ["Ray", "ray", "RAY", "ＲＡＹ", "𝐑𝐚𝐲"].iter() {
+            assert_eq!(normalize_username(name), "ray");
+        }
+        assert_eq!(
+            normalize_username("Rene\u{301}"),
+            normalize_username("RENÉ")
+        );
+        assert_eq!(normalize_username("Strasse"), normalize_username("STRAẞE"));
+        // look-alikes are still different names
+        assert_ne!(normalize_username("R\u{430}y"), "ray");
+    }
+
+    #[test]
+    fn test_skeleton() {
+        let ray = skeleton("ray");
+        for name in ["Ray", "R\u{430}y", "r\u{430}\u{443}", "r\u{200b}ay"].iter() {
+            assert_eq!(skeleton(name), ray, "skeleton({:?})", name);
+        }
+        assert_eq!(skeleton("paypal"), skeleton("р\u{430}ур\u{430}l"));
+        assert_eq!(skeleton("modern"), skeleton("rnodern"));
+        assert_eq!(skeleton("Iran"), skeleton("lran"));
+        assert_eq!(skeleton("g00gle"), skeleton("google"));
+        assert_eq!(skeleton("user-1"), skeleton("user\u{2013}l"));
+        assert_ne!(skeleton("ray"), skeleton("roy"));
+        assert_ne!(skeleton("rené"), skeleton("rene"));
+    }
+}
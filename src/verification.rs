@@ -0,0 +1,549 @@
+// Email verification and password reset by emailed token.
+//
+// A token is `<user id>.<purpose>.<expiry>.<nonce>.<mac>`: the expiry in unix seconds, a random
+// nonce so no two tokens are alike, and an HMAC-SHA256 over everything before it under a server
+// secret, both in url-safe base64. Nothing has to be stored to check a token; only the ones
+// already redeemed are remembered, until they would have expired anyway.
+//
+// New users start out pending. `send_verification` mails them a token and `verify_email`
+// takes it back and activates the account. Password resets are only for accounts that could
+// sign in with the new password, so not for pending, suspended, deactivated or deleted ones.
+use crate::base64;
+use crate::clock::{unix_secs, Clock};
+use crate::lifecycle::{AccountState, LifecycleError};
+use crate::password::{PasswordError, PasswordParams};
+use crate::random::random_bytes;
+use crate::sha256::{constant_time_eq, hmac_sha256, DIGEST_LEN};
+use crate::structs::User;
+use crate::user_repository::{RepositoryError, UserId, UserRepository};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const NONCE_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Purpose {
+    VerifyEmail,
+    ResetPassword,
+}
+
+impl Purpose {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Purpose::VerifyEmail => "verify-email",
+            Purpose::ResetPassword => "reset-password",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Purpose> {
+        match name {
+            "verify-email" => Some(Purpose::VerifyEmail),
+            "reset-password" => Some(Purpose::ResetPassword),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenPolicy {
+    pub verify_ttl: Duration,
+    pub reset_ttl: Duration,
+}
+
+impl Default for TokenPolicy {
+    fn default() -> TokenPolicy {
+        TokenPolicy {
+            verify_ttl: Duration::from_secs(48 * 60 * 60),
+            reset_ttl: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+// where emails go; a real one would hand them to an SMTP relay
+pub trait Outbox {
+    fn send(&mut self, email: Email) -> io::Result<()>;
+}
+
+// keeps every email, for tests and local development
+#[derive(Debug, Default)]
+pub struct MemoryOutbox {
+    pub sent: Vec<Email>,
+}
+
+impl Outbox for MemoryOutbox {
+    fn send(&mut self, email: Email) -> io::Result<()> {
+        self.sent.push(email);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum VerificationError {
+    Malformed,
+    // not signed with our key, or tampered with
+    BadSignature,
+    // a genuine token, but issued for something else
+    WrongPurpose(Purpose),
+    Expired,
+    AlreadyUsed,
+    // verification only makes sense while the account is pending
+    NotPending(AccountState),
+    // the account can't sign in, so there is no password to reset
+    NotActive(AccountState),
+    Random(io::Error),
+    Send(io::Error),
+    Repository(RepositoryError),
+    Lifecycle(LifecycleError),
+    Password(PasswordError),
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerificationError::Malformed => write!(f, "malformed token"),
+            VerificationError::BadSignature => write!(f, "token signature does not match"),
+            VerificationError::WrongPurpose(purpose) => {
+                write!(f, "token was issued for {}", purpose.name())
+            }
+            VerificationError::Expired => write!(f, "token has expired"),
+            VerificationError::AlreadyUsed => write!(f, "token has already been used"),
+            VerificationError::NotPending(state) => write!(f, "account is {}", state),
+            VerificationError::NotActive(state) => write!(f, "account is {}", state),
+            VerificationError::Random(e) => write!(f, "could not generate a token: {}", e),
+            VerificationError::Send(e) => write!(f, "could not send email: {}", e),
+            VerificationError::Repository(e) => write!(f, "{}", e),
+            VerificationError::Lifecycle(e) => write!(f, "{}", e),
+            VerificationError::Password(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+// what a token says once its signature has been checked
+struct Claims {
+    user: UserId,
+    expires: SystemTime,
+    mac: [u8; DIGEST_LEN],
+}
+
+pub struct Verifier<C: Clock> {
+    clock: C,
+    key: Vec<u8>,
+    policy: TokenPolicy,
+    // redeemed tokens by MAC, with their expiry
+    spent: HashMap<[u8; DIGEST_LEN], SystemTime>,
+}
+
+impl<C: Clock> Verifier<C> {
+    // `key` is the server secret; anyone who has it can mint tokens
+    pub fn new(clock: C, key: &[u8], policy: TokenPolicy) -> Verifier<C> {
+        Verifier {
+            clock,
+            key: key.to_vec(),
+            policy,
+            spent: HashMap::new(),
+        }
+    }
+
+    fn ttl(&self, purpose: Purpose) -> Duration {
+        match purpose {
+            Purpose::VerifyEmail => self.policy.verify_ttl,
+            Purpose::ResetPassword => self.policy.reset_ttl,
+        }
+    }
+
+    pub fn issue(&self, user: UserId, purpose: Purpose) -> Result<String, VerificationError> {
+        let nonce = random_bytes(NONCE_LEN).map_err(VerificationError::Random)?;
+        let expires = unix_secs(self.clock.now() + self.ttl(purpose));
+        let payload = format!(
+            "{}.{}.{}.{}",
+            user,
+            purpose.name(),
+            expires,
+            base64::encode_url_safe(&nonce)
+        );
+        let mac = hmac_sha256(&self.key, payload.as_bytes());
+        Ok(format!("{}.{}", payload, base64::encode_url_safe(&mac)))
+    }
+
+    // everything but the single-use check, which would need `&mut self`
+    fn check(&self, token: &str, purpose: Purpose) -> Result<Claims, VerificationError> {
+        let dot = token.rfind('.').ok_or(VerificationError::Malformed)?;
+        let (payload, mac) = (&token[..dot], &token[dot + 1..]);
+        let mac = base64::decode_url_safe(mac).ok_or(VerificationError::Malformed)?;
+        let expected = hmac_sha256(&self.key, payload.as_bytes());
+        if !constant_time_eq(&mac, &expected) {
+            return Err(VerificationError::BadSignature);
+        }
+        // signed by us, so the rest was written by `issue`
+        let parts: Vec<&str> = payload.split('.').collect();
+        if parts.len() != 4 {
+            return Err(VerificationError::Malformed);
+        }
+        let user = parts[0].parse().map_err(|_| VerificationError::Malformed)?;
+        let issued_for = Purpose::from_name(parts[1]).ok_or(VerificationError::Malformed)?;
+        let expires: u64 = parts[2].parse().map_err(|_| VerificationError::Malformed)?;
+        let expires = UNIX_EPOCH + Duration::from_secs(expires);
+        if issued_for != purpose {
+            return Err(VerificationError::WrongPurpose(issued_for));
+        }
+        if self.clock.now() >= expires {
+            return Err(VerificationError::Expired);
+        }
+        if self.spent.contains_key(&expected) {
+            return Err(VerificationError::AlreadyUsed);
+        }
+        Ok(Claims {
+            user,
+            expires,
+            mac: expected,
+        })
+    }
+
+    fn spend(&mut self, claims: &Claims) {
+        let now = self.clock.now();
+        self.spent.retain(|_, expires| *expires > now);
+        self.spent.insert(claims.mac, claims.expires);
+    }
+
+    // checks a token and uses it up, returning the user it was issued to
+    pub fn redeem(&mut self, token: &str, purpose: Purpose) -> Result<UserId, VerificationError> {
+        let claims = self.check(token, purpose)?;
+        self.spend(&claims);
+        Ok(claims.user)
+    }
+
+    pub fn send_verification<O: Outbox>(
+        &self,
+        id: UserId,
+        user: &User,
+        outbox: &mut O,
+    ) -> Result<(), VerificationError> {
+        let token = self.issue(id, Purpose::VerifyEmail)?;
+        outbox
+            .send(Email {
+                to: user.email.clone(),
+                subject: "Confirm your email address".to_string(),
+                body: format!(
+                    "Hi {},\n\nplease confirm your email address with this code:\n\n{}\n",
+                    user.username, token
+                ),
+            })
+            .map_err(VerificationError::Send)
+    }
+
+    // Activates the account the token was issued for. The token is only used up once the
+    // account has actually changed.
+    pub fn verify_email(
+        &mut self,
+        token: &str,
+        repository: &mut UserRepository,
+    ) -> Result<UserId, VerificationError> {
+        let claims = self.check(token, Purpose::VerifyEmail)?;
+        let mut user = repository
+            .get(claims.user)
+            .ok_or(VerificationError::Repository(RepositoryError::NotFound(
+                claims.user,
+            )))?
+            .clone();
+        let state = user.lifecycle.state();
+        if state != AccountState::PendingVerification {
+            return Err(VerificationError::NotPending(state));
+        }
+        user.lifecycle
+            .activate("email verified", self.clock.now())
+            .map_err(VerificationError::Lifecycle)?;
+        repository
+            .update(claims.user, user)
+            .map_err(VerificationError::Repository)?;
+        self.spend(&claims);
+        Ok(claims.user)
+    }
+
+    // Mails a reset token if the address belongs to an account that can sign in. Says nothing
+    // either way, so the form can't be used to find out who has an account.
+    pub fn send_password_reset<O: Outbox>(
+        &self,
+        email: &str,
+        repository: &UserRepository,
+        outbox: &mut O,
+    ) -> Result<(), VerificationError> {
+        let (id, user) = match repository.find_by_email(email) {
+            Some(found) => found,
+            None => return Ok(()),
+        };
+        if !user.lifecycle.is_active_at(self.clock.now()) {
+            return Ok(());
+        }
+        let token = self.issue(id, Purpose::ResetPassword)?;
+        outbox
+            .send(Email {
+                to: user.email.clone(),
+                subject: "Reset your password".to_string(),
+                body: format!(
+                    "Hi {},\n\nsomeone asked to reset your password. If it was you, use this code:\n\n{}\n",
+                    user.username, token
+                ),
+            })
+            .map_err(VerificationError::Send)
+    }
+
+    // Sets a new password. Callers should also end the user's sessions, see sessions.rs.
+    pub fn reset_password(
+        &mut self,
+        token: &str,
+        password: &str,
+        params: &PasswordParams,
+        repository: &mut UserRepository,
+    ) -> Result<UserId, VerificationError> {
+        let claims = self.check(token, Purpose::ResetPassword)?;
+        let mut user = repository
+            .get(claims.user)
+            .ok_or(VerificationError::Repository(RepositoryError::NotFound(
+                claims.user,
+            )))?
+            .clone();
+        // the account may have been suspended or deleted since the token was sent
+        if !user.lifecycle.is_active_at(self.clock.now()) {
+            return Err(VerificationError::NotActive(user.lifecycle.state()));
+        }
+        user.set_password(password, params)
+            .map_err(VerificationError::Password)?;
+        repository
+            .update(claims.user, user)
+            .map_err(VerificationError::Repository)?;
+        self.spend(&claims);
+        Ok(claims.user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::{Clock, ManualClock};
+    use crate::lifecycle::AccountState;
+    use crate::password::PasswordParams;
+    use crate::structs::build_user;
+    use crate::user_repository::UserRepository;
+    use crate::verification::{MemoryOutbox, Purpose, TokenPolicy, VerificationError, Verifier};
+    use std::time::Duration;
+
+    const KEY: &[u8] = b"not a secret, only for tests";
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    // the code is the last line of every email
+    fn code(outbox: &MemoryOutbox) -> String {
+        outbox
+            .sent
+            .last()
+            .unwrap()
+            .body
+            .lines()
+            .last()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_verify_then_activate() {
+        let clock = ManualClock::at_unix(1_000_000);
+        let mut verifier = Verifier::new(&clock, KEY, TokenPolicy::default());
+        let mut repository = UserRepository::new();
+        let mut outbox = MemoryOutbox::default();
+        let ray = build_user("ray@example.com".to_string(), "Ray".to_string());
+        assert_eq!(ray.lifecycle.state(), AccountState::PendingVerification);
+        let id = repository.create(ray).unwrap();
+        verifier
+            .send_verification(id, repository.get(id).unwrap(), &mut outbox)
+            .unwrap();
+        assert_eq!(outbox.sent.len(), 1);
+        assert_eq!(outbox.sent[0].to, "ray@example.com");
+        let token = code(&outbox);
+        clock.advance(HOUR);
+        assert_eq!(verifier.verify_email(&token, &mut repository).unwrap(), id);
+        let ray = repository.get(id).unwrap();
+        assert!(ray.is_active());
+        assert_eq!(ray.lifecycle.history()[0].reason, "email verified");
+        // single use
+        assert!(matches!(
+            verifier.verify_email(&token, &mut repository),
+            Err(VerificationError::AlreadyUsed)
+        ));
+    }
+
+    #[test]
+    fn test_verified_accounts_keep_their_token() {
+        let clock = ManualClock::at_unix(1_000_000);
+        let mut verifier = Verifier::new(&clock, KEY, TokenPolicy::default());
+        let mut repository = UserRepository::new();
+        let mut ray = build_user("ray@example.com".to_string(), "Ray".to_string());
+        ray.lifecycle.activate("imported", clock.now()).unwrap();
+        let id = repository.create(ray).unwrap();
+        let token = verifier.issue(id, Purpose::VerifyEmail).unwrap();
+        assert!(matches!(
+            verifier.verify_email(&token, &mut repository),
+            Err(VerificationError::NotPending(AccountState::Active))
+        ));
+        // nothing happened, so the token is still good
+        assert_eq!(verifier.redeem(&token, Purpose::VerifyEmail).unwrap(), id);
+    }
+
+    #[test]
+    fn test_tokens_expire() {
+        let clock = ManualClock::at_unix(1_000_000);
+        let mut verifier = Verifier::new(&clock, KEY, TokenPolicy::default());
+        let verify = verifier.issue(1, Purpose::VerifyEmail).unwrap();
+        let reset = verifier.issue(1, Purpose::ResetPassword).unwrap();
+        clock.advance(HOUR);
+        assert!(matches!(
+            verifier.redeem(&reset, Purpose::ResetPassword),
+            Err(VerificationError::Expired)
+        ));
+        clock.advance(46 * HOUR);
+        assert_eq!(verifier.redeem(&verify, Purpose::VerifyEmail).unwrap(), 1);
+        let late = verifier.issue(2, Purpose::VerifyEmail).unwrap();
+        clock.advance(48 * HOUR);
+        assert!(matches!(
+            verifier.redeem(&late, Purpose::VerifyEmail),
+            Err(VerificationError::Expired)
+        ));
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let clock = ManualClock::at_unix(1_000_000);
+        let mut verifier = Verifier::new(&clock, KEY, TokenPolicy::default());
+        let token = verifier.issue(1, Purpose::ResetPassword).unwrap();
+        // someone else's account
+        let forged = format!("2{}", &token[1..]);
+        assert!(matches!(
+            verifier.redeem(&forged, Purpose::ResetPassword),
+            Err(VerificationError::BadSignature)
+        ));
+        // a token for one purpose can't be used for another
+        assert!(matches!(
+            verifier.redeem(&token, Purpose::VerifyEmail),
+            Err(VerificationError::WrongPurpose(Purpose::ResetPassword))
+        ));
+        let mut other_key = Verifier::new(&clock, b"another key", TokenPolicy::default());
+        assert!(matches!(
+            other_key.redeem(&token, Purpose::ResetPassword),
+            Err(VerificationError::BadSignature)
+        ));
+        for junk in ["", "no dots", "a.b.c.d.!!!"].iter() {
+            assert!(matches!(
+                verifier.redeem(junk, Purpose::ResetPassword),
+                Err(VerificationError::Malformed)
+            ));
+        }
+        assert_eq!(verifier.redeem(&token, Purpose::ResetPassword).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_password_reset() {
+        let fast = PasswordParams {
+            iterations: 1_000,
+            ..PasswordParams::default()
+        };
+        let clock = ManualClock::at_unix(1_000_000);
+        let mut verifier = Verifier::new(&clock, KEY, TokenPolicy::default());
+        let mut repository = UserRepository::new();
+        let mut outbox = MemoryOutbox::default();
+        let mut ray = build_user("ray@example.com".to_string(), "Ray".to_string());
+        ray.set_password("forgotten", &fast).unwrap();
+        ray.lifecycle
+            .activate("email verified", clock.now())
+            .unwrap();
+        let id = repository.create(ray).unwrap();
+        // unknown addresses look the same from outside, but nothing is sent
+        verifier
+            .send_password_reset("nobody@example.com", &repository, &mut outbox)
+            .unwrap();
+        assert!(outbox.sent.is_empty());
+        verifier
+            .send_password_reset("RAY@example.com", &repository, &mut outbox)
+            .unwrap();
+        assert_eq!(outbox.sent[0].subject, "Reset your password");
+        let token = code(&outbox);
+        verifier
+            .reset_password(&token, "remembered", &fast, &mut repository)
+            .unwrap();
+        let ray = repository.get(id).unwrap();
+        assert!(ray.verify_password("remembered"));
+        assert!(!ray.verify_password("forgotten"));
+        assert!(matches!(
+            verifier.reset_password(&token, "again", &fast, &mut repository),
+            Err(VerificationError::AlreadyUsed)
+        ));
+    }
+
+    #[test]
+    fn test_no_reset_unless_the_account_can_sign_in() {
+        let fast = PasswordParams {
+            iterations: 1_000,
+            ..PasswordParams::default()
+        };
+        let clock = ManualClock::at_unix(1_000_000);
+        let mut verifier = Verifier::new(&clock, KEY, TokenPolicy::default());
+        let mut repository = UserRepository::new();
+        let mut outbox = MemoryOutbox::default();
+        let mut ray = build_user("ray@example.com".to_string(), "Ray".to_string());
+        ray.set_password("forgotten", &fast).unwrap();
+        ray.lifecycle
+            .activate("email verified", clock.now())
+            .unwrap();
+        let id = repository.create(ray).unwrap();
+        verifier
+            .send_password_reset("ray@example.com", &repository, &mut outbox)
+            .unwrap();
+        let token = code(&outbox);
+
+        // suspended after the token went out
+        let mut ray = repository.get(id).unwrap().clone();
+        let until = clock.now() + HOUR / 6;
+        ray.lifecycle
+            .suspend(Some(until), "abuse", clock.now())
+            .unwrap();
+        repository.update(id, ray).unwrap();
+        verifier
+            .send_password_reset("ray@example.com", &repository, &mut outbox)
+            .unwrap();
+        assert_eq!(outbox.sent.len(), 1);
+        assert!(matches!(
+            verifier.reset_password(&token, "remembered", &fast, &mut repository),
+            Err(VerificationError::NotActive(AccountState::Suspended { .. }))
+        ));
+        assert!(repository.get(id).unwrap().verify_password("forgotten"));
+
+        // once the suspension is over the token still works
+        clock.advance(HOUR / 6);
+        verifier
+            .reset_password(&token, "remembered", &fast, &mut repository)
+            .unwrap();
+
+        verifier
+            .send_password_reset("ray@example.com", &repository, &mut outbox)
+            .unwrap();
+        let token = code(&outbox);
+        let mut ray = repository.get(id).unwrap().clone();
+        ray.lifecycle.delete("asked to", clock.now()).unwrap();
+        repository.update(id, ray).unwrap();
+        verifier
+            .send_password_reset("ray@example.com", &repository, &mut outbox)
+            .unwrap();
+        assert_eq!(outbox.sent.len(), 2);
+        assert!(matches!(
+            verifier.reset_password(&token, "again", &fast, &mut repository),
+            Err(VerificationError::NotActive(AccountState::Deleted))
+        ));
+    }
+}
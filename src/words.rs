@@ -0,0 +1,304 @@
+// Word segmentation by the word boundary rules of UAX #29, Unicode Text Segmentation. These are
+// the default rules without a dictionary, so Chinese and Japanese ideographs and hiragana come
+// out one character at a time while katakana runs stay together. `segments` yields everything
+// between two boundaries, spaces and punctuation included, `words` only the segments with a
+// letter or digit in them. Everything borrows from the text it was given.
+use crate::unicode_tables::{WordBreak, EXTENDED_PICTOGRAPHIC, WORD_BREAK};
+use std::cmp::Ordering;
+
+fn find_range(ranges: &[(u32, u32)], cp: u32) -> bool {
+    ranges
+        .binary_search_by(|&(first, last)| {
+            if last < cp {
+                Ordering::Less
+            } else if first > cp {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+fn word_break(c: char) -> WordBreak {
+    let cp = c as u32;
+    WORD_BREAK
+        .binary_search_by(|&(first, last, _)| {
+            if last < cp {
+                Ordering::Less
+            } else if first > cp {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        })
+        .map(|i| WORD_BREAK[i].2)
+        .unwrap_or(WordBreak::Other)
+}
+
+fn is_extended_pictographic(c: char) -> bool {
+    find_range(EXTENDED_PICTOGRAPHIC, c as u32)
+}
+
+// WB4: these attach to whatever comes before them and are skipped by the rules after it
+fn is_ignored(p: WordBreak) -> bool {
+    matches!(p, WordBreak::Extend | WordBreak::Format | WordBreak::Zwj)
+}
+
+fn is_ahletter(p: WordBreak) -> bool {
+    matches!(p, WordBreak::ALetter | WordBreak::HebrewLetter)
+}
+
+fn is_mid_letter(p: WordBreak) -> bool {
+    matches!(
+        p,
+        WordBreak::MidLetter | WordBreak::MidNumLet | WordBreak::SingleQuote
+    )
+}
+
+fn is_mid_num(p: WordBreak) -> bool {
+    matches!(
+        p,
+        WordBreak::MidNum | WordBreak::MidNumLet | WordBreak::SingleQuote
+    )
+}
+
+// the first property in `rest` the rules look at, skipping what WB4 skips
+fn lookahead(rest: &str) -> Option<WordBreak> {
+    rest.chars().map(word_break).find(|&p| !is_ignored(p))
+}
+
+// WB5 to WB16, for `prev` and `next` with what WB4 skips left out. `before` is the property
+// before `prev` in the same segment, `regional` the number of regional indicators in a row
+// ending at `prev`.
+fn joins(
+    before: Option<WordBreak>,
+    prev: WordBreak,
+    next: WordBreak,
+    rest: &str,
+    regional: usize,
+) -> bool {
+    use crate::unicode_tables::WordBreak::*;
+    match (prev, next) {
+        (a, b) if is_ahletter(a) && is_ahletter(b) => true,
+        (HebrewLetter, SingleQuote) => true,
+        (a, b) if is_ahletter(a) && is_mid_letter(b) => lookahead(rest).is_some_and(is_ahletter),
+        (b, a) if is_mid_letter(b) && is_ahletter(a) => before.is_some_and(is_ahletter),
+        (HebrewLetter, DoubleQuote) => lookahead(rest) == Some(HebrewLetter),
+        (DoubleQuote, HebrewLetter) => before == Some(HebrewLetter),
+        (Numeric, Numeric) => true,
+        (a, Numeric) | (Numeric, a) if is_ahletter(a) => true,
+        (m, Numeric) if is_mid_num(m) => before == Some(Numeric),
+        (Numeric, m) if is_mid_num(m) => lookahead(rest) == Some(Numeric),
+        (Katakana, Katakana) => true,
+        (a, ExtendNumLet) if is_ahletter(a) || a == Numeric || a == Katakana => true,
+        (ExtendNumLet, ExtendNumLet) => true,
+        (ExtendNumLet, a) if is_ahletter(a) || a == Numeric || a == Katakana => true,
+        // flags are pairs of regional indicators
+        (RegionalIndicator, RegionalIndicator) => regional % 2 == 1,
+        _ => false,
+    }
+}
+
+// the byte offset of the next boundary after `start`, which has to be one
+fn next_boundary(text: &str, start: usize) -> usize {
+    use crate::unicode_tables::WordBreak::*;
+    let mut chars = text[start..].char_indices();
+    let first = match chars.next() {
+        Some((_, c)) => word_break(c),
+        None => return text.len(),
+    };
+    // the previous character, and the last one WB4 didn't skip
+    let mut raw = first;
+    let mut prev = first;
+    let mut before = None;
+    let mut regional = usize::from(first == RegionalIndicator);
+    for (i, c) in chars {
+        let offset = start + i;
+        let next = word_break(c);
+        let keep = match (raw, next) {
+            (CR, LF) => true,
+            (CR, _) | (LF, _) | (Newline, _) => false,
+            (_, CR) | (_, LF) | (_, Newline) => false,
+            (Zwj, _) if is_extended_pictographic(c) => true,
+            (WSegSpace, WSegSpace) => true,
+            (_, p) if is_ignored(p) => true,
+            _ => joins(before, prev, next, &text[offset + c.len_utf8()..], regional),
+        };
+        if !keep {
+            return offset;
+        }
+        raw = next;
+        if !is_ignored(next) {
+            before = Some(prev);
+            prev = next;
+            regional = if next == RegionalIndicator {
+                regional + 1
+            } else {
+                0
+            };
+        }
+    }
+    text.len()
+}
+
+// the text between each pair of word boundaries, in order; together they make up the text
+pub struct Segments<'a> {
+    text: &'a str,
+    start: usize,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.start >= self.text.len() {
+            return None;
+        }
+        let end = next_boundary(self.text, self.start);
+        let segment = &self.text[self.start..end];
+        self.start = end;
+        Some(segment)
+    }
+}
+
+pub fn segments(text: &str) -> Segments<'_> {
+    Segments { text, start: 0 }
+}
+
+// the segments that are words, leaving out spaces, punctuation and emoji
+pub struct Words<'a> {
+    segments: Segments<'a>,
+}
+
+impl<'a> Iterator for Words<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.segments
+            .find(|segment| segment.chars().any(char::is_alphanumeric))
+    }
+}
+
+pub fn words(text: &str) -> Words<'_> {
+    Words {
+        segments: segments(text),
+    }
+}
+
+// the first word, or an empty slice when there is none
+pub fn first_word(text: &str) -> &str {
+    words(text).next().unwrap_or(&text[..0])
+}
+
+// the word at `n`, counting from 0
+pub fn nth_word(text: &str, n: usize) -> Option<&str> {
+    words(text).nth(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::words::{first_word, nth_word, segments, words};
+
+    fn all_words(text: &str) -> Vec<&str> {
+        words(text).collect()
+    }
+
+    #[test]
+    fn test_first_word() {
+        assert_eq!(first_word("hello world"), "hello");
+        assert_eq!(first_word("hello\tworld"), "hello");
+        assert_eq!(first_word("hello\nworld"), "hello");
+        assert_eq!(first_word("hello\u{a0}world"), "hello");
+        assert_eq!(first_word("hello\u{3000}world"), "hello");
+        assert_eq!(first_word("hello, world"), "hello");
+        assert_eq!(first_word("  ...hello"), "hello");
+        assert_eq!(first_word("hello"), "hello");
+        assert_eq!(first_word(""), "");
+        assert_eq!(first_word(" !? "), "");
+        // a slice of the input, not a copy
+        let text = String::from("  hello world");
+        assert_eq!(first_word(&text).as_ptr(), text[2..].as_ptr());
+    }
+
+    #[test]
+    fn test_nth_word() {
+        let text = "The quick (\"brown\") fox can't jump 32.3 feet, right?";
+        assert_eq!(nth_word(text, 0), Some("The"));
+        assert_eq!(nth_word(text, 2), Some("brown"));
+        assert_eq!(nth_word(text, 4), Some("can't"));
+        assert_eq!(nth_word(text, 6), Some("32.3"));
+        assert_eq!(nth_word(text, 8), Some("right"));
+        assert_eq!(nth_word(text, 9), None);
+    }
+
+    #[test]
+    fn test_segments_cover_the_text() {
+        let text = "Hello, wörld!\r\n  e.g. 1,000.5 a_b \u{1f1e9}\u{1f1ea}\u{1f1eb}";
+        let parts: Vec<&str> = segments(text).collect();
+        assert_eq!(
+            parts,
+            vec![
+                "Hello",
+                ",",
+                " ",
+                "wörld",
+                "!",
+                "\r\n",
+                "  ",
+                "e.g",
+                ".",
+                " ",
+                "1,000.5",
+                " ",
+                "a_b",
+                " ",
+                "\u{1f1e9}\u{1f1ea}",
+                "\u{1f1eb}",
+            ]
+        );
+        assert_eq!(parts.concat(), text);
+        assert_eq!(segments("").count(), 0);
+    }
+
+    #[test]
+    fn test_letters_and_digits() {
+        assert_eq!(all_words("can't won’t"), vec!["can't", "won’t"]);
+        // a trailing apostrophe or point isn't part of the word
+        assert_eq!(all_words("dogs' 3."), vec!["dogs", "3"]);
+        assert_eq!(all_words("v1.2 H2O"), vec!["v1.2", "H2O"]);
+        assert_eq!(
+            all_words("snake_case kebab-case"),
+            vec!["snake_case", "kebab", "case"]
+        );
+        assert_eq!(all_words("user@example.com"), vec!["user", "example.com"]);
+        // combining marks and format characters stay with their letter
+        assert_eq!(
+            all_words("cafe\u{301} soft\u{ad}ware"),
+            vec!["cafe\u{301}", "soft\u{ad}ware"]
+        );
+        assert_eq!(all_words("שָׁלוֹם עוֹלָם"), vec!["שָׁלוֹם", "עוֹלָם"]);
+    }
+
+    #[test]
+    fn test_cjk() {
+        assert_eq!(all_words("你好世界"), vec!["你", "好", "世", "界"]);
+        assert_eq!(
+            all_words("これはテストです。"),
+            vec!["こ", "れ", "は", "テスト", "で", "す"]
+        );
+        assert_eq!(all_words("한국어 단어"), vec!["한국어", "단어"]);
+    }
+
+    #[test]
+    fn test_emoji() {
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        let text = format!("hi {} \u{1f44b}\u{1f3fd}!", family);
+        let parts: Vec<&str> = segments(&text).collect();
+        assert_eq!(
+            parts,
+            vec!["hi", " ", family, " ", "\u{1f44b}\u{1f3fd}", "!"]
+        );
+        assert_eq!(all_words(&text), vec!["hi"]);
+    }
+}